@@ -27,6 +27,19 @@ pub fn main() -> GameResult {
         // Tell the timer stuff a frame has happened.
         // Without this the FPS timer functions and such won't work.
         ctx.timer_context.tick();
+        // Forget which keys and buttons were pressed last frame, so
+        // `keyboard::is_key_just_pressed()` and friends work.
+        ggez::input::reset_transitions(ctx);
+        // Recompile shaders whose source files changed, if
+        // `graphics::watch_shaders()` has been called.
+        graphics::reload_changed_shaders(ctx);
+        // Likewise for assets loaded through `assets::Handle`.
+        let _ = ggez::assets::reload_changed_assets(ctx);
+        // And turn anything loaded in the background with
+        // `assets::load_batch()` into assets.
+        ggez::assets::update_loading(ctx);
+        // Move any fading audio buses along.
+        ggez::audio::update(ctx);
         // Handle events. Refer to `winit` docs for more information.
         events_loop.poll_events(|event| {
             // This tells `ggez` to update it's internal states, should the event require that.
@@ -54,6 +67,12 @@ pub fn main() -> GameResult {
                 x => println!("Device event fired: {:?}", x),
             }
         });
+        // Gamepad events come from the `gamepad_context` instead, and need
+        // telling `ggez` about too for `gamepad::button_just_pressed()` to work.
+        while let Some(event) = ctx.gamepad_context.next_event() {
+            ctx.process_gamepad_event(&event);
+            println!("Gamepad event fired: {:?}", event);
+        }
 
         // Update
         position += 1.0;
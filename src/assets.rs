@@ -0,0 +1,574 @@
+//! Reloadable handles to images, fonts and sounds, for changing
+//! assets while the game is running.
+//!
+//! Load an asset with [`Handle::new()`](struct.Handle.html#method.new)
+//! instead of its own constructor, and get the asset itself with
+//! [`Handle::get()`](struct.Handle.html#method.get) whenever you use it.
+//! Once a frame [`event::run()`](../event/fn.run.html) checks whether the
+//! file of any asset that still has a handle has changed, and if it has,
+//! loads it again and calls
+//! [`EventHandler::asset_reloaded_event()`](../event/trait.EventHandler.html#method.asset_reloaded_event).
+//! Everything that gets the asset from a handle after that gets the
+//! new version.
+//!
+//! ```rust,no_run
+//! # use ggez::*;
+//! # use ggez::assets::Handle;
+//! # fn t(ctx: &mut Context) -> GameResult {
+//! let image: Handle<graphics::Image> = Handle::new(ctx, "/player.png")?;
+//! graphics::draw(ctx, &image.get(), graphics::DrawParam::new())?;
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! Only files on the physical filesystem, such as the `resources/`
+//! directory or paths added with
+//! [`ContextBuilder::add_resource_path()`](../struct.ContextBuilder.html#method.add_resource_path),
+//! are checked; files in `resources.zip` never change.  If an asset
+//! fails to reload, say because its file was only half saved, the old
+//! one is kept, a warning is logged and it is tried again the next
+//! time the file changes.
+//!
+//! This module can also load assets in the background, for loading
+//! screens that don't freeze: collect them in a
+//! [`LoadBatch`](struct.LoadBatch.html) and start it with
+//! [`load_batch()`](fn.load_batch.html).  Files are read and images
+//! decoded on worker threads, and once a frame `event::run()` uploads
+//! whatever has finished to the GPU, since that can only happen on the
+//! main thread.  Check on it with [`batch_progress()`](fn.batch_progress.html)
+//! and get the assets with [`take_batch()`](fn.take_batch.html) when
+//! it's done.
+//!
+//! ```rust,no_run
+//! # use ggez::*;
+//! # use ggez::assets::{self, LoadBatch};
+//! # fn t(ctx: &mut Context) -> GameResult {
+//! let batch = LoadBatch::new()
+//!     .image("/player.png")
+//!     .sound("/music.ogg")
+//!     .font("/DejaVuSerif.ttf");
+//! let id = assets::load_batch(ctx, batch);
+//! // ...then each frame, in update():
+//! if let Some(assets) = assets::take_batch(ctx, id) {
+//!     let player = assets.image("/player.png");
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::fmt;
+use std::fs;
+use std::io::Read;
+use std::mem;
+use std::path::{Path, PathBuf};
+use std::rc::{Rc, Weak};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::SystemTime;
+
+use image;
+
+use crate::audio::SoundData;
+use crate::graphics::{Font, Image};
+use crate::{Context, GameError, GameResult};
+
+/// Something that can be loaded from a file and reloaded
+/// through a [`Handle`](struct.Handle.html).  Implement it for your own
+/// types to make them reloadable too.
+pub trait Asset: Clone + 'static {
+    /// Loads the asset from the file at the given path.
+    fn load(ctx: &mut Context, path: &Path) -> GameResult<Self>;
+}
+
+impl Asset for Image {
+    fn load(ctx: &mut Context, path: &Path) -> GameResult<Self> {
+        Image::new(ctx, path)
+    }
+}
+
+impl Asset for Font {
+    fn load(ctx: &mut Context, path: &Path) -> GameResult<Self> {
+        Font::new(ctx, path)
+    }
+}
+
+impl Asset for SoundData {
+    fn load(ctx: &mut Context, path: &Path) -> GameResult<Self> {
+        SoundData::new(ctx, path)
+    }
+}
+
+/// A handle to an asset loaded from a file, which is reloaded when
+/// the file changes.  Clones of a handle share the same asset.
+#[derive(Debug, Clone)]
+pub struct Handle<T: Asset> {
+    asset: Rc<RefCell<T>>,
+    path: PathBuf,
+}
+
+impl<T: Asset> Handle<T> {
+    /// Loads the asset from the file at the given path, and starts
+    /// watching the file for changes.
+    pub fn new<P: AsRef<Path>>(ctx: &mut Context, path: P) -> GameResult<Self> {
+        let path = path.as_ref();
+        let asset = Rc::new(RefCell::new(T::load(ctx, path)?));
+        let weak: Weak<RefCell<T>> = Rc::downgrade(&asset);
+        ctx.asset_watcher.assets.push(WatchedAsset {
+            path: path.to_path_buf(),
+            stamp: ctx.filesystem.stamp(path),
+            asset: Box::new(weak),
+        });
+        Ok(Handle {
+            asset,
+            path: path.to_path_buf(),
+        })
+    }
+
+    /// Gets the current version of the asset.  Assets are cheap to
+    /// clone, so get it again each time you use it rather than
+    /// holding on to it, or you won't see it change.
+    pub fn get(&self) -> T {
+        self.asset.borrow().clone()
+    }
+
+    /// Gets the path the asset was loaded from.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+/// An asset behind a `Handle`, without its type.
+trait Reload {
+    /// Returns whether any handles to the asset are left.
+    fn is_alive(&self) -> bool;
+
+    /// Loads the asset again from the given path.
+    fn reload(&self, ctx: &mut Context, path: &Path) -> GameResult;
+}
+
+impl<T: Asset> Reload for Weak<RefCell<T>> {
+    fn is_alive(&self) -> bool {
+        self.upgrade().is_some()
+    }
+
+    fn reload(&self, ctx: &mut Context, path: &Path) -> GameResult {
+        let new_asset = T::load(ctx, path)?;
+        if let Some(asset) = self.upgrade() {
+            *asset.borrow_mut() = new_asset;
+        }
+        Ok(())
+    }
+}
+
+struct WatchedAsset {
+    path: PathBuf,
+    /// When the file was last modified and how long it was.
+    stamp: Option<(SystemTime, u64)>,
+    asset: Box<dyn Reload>,
+}
+
+/// Keeps track of the assets behind every `Handle`, and when
+/// their files were last modified.
+#[derive(Default)]
+pub(crate) struct AssetWatcher {
+    assets: Vec<WatchedAsset>,
+}
+
+impl fmt::Debug for AssetWatcher {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        write!(formatter, "<AssetWatcher: {} assets>", self.assets.len())
+    }
+}
+
+/// Reloads every asset with a [`Handle`](struct.Handle.html) whose file has
+/// changed since it was last loaded, and returns the paths of the ones
+/// that were.  [`event::run()`](../event/fn.run.html) calls this every frame
+/// and passes each path to
+/// [`EventHandler::asset_reloaded_event()`](../event/trait.EventHandler.html#method.asset_reloaded_event),
+/// so you only need it if you have your own main loop.
+pub fn reload_changed_assets(ctx: &mut Context) -> Vec<PathBuf> {
+    // Loading assets needs the whole context, so take them out of it
+    // while we go.
+    let mut assets = mem::replace(&mut ctx.asset_watcher.assets, vec![]);
+    assets.retain(|watched| watched.asset.is_alive());
+    let mut reloaded = vec![];
+    for watched in &mut assets {
+        let stamp = ctx.filesystem.stamp(&watched.path);
+        // Files that vanished, such as halfway through being saved,
+        // are left alone until they come back.
+        if stamp.is_none() || stamp == watched.stamp {
+            continue;
+        }
+        watched.stamp = stamp;
+        match watched.asset.reload(ctx, &watched.path) {
+            Ok(()) => {
+                if !reloaded.contains(&watched.path) {
+                    reloaded.push(watched.path.clone());
+                }
+            }
+            Err(e) => warn!("Could not reload asset {:?}: {}", watched.path, e),
+        }
+    }
+    // Anything that made new handles while loading gets to keep them.
+    assets.append(&mut ctx.asset_watcher.assets);
+    ctx.asset_watcher.assets = assets;
+    reloaded
+}
+
+/// How many worker threads background loading uses.
+const WORKER_COUNT: usize = 4;
+
+/// An identifier for a [`LoadBatch`](struct.LoadBatch.html) that is
+/// being loaded, returned by [`load_batch()`](fn.load_batch.html).
+pub type BatchId = usize;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum AssetKind {
+    Image,
+    Font,
+    Sound,
+}
+
+/// A set of assets to load in the background with
+/// [`load_batch()`](fn.load_batch.html).
+#[derive(Debug, Clone, Default)]
+pub struct LoadBatch {
+    assets: Vec<(PathBuf, AssetKind)>,
+}
+
+impl LoadBatch {
+    /// Creates an empty batch.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds an `Image` to load from the given path.
+    pub fn image<P: AsRef<Path>>(mut self, path: P) -> Self {
+        self.assets
+            .push((path.as_ref().to_path_buf(), AssetKind::Image));
+        self
+    }
+
+    /// Adds a `Font` to load from the given path.
+    pub fn font<P: AsRef<Path>>(mut self, path: P) -> Self {
+        self.assets
+            .push((path.as_ref().to_path_buf(), AssetKind::Font));
+        self
+    }
+
+    /// Adds a `SoundData` to load from the given path.
+    pub fn sound<P: AsRef<Path>>(mut self, path: P) -> Self {
+        self.assets
+            .push((path.as_ref().to_path_buf(), AssetKind::Sound));
+        self
+    }
+}
+
+/// How far along loading a batch is.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Progress {
+    /// How many assets have been loaded.
+    pub loaded: usize,
+    /// How many assets couldn't be loaded.
+    pub failed: usize,
+    /// How many assets are in the batch.
+    pub total: usize,
+}
+
+impl Progress {
+    /// Returns how much of the batch is finished, one way or another,
+    /// from 0.0 to 1.0, for drawing a progress bar.
+    pub fn fraction(&self) -> f32 {
+        if self.total == 0 {
+            1.0
+        } else {
+            (self.loaded + self.failed) as f32 / self.total as f32
+        }
+    }
+
+    /// Returns whether every asset in the batch is finished.
+    pub fn is_done(&self) -> bool {
+        self.loaded + self.failed == self.total
+    }
+}
+
+/// The assets from a finished batch, as returned by
+/// [`take_batch()`](fn.take_batch.html), looked up by the paths
+/// they were added to the batch with.
+#[derive(Debug, Default)]
+pub struct LoadedAssets {
+    images: HashMap<PathBuf, Image>,
+    fonts: HashMap<PathBuf, Font>,
+    sounds: HashMap<PathBuf, SoundData>,
+    errors: Vec<(PathBuf, GameError)>,
+}
+
+impl LoadedAssets {
+    /// Gets the `Image` loaded from the given path, if it loaded.
+    pub fn image<P: AsRef<Path>>(&self, path: P) -> Option<Image> {
+        self.images.get(path.as_ref()).cloned()
+    }
+
+    /// Gets the `Font` loaded from the given path, if it loaded.
+    pub fn font<P: AsRef<Path>>(&self, path: P) -> Option<Font> {
+        self.fonts.get(path.as_ref()).cloned()
+    }
+
+    /// Gets the `SoundData` loaded from the given path, if it loaded.
+    pub fn sound<P: AsRef<Path>>(&self, path: P) -> Option<SoundData> {
+        self.sounds.get(path.as_ref()).cloned()
+    }
+
+    /// Gets the paths of the assets that couldn't be loaded,
+    /// and why not.
+    pub fn errors(&self) -> &[(PathBuf, GameError)] {
+        &self.errors
+    }
+}
+
+/// Where a worker gets an asset's bytes from.
+enum Source {
+    /// A file on disk, which the worker reads itself.
+    File(PathBuf),
+    /// Bytes already read on the main thread, for files that
+    /// can only be read through the `Filesystem`, such as
+    /// ones in `resources.zip`.
+    Bytes(Vec<u8>),
+}
+
+struct Job {
+    batch: BatchId,
+    path: PathBuf,
+    kind: AssetKind,
+    source: Source,
+}
+
+/// An asset that has been read and decoded as far as it can be
+/// off the main thread.
+enum Decoded {
+    Image(u16, u16, Vec<u8>),
+    Font(Vec<u8>),
+    Sound(Vec<u8>),
+}
+
+/// An asset that is ready to use.
+enum Loaded {
+    Image(Image),
+    Font(Font),
+    Sound(SoundData),
+}
+
+struct Finished {
+    batch: BatchId,
+    path: PathBuf,
+    result: GameResult<Decoded>,
+}
+
+struct Workers {
+    jobs: Sender<Job>,
+    finished: Receiver<Finished>,
+}
+
+impl Workers {
+    fn new() -> Self {
+        let (jobs, job_receiver) = mpsc::channel::<Job>();
+        let (finished_sender, finished) = mpsc::channel();
+        let job_receiver = Arc::new(Mutex::new(job_receiver));
+        for _ in 0..WORKER_COUNT {
+            let job_receiver = job_receiver.clone();
+            let finished_sender = finished_sender.clone();
+            let _ = thread::spawn(move || loop {
+                // The workers all stop once the `Sender` is dropped
+                // along with the `Context`.
+                let job = job_receiver
+                    .lock()
+                    .expect("Asset worker panicked; should never happen!")
+                    .recv();
+                let job = match job {
+                    Ok(job) => job,
+                    Err(_) => return,
+                };
+                let finished = Finished {
+                    batch: job.batch,
+                    path: job.path,
+                    result: decode(job.kind, job.source),
+                };
+                if finished_sender.send(finished).is_err() {
+                    return;
+                }
+            });
+        }
+        Workers { jobs, finished }
+    }
+}
+
+fn decode(kind: AssetKind, source: Source) -> GameResult<Decoded> {
+    let bytes = match source {
+        Source::File(path) => {
+            let mut buf = Vec::new();
+            let _ = fs::File::open(path)?.read_to_end(&mut buf)?;
+            buf
+        }
+        Source::Bytes(bytes) => bytes,
+    };
+    match kind {
+        AssetKind::Image => {
+            let img = image::load_from_memory(&bytes)?.to_rgba();
+            let (width, height) = img.dimensions();
+            if width > u32::from(u16::max_value()) || height > u32::from(u16::max_value()) {
+                let msg = format!("Image of size {}x{} is too big", width, height);
+                return Err(GameError::ResourceLoadError(msg));
+            }
+            Ok(Decoded::Image(width as u16, height as u16, img.into_raw()))
+        }
+        AssetKind::Font => Ok(Decoded::Font(bytes)),
+        AssetKind::Sound => Ok(Decoded::Sound(bytes)),
+    }
+}
+
+#[derive(Debug)]
+struct BatchState {
+    progress: Progress,
+    assets: LoadedAssets,
+}
+
+impl BatchState {
+    fn finish(&mut self, path: PathBuf, result: GameResult<Loaded>) {
+        let assets = &mut self.assets;
+        match result {
+            Ok(Loaded::Image(image)) => {
+                let _ = assets.images.insert(path, image);
+            }
+            Ok(Loaded::Font(font)) => {
+                let _ = assets.fonts.insert(path, font);
+            }
+            Ok(Loaded::Sound(sound)) => {
+                let _ = assets.sounds.insert(path, sound);
+            }
+            Err(e) => {
+                assets.errors.push((path, e));
+                self.progress.failed += 1;
+                return;
+            }
+        }
+        self.progress.loaded += 1;
+    }
+}
+
+/// Keeps track of batches being loaded in the background, and the
+/// worker threads loading them, which are only started once they're
+/// first needed.
+#[derive(Default)]
+pub(crate) struct AssetLoader {
+    workers: Option<Workers>,
+    batches: HashMap<BatchId, BatchState>,
+    next_batch: BatchId,
+}
+
+impl fmt::Debug for AssetLoader {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        write!(formatter, "<AssetLoader: {} batches>", self.batches.len())
+    }
+}
+
+/// Starts loading the assets in a batch in the background, and returns
+/// its ID for checking on it with [`batch_progress()`](fn.batch_progress.html)
+/// and getting the assets with [`take_batch()`](fn.take_batch.html).
+///
+/// Files in `resources.zip` are read before this returns, since only
+/// the main thread can read them; everything else happens on worker
+/// threads, apart from uploading images to the GPU, which
+/// [`update_loading()`](fn.update_loading.html) does.
+pub fn load_batch(ctx: &mut Context, batch: LoadBatch) -> BatchId {
+    let loader = &mut ctx.asset_loader;
+    let id = loader.next_batch;
+    loader.next_batch += 1;
+    let mut state = BatchState {
+        progress: Progress {
+            loaded: 0,
+            failed: 0,
+            total: batch.assets.len(),
+        },
+        assets: LoadedAssets::default(),
+    };
+    let workers = loader.workers.get_or_insert_with(Workers::new);
+    for (path, kind) in batch.assets {
+        let source = match ctx.filesystem.physical_path(&path) {
+            Some(file) => Ok(Source::File(file)),
+            None => ctx.filesystem.open(&path).and_then(|mut file| {
+                let mut buf = Vec::new();
+                let _ = file.read_to_end(&mut buf)?;
+                Ok(Source::Bytes(buf))
+            }),
+        };
+        match source {
+            Ok(source) => {
+                let job = Job {
+                    batch: id,
+                    path,
+                    kind,
+                    source,
+                };
+                workers
+                    .jobs
+                    .send(job)
+                    .expect("Asset workers stopped; should never happen!");
+            }
+            Err(e) => state.finish(path, Err(e)),
+        }
+    }
+    let _ = loader.batches.insert(id, state);
+    id
+}
+
+/// Returns how far along loading the given batch is, or `None` if there
+/// is no such batch, or it has already been taken with
+/// [`take_batch()`](fn.take_batch.html).
+pub fn batch_progress(ctx: &Context, batch: BatchId) -> Option<Progress> {
+    ctx.asset_loader
+        .batches
+        .get(&batch)
+        .map(|state| state.progress)
+}
+
+/// Returns the assets of the given batch once all of them are finished
+/// loading, one way or another, and forgets about the batch.  Returns
+/// `None` if it isn't done yet, or if there is no such batch.
+pub fn take_batch(ctx: &mut Context, batch: BatchId) -> Option<LoadedAssets> {
+    let batches = &mut ctx.asset_loader.batches;
+    if batches.get(&batch)?.progress.is_done() {
+        batches.remove(&batch).map(|state| state.assets)
+    } else {
+        None
+    }
+}
+
+/// Turns everything the worker threads have finished loading into
+/// assets, uploading images to the GPU.  [`event::run()`](../event/fn.run.html)
+/// calls this every frame, so you only need it if you have your own
+/// main loop.
+pub fn update_loading(ctx: &mut Context) {
+    loop {
+        let finished = match ctx.asset_loader.workers {
+            Some(ref workers) => match workers.finished.try_recv() {
+                Ok(finished) => finished,
+                Err(_) => return,
+            },
+            None => return,
+        };
+        let loaded = finished.result.and_then(|decoded| match decoded {
+            Decoded::Image(width, height, rgba) => {
+                Image::from_rgba8(ctx, width, height, &rgba).map(Loaded::Image)
+            }
+            Decoded::Font(bytes) => Font::new_glyph_font_bytes(ctx, &bytes).map(Loaded::Font),
+            Decoded::Sound(bytes) => Ok(Loaded::Sound(SoundData::from(bytes))),
+        });
+        // Batches can't be taken until they're done, so it's still there.
+        if let Some(state) = ctx.asset_loader.batches.get_mut(&finished.batch) {
+            state.finish(finished.path, loaded);
+        }
+    }
+}
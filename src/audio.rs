@@ -3,34 +3,107 @@
 //! It consists of two main types: [`SoundData`](struct.SoundData.html)
 //! is just an array of raw sound data bytes, and a [`Source`](struct.Source.html) is a
 //! `SoundData` connected to a particular sound channel ready to be played.
+//!
+//! Long sounds such as music are better played with a
+//! [`StreamingSource`](struct.StreamingSource.html), which reads its file
+//! bit by bit as it plays instead of loading all of it first.
+//!
+//! Sources play into the buses of the [`Mixer`](struct.Mixer.html), which
+//! turn whole groups of sounds up or down, mute or pause them at once.
 
+use std::cell::Cell;
+use std::collections::HashMap;
 use std::fmt;
 use std::io;
 use std::io::Read;
 use std::mem;
 use std::path;
+use std::thread;
 use std::time;
 
-use std::sync::atomic::{AtomicUsize, Ordering};
-use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{mpsc, Arc, Mutex, MutexGuard};
 
 use mint;
 use rodio;
+use rodio::queue;
+use rodio::source::UniformSourceIterator;
 
 use crate::context::Context;
 use crate::error::GameError;
 use crate::error::GameResult;
 use crate::filesystem;
+use crate::timer;
+use crate::vfs;
 
 /// A trait object defining an audio context, allowing us to someday
 /// use something other than `rodio` if we really want.
 ///
 /// End-users usually don't need to mess with this, but it's there
 /// if you want to bypass `ggez`'s sound functionality and write your
-/// own.
+/// own, or to swap in an [`OfflineAudioContext`](struct.OfflineAudioContext.html).
 pub trait AudioContext {
-    /// Returns the audio device.
-    fn device(&self) -> &rodio::Device;
+    /// Returns where sources send their sound.
+    fn output(&self) -> AudioOutput;
+
+    /// Returns the mixer that sources play into.
+    fn mixer(&self) -> &Mixer;
+
+    /// Returns the mixer that sources play into, mutably.
+    fn mixer_mut(&mut self) -> &mut Mixer;
+
+    /// Moves playback forward by the given time.  Only contexts that
+    /// play sound themselves instead of through a device, like an
+    /// `OfflineAudioContext`, need to do anything here.
+    fn advance(&mut self, _dt: time::Duration) {}
+}
+
+#[derive(Clone)]
+enum OutputKind {
+    Device,
+    Offline(Arc<Mutex<OfflineMix>>),
+}
+
+/// Where the sound of sources ends up: the default output device, or
+/// the mix of an [`OfflineAudioContext`](struct.OfflineAudioContext.html).
+/// Sources keep one of these to make a new sink when they are stopped.
+#[derive(Clone)]
+pub struct AudioOutput(OutputKind);
+
+impl AudioOutput {
+    /// Plays sound on the default output device.
+    pub fn default_device() -> Self {
+        AudioOutput(OutputKind::Device)
+    }
+
+    /// Creates a new sink that plays into this output.
+    pub fn new_sink(&self) -> rodio::Sink {
+        match self.0 {
+            // This uses the default device rather than the one the
+            // `RodioAudioContext` was created with, which is fine as long
+            // as that's the default device too.
+            OutputKind::Device => {
+                let device = rodio::default_output_device().unwrap();
+                rodio::Sink::new(&device)
+            }
+            OutputKind::Offline(ref mix) => {
+                let (sink, output) = rodio::Sink::new_idle();
+                mix.lock()
+                    .expect("OfflineMix lock poisoned")
+                    .add_output(output);
+                sink
+            }
+        }
+    }
+}
+
+impl fmt::Debug for AudioOutput {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.0 {
+            OutputKind::Device => write!(f, "<AudioOutput: default device>"),
+            OutputKind::Offline(_) => write!(f, "<AudioOutput: offline>"),
+        }
+    }
 }
 
 /// A struct that contains all information for tracking sound info.
@@ -38,24 +111,36 @@ pub trait AudioContext {
 /// You generally don't have to create this yourself, it will be part
 /// of your `Context` object.
 pub(crate) struct RodioAudioContext {
-    device: rodio::Device,
+    mixer: Mixer,
 }
 
 impl RodioAudioContext {
     /// Create new `RodioAudioContext`.
     pub fn new() -> GameResult<Self> {
-        let device = rodio::default_output_device().ok_or_else(|| {
+        // Sinks are made on the default device by the `AudioOutput`,
+        // so this only checks that there is one.
+        let _ = rodio::default_output_device().ok_or_else(|| {
             GameError::AudioError(String::from(
                 "Could not initialize sound system using default output device (for some reason)",
             ))
         })?;
-        Ok(Self { device })
+        Ok(Self {
+            mixer: Mixer::new(),
+        })
     }
 }
 
 impl AudioContext for RodioAudioContext {
-    fn device(&self) -> &rodio::Device {
-        &self.device
+    fn output(&self) -> AudioOutput {
+        AudioOutput::default_device()
+    }
+
+    fn mixer(&self) -> &Mixer {
+        &self.mixer
+    }
+
+    fn mixer_mut(&mut self) -> &mut Mixer {
+        &mut self.mixer
     }
 }
 
@@ -65,16 +150,542 @@ impl fmt::Debug for RodioAudioContext {
     }
 }
 
-/// A structure that implements `AudioContext` but does nothing; serves as a
-/// stub for when you don't need audio.  Will panic if you try to actually
-/// play sound from it.
-#[derive(Debug, Clone, Copy, Default)]
-pub(crate) struct NullAudioContext;
+/// A structure that implements `AudioContext` without a device; serves as
+/// a stub for when you don't need audio.  Sounds play silently as the game
+/// clock advances, and nothing is kept of them.
+#[derive(Debug)]
+pub(crate) struct NullAudioContext(OfflineAudioContext);
+
+impl Default for NullAudioContext {
+    fn default() -> Self {
+        let mix = OfflineMix::new(2, 44100, false);
+        NullAudioContext(OfflineAudioContext::with_mix(mix))
+    }
+}
 
 impl AudioContext for NullAudioContext {
-    fn device(&self) -> &rodio::Device {
-        panic!("Audio module disabled")
+    fn output(&self) -> AudioOutput {
+        self.0.output()
+    }
+
+    fn mixer(&self) -> &Mixer {
+        self.0.mixer()
+    }
+
+    fn mixer_mut(&mut self) -> &mut Mixer {
+        self.0.mixer_mut()
+    }
+
+    fn advance(&mut self, dt: time::Duration) {
+        self.0.advance(dt)
+    }
+}
+
+/// The sinks of an `OfflineAudioContext`, which it mixes itself.
+struct OfflineMix {
+    channels: u16,
+    sample_rate: u32,
+    outputs: Vec<UniformSourceIterator<queue::SourcesQueueOutput<f32>, f32>>,
+    /// How much has been mixed, counting the leftover time that
+    /// didn't add up to a whole frame.
+    time: time::Duration,
+    frames: u64,
+    samples: Option<Vec<f32>>,
+}
+
+impl OfflineMix {
+    fn new(channels: u16, sample_rate: u32, record: bool) -> Self {
+        OfflineMix {
+            channels,
+            sample_rate,
+            outputs: Vec::new(),
+            time: time::Duration::from_secs(0),
+            frames: 0,
+            samples: if record { Some(Vec::new()) } else { None },
+        }
+    }
+
+    fn add_output(&mut self, output: queue::SourcesQueueOutput<f32>) {
+        let output = UniformSourceIterator::new(output, self.channels, self.sample_rate);
+        self.outputs.push(output);
+    }
+
+    fn advance(&mut self, dt: time::Duration) {
+        self.time += dt;
+        let frames = duration_to_frames(self.time, self.sample_rate);
+        let samples = (frames - self.frames) * u64::from(self.channels);
+        self.frames = frames;
+        for _ in 0..samples {
+            let mut sum = 0.0;
+            let mut i = 0;
+            while i < self.outputs.len() {
+                match self.outputs[i].next() {
+                    Some(sample) => {
+                        sum += sample;
+                        i += 1;
+                    }
+                    // A sink that has been dropped and played out.
+                    None => {
+                        let _ = self.outputs.swap_remove(i);
+                    }
+                }
+            }
+            if let Some(ref mut recorded) = self.samples {
+                recorded.push(sum);
+            }
+        }
+    }
+}
+
+impl fmt::Debug for OfflineMix {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "<OfflineMix: {:p}>", self)
+    }
+}
+
+/// An `AudioContext` with no device, which mixes what is played into a
+/// [`Recording`](struct.Recording.html) in memory as the game clock moves
+/// on.  Sources play, pause and stop just like they would through a
+/// sound card, so code that plays sound can run and be tested without
+/// one, and tests can look at what it sounded like.
+///
+/// Swap it in for the `Context`'s audio context before creating any
+/// sources; each frame, [`audio::update()`](fn.update.html) mixes as much
+/// sound as the frame took.
+///
+/// ```rust,no_run
+/// # use ggez::*;
+/// # use ggez::audio::*;
+/// # fn t(ctx: &mut Context) -> GameResult {
+/// let offline = OfflineAudioContext::new(2, 44100);
+/// let recording = offline.recording();
+/// ctx.audio_context = Box::new(offline);
+///
+/// let mut sound = Source::new(ctx, "/pew.ogg")?;
+/// sound.play()?;
+/// ctx.audio_context.advance(std::time::Duration::from_secs(1));
+/// assert!(sound.stopped());
+/// recording.write_wav(&mut filesystem::create(ctx, "/pew.wav")?)?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct OfflineAudioContext {
+    mix: Arc<Mutex<OfflineMix>>,
+    mixer: Mixer,
+}
+
+impl OfflineAudioContext {
+    /// Creates an `OfflineAudioContext` that mixes sound with the given
+    /// number of channels and sample rate.
+    pub fn new(channels: u16, sample_rate: u32) -> Self {
+        Self::with_mix(OfflineMix::new(channels, sample_rate, true))
+    }
+
+    fn with_mix(mix: OfflineMix) -> Self {
+        OfflineAudioContext {
+            mix: Arc::new(Mutex::new(mix)),
+            mixer: Mixer::new(),
+        }
+    }
+
+    /// Returns a handle to the sound mixed so far, which can be kept
+    /// after the context is given to the `Context`.
+    pub fn recording(&self) -> Recording {
+        Recording(self.mix.clone())
+    }
+}
+
+impl AudioContext for OfflineAudioContext {
+    fn output(&self) -> AudioOutput {
+        AudioOutput(OutputKind::Offline(self.mix.clone()))
+    }
+
+    fn mixer(&self) -> &Mixer {
+        &self.mixer
+    }
+
+    fn mixer_mut(&mut self) -> &mut Mixer {
+        &mut self.mixer
+    }
+
+    fn advance(&mut self, dt: time::Duration) {
+        self.mix
+            .lock()
+            .expect("OfflineMix lock poisoned")
+            .advance(dt)
+    }
+}
+
+/// The sound an [`OfflineAudioContext`](struct.OfflineAudioContext.html)
+/// has mixed.  Clones share the same recording.
+#[derive(Debug, Clone)]
+pub struct Recording(Arc<Mutex<OfflineMix>>);
+
+impl Recording {
+    fn with_mix<T>(&self, f: impl FnOnce(&mut OfflineMix) -> T) -> T {
+        let mut mix = self.0.lock().expect("OfflineMix lock poisoned");
+        f(&mut mix)
+    }
+
+    /// Gets the number of channels.
+    pub fn channels(&self) -> u16 {
+        self.with_mix(|mix| mix.channels)
+    }
+
+    /// Gets the sample rate.
+    pub fn sample_rate(&self) -> u32 {
+        self.with_mix(|mix| mix.sample_rate)
+    }
+
+    /// Returns the samples mixed so far, with the channels interleaved.
+    pub fn samples(&self) -> Vec<f32> {
+        self.with_mix(|mix| mix.samples.clone().unwrap_or_default())
+    }
+
+    /// Returns how long the recording is.
+    pub fn duration(&self) -> time::Duration {
+        self.with_mix(|mix| {
+            let frames = mix.samples.as_ref().map_or(0, Vec::len) as u64 / u64::from(mix.channels);
+            frames_to_duration(frames, mix.sample_rate)
+        })
+    }
+
+    /// Throws away everything mixed so far, while carrying on mixing.
+    pub fn clear(&self) {
+        self.with_mix(|mix| {
+            if let Some(ref mut samples) = mix.samples {
+                samples.clear();
+            }
+        })
+    }
+
+    /// Writes the recording as a 16-bit PCM WAV file.
+    pub fn write_wav<W: io::Write>(&self, writer: &mut W) -> GameResult {
+        let (channels, sample_rate, samples) =
+            self.with_mix(|mix| (mix.channels, mix.sample_rate, mix.samples.clone()));
+        let samples = samples.unwrap_or_default();
+        let data_len = samples.len() as u32 * 2;
+        let block_align = channels * 2;
+
+        writer.write_all(b"RIFF")?;
+        writer.write_all(&(36 + data_len).to_le_bytes())?;
+        writer.write_all(b"WAVEfmt ")?;
+        writer.write_all(&16u32.to_le_bytes())?;
+        writer.write_all(&1u16.to_le_bytes())?;
+        writer.write_all(&channels.to_le_bytes())?;
+        writer.write_all(&sample_rate.to_le_bytes())?;
+        writer.write_all(&(sample_rate * u32::from(block_align)).to_le_bytes())?;
+        writer.write_all(&block_align.to_le_bytes())?;
+        writer.write_all(&16u16.to_le_bytes())?;
+        writer.write_all(b"data")?;
+        writer.write_all(&data_len.to_le_bytes())?;
+        for sample in samples {
+            let sample = (sample.max(-1.0).min(1.0) * f32::from(i16::max_value())) as i16;
+            writer.write_all(&sample.to_le_bytes())?;
+        }
+        Ok(())
+    }
+}
+
+/// The name of the bus that all the other buses play into.
+pub const MASTER_BUS: &str = "master";
+/// The name of the bus for music.
+pub const MUSIC_BUS: &str = "music";
+/// The name of the bus for sound effects.
+pub const SFX_BUS: &str = "sfx";
+/// The name of the bus for voices and dialogue.
+pub const VOICE_BUS: &str = "voice";
+
+/// A bus's or source's volume changing over time.
+#[derive(Debug, Copy, Clone, PartialEq)]
+struct Fade {
+    from: f32,
+    to: f32,
+    duration: time::Duration,
+    elapsed: time::Duration,
+}
+
+#[derive(Debug)]
+struct BusState {
+    // These are read for every sample on the audio thread, so they are
+    // atomics instead of being behind the lock.  The volume is the bits
+    // of an `f32`, since `AtomicU32` needs a newer Rust than we support.
+    volume: AtomicUsize,
+    muted: AtomicBool,
+    paused: AtomicBool,
+    fade: Mutex<Option<Fade>>,
+    parent: Option<Bus>,
+}
+
+/// One of the channels of the [`Mixer`](struct.Mixer.html), which sets
+/// the volume of all the sources playing into it and can mute or pause
+/// them all at once.
+///
+/// It is a cheap handle to the bus, so clones of it control the same one.
+#[derive(Debug, Clone)]
+pub struct Bus(Arc<BusState>);
+
+impl Bus {
+    fn new(parent: Option<Bus>) -> Self {
+        Bus(Arc::new(BusState {
+            volume: AtomicUsize::new(1.0f32.to_bits() as usize),
+            muted: AtomicBool::new(false),
+            paused: AtomicBool::new(false),
+            fade: Mutex::new(None),
+            parent,
+        }))
+    }
+
+    fn fade(&self) -> MutexGuard<Option<Fade>> {
+        self.0.fade.lock().expect("Bus fade lock poisoned")
+    }
+
+    fn store_volume(&self, value: f32) {
+        self.0
+            .volume
+            .store(value.to_bits() as usize, Ordering::Relaxed);
+    }
+
+    /// Gets the volume of the bus, partway through a fade if
+    /// there is one.
+    pub fn volume(&self) -> f32 {
+        f32::from_bits(self.0.volume.load(Ordering::Relaxed) as u32)
+    }
+
+    /// Sets the volume of the bus, stopping any fade.
+    pub fn set_volume(&self, value: f32) {
+        *self.fade() = None;
+        self.store_volume(value);
+    }
+
+    /// Fades the volume of the bus to the given value over the given time,
+    /// as the mixer is [`update()`](fn.update.html)d.
+    pub fn fade_to(&self, value: f32, duration: time::Duration) {
+        let mut fade = self.fade();
+        if duration == time::Duration::from_secs(0) {
+            *fade = None;
+            self.store_volume(value);
+        } else {
+            *fade = Some(Fade {
+                from: self.volume(),
+                to: value,
+                duration,
+                elapsed: time::Duration::from_secs(0),
+            });
+        }
+    }
+
+    /// Returns whether the bus is partway through a fade.
+    pub fn fading(&self) -> bool {
+        self.fade().is_some()
+    }
+
+    /// Gets whether the bus is muted.
+    pub fn muted(&self) -> bool {
+        self.0.muted.load(Ordering::Relaxed)
+    }
+
+    /// Mutes or unmutes the bus.  Its sources keep playing silently
+    /// and it keeps its volume.
+    pub fn set_muted(&self, muted: bool) {
+        self.0.muted.store(muted, Ordering::Relaxed)
+    }
+
+    /// Gets whether the bus is paused.
+    pub fn paused(&self) -> bool {
+        self.0.paused.load(Ordering::Relaxed)
+    }
+
+    /// Pauses all the sources playing into the bus.
+    pub fn pause(&self) {
+        self.0.paused.store(true, Ordering::Relaxed)
+    }
+
+    /// Resumes all the sources playing into the bus.
+    pub fn resume(&self) {
+        self.0.paused.store(false, Ordering::Relaxed)
+    }
+
+    /// How loud the bus's sources end up: its volume times that of the
+    /// bus it plays into, or nothing if either of them is muted.
+    fn gain(&self) -> f32 {
+        if self.muted() {
+            return 0.0;
+        }
+        match self.0.parent {
+            Some(ref parent) => self.volume() * parent.gain(),
+            None => self.volume(),
+        }
+    }
+
+    /// Whether the bus or the bus it plays into is paused.
+    fn halted(&self) -> bool {
+        self.paused() || self.0.parent.as_ref().map_or(false, Bus::halted)
+    }
+
+    fn advance(&self, dt: time::Duration) {
+        let mut fade = self.fade();
+        if let Some(mut f) = *fade {
+            f.elapsed += dt;
+            if f.elapsed >= f.duration {
+                self.store_volume(f.to);
+                *fade = None;
+            } else {
+                let t = timer::duration_to_f64(f.elapsed) / timer::duration_to_f64(f.duration);
+                self.store_volume(f.from + (f.to - f.from) * t as f32);
+                *fade = Some(f);
+            }
+        }
+    }
+
+    fn play<S>(&self, sound: S) -> BusOutput<S> {
+        BusOutput {
+            inner: sound,
+            bus: self.clone(),
+        }
+    }
+}
+
+/// A sound playing into a bus, which sets its volume and can pause it.
+struct BusOutput<S> {
+    inner: S,
+    bus: Bus,
+}
+
+impl<S> Iterator for BusOutput<S>
+where
+    S: rodio::Source,
+    S::Item: rodio::Sample,
+{
+    type Item = S::Item;
+
+    fn next(&mut self) -> Option<S::Item> {
+        use rodio::Sample;
+        // Like `rodio::source::Pausable`, play silence while paused.
+        if self.bus.halted() {
+            return Some(<S::Item as Sample>::zero_value());
+        }
+        let gain = self.bus.gain();
+        self.inner.next().map(|sample| sample.amplify(gain))
+    }
+}
+
+impl<S> rodio::Source for BusOutput<S>
+where
+    S: rodio::Source,
+    S::Item: rodio::Sample,
+{
+    fn current_frame_len(&self) -> Option<usize> {
+        self.inner.current_frame_len()
+    }
+
+    fn channels(&self) -> u16 {
+        self.inner.channels()
+    }
+
+    fn sample_rate(&self) -> u32 {
+        self.inner.sample_rate()
+    }
+
+    fn total_duration(&self) -> Option<time::Duration> {
+        self.inner.total_duration()
+    }
+}
+
+/// Mixes sources together through named [`Bus`](struct.Bus.html)es, to
+/// turn down all the sound effects at once, mute the music when the window
+/// loses focus or lower it under dialogue.
+///
+/// It starts out with a [`MASTER_BUS`](constant.MASTER_BUS.html), which
+/// all the other buses play into, and a [`MUSIC_BUS`](constant.MUSIC_BUS.html),
+/// [`SFX_BUS`](constant.SFX_BUS.html) and [`VOICE_BUS`](constant.VOICE_BUS.html).
+/// Sources play into the master bus unless they are given another one
+/// with [`SoundSource::set_bus()`](trait.SoundSource.html#tymethod.set_bus).
+///
+/// ```rust,no_run
+/// # use ggez::*;
+/// # use ggez::audio::*;
+/// # fn t(ctx: &mut Context) -> GameResult {
+/// let music_bus = audio::mixer(ctx).bus(audio::MUSIC_BUS).unwrap().clone();
+/// let mut music = StreamingSource::new(ctx, "/music.ogg")?;
+/// music.set_bus(&music_bus);
+/// music.play()?;
+///
+/// // Later, when someone starts talking:
+/// music_bus.fade_to(0.3, std::time::Duration::from_millis(500));
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct Mixer {
+    buses: HashMap<String, Bus>,
+}
+
+impl Mixer {
+    /// Creates a mixer with the default buses.
+    pub fn new() -> Self {
+        let mut mixer = Mixer {
+            buses: HashMap::new(),
+        };
+        let _ = mixer.buses.insert(MASTER_BUS.to_string(), Bus::new(None));
+        for name in &[MUSIC_BUS, SFX_BUS, VOICE_BUS] {
+            let _ = mixer.add_bus(name);
+        }
+        mixer
+    }
+
+    /// Gets the bus with the given name.
+    pub fn bus(&self, name: &str) -> Option<&Bus> {
+        self.buses.get(name)
+    }
+
+    /// Gets the master bus, which all the others play into.
+    pub fn master(&self) -> &Bus {
+        &self.buses[MASTER_BUS]
+    }
+
+    /// Adds a bus with the given name that plays into the master bus,
+    /// or gets the bus if there already is one with that name.
+    pub fn add_bus(&mut self, name: &str) -> &Bus {
+        let master = self.master().clone();
+        self.buses
+            .entry(name.to_string())
+            .or_insert_with(|| Bus::new(Some(master)))
     }
+
+    /// Advances the fades of all the buses by the given time.
+    pub fn update(&self, dt: time::Duration) {
+        for bus in self.buses.values() {
+            bus.advance(dt);
+        }
+    }
+}
+
+impl Default for Mixer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Gets the mixer that sources play into.
+pub fn mixer(ctx: &Context) -> &Mixer {
+    ctx.audio_context.mixer()
+}
+
+/// Gets the mixer that sources play into, mutably, for adding buses.
+pub fn mixer_mut(ctx: &mut Context) -> &mut Mixer {
+    ctx.audio_context.mixer_mut()
+}
+
+/// Advances the fades of the mixer's buses, and the playback of an
+/// [`OfflineAudioContext`](struct.OfflineAudioContext.html), by the length
+/// of the last frame.  [`event::run()`](../event/fn.run.html) calls this every frame;
+/// if you write your own main loop, you should too.
+pub fn update(ctx: &mut Context) {
+    let dt = timer::delta(ctx);
+    ctx.audio_context.mixer().update(dt);
+    ctx.audio_context.advance(dt);
 }
 
 /// Static sound data stored in memory.
@@ -141,7 +752,7 @@ impl AsRef<[u8]> for SoundData {
 }
 
 /// A trait defining the operations possible on a sound;
-/// it is implemented by both `Source` and `SpatialSource`.
+/// it is implemented by `Source`, `SpatialSource` and `StreamingSource`.
 pub trait SoundSource {
     /// Plays the audio source; restarts the sound if currently playing
     #[inline(always)]
@@ -159,9 +770,30 @@ pub trait SoundSource {
     /// Sets the source to repeat playback infinitely on next [`play()`](#method.play)
     fn set_repeat(&mut self, repeat: bool);
 
+    /// Sets the part of the sound that repeats, from the next
+    /// [`play()`](#method.play), if the source is set to repeat.  The sound
+    /// plays from the start up to `end`, then goes back to `start` and plays
+    /// up to `end` again, over and over; an `end` of `None` is the end of the
+    /// sound.  This is how to play music with an intro that isn't repeated.
+    ///
+    /// Returns an error if `end` comes before `start`.
+    fn set_loop_region(&mut self, start: time::Duration, end: Option<time::Duration>)
+        -> GameResult;
+
+    /// Gets the start and end of the part of the sound that repeats.
+    fn loop_region(&self) -> (time::Duration, Option<time::Duration>);
+
     /// Sets the fade-in time of the source
     fn set_fade_in(&mut self, dur: time::Duration);
 
+    /// Sets how long the source fades out for when it is stopped or
+    /// dropped while playing, instead of cutting off with a click.  It
+    /// is zero to begin with, which cuts off.
+    ///
+    /// The sound fades out by itself, so the source is stopped straight
+    /// away and can be played again while it does.
+    fn set_fade_out(&mut self, dur: time::Duration);
+
     /// Sets the speed ratio (by adjusting the playback speed)
     fn set_pitch(&mut self, ratio: f32);
 
@@ -177,6 +809,19 @@ pub trait SoundSource {
     /// Stops playback
     fn stop(&mut self);
 
+    /// Moves playback to the given time into the sound.  If the source is
+    /// playing or paused it carries on from there, otherwise it starts
+    /// from there the next time it is played.  It jumps straight there
+    /// without fading out, even if the source has a fade-out time.
+    ///
+    /// Sounds can't jump straight to a point, so this decodes everything
+    /// up to it, which takes a while far into long sounds.
+    fn seek(&mut self, position: time::Duration) -> GameResult;
+
+    /// Gets how long the sound is.  The first call decodes the whole sound
+    /// to find out, unless its format says.
+    fn duration(&self) -> GameResult<time::Duration>;
+
     /// Returns whether or not the source is stopped
     /// -- that is, has no more data to play.
     fn stopped(&self) -> bool;
@@ -184,9 +829,22 @@ pub trait SoundSource {
     /// Gets the current volume.
     fn volume(&self) -> f32;
 
-    /// Sets the current volume.
+    /// Sets the current volume, stopping any fade.
     fn set_volume(&mut self, value: f32);
 
+    /// Fades the volume to the given value over the given time, as the
+    /// source plays.  If it is stopped the volume is set straight away.
+    fn fade_to(&mut self, value: f32, duration: time::Duration);
+
+    /// Fades the source out over the given time as it plays, then stops
+    /// it and puts the volume back to what it was for the next time it
+    /// is played.
+    fn fade_out(&mut self, duration: time::Duration);
+
+    /// Gets whether the source is partway through a
+    /// [`fade_to()`](#tymethod.fade_to) or [`fade_out()`](#tymethod.fade_out).
+    fn fading(&self) -> bool;
+
     /// Get whether or not the source is paused.
     fn paused(&self) -> bool;
 
@@ -203,29 +861,87 @@ pub trait SoundSource {
     ///
     /// This parameter determines the precision of the time measured by [`elapsed()`](#method.elapsed).
     fn set_query_interval(&mut self, t: time::Duration);
+
+    /// Sets the [`Bus`](struct.Bus.html) the source plays into, from the
+    /// next time it is played; sources start out on the master bus.
+    fn set_bus(&mut self, bus: &Bus);
+}
+
+/// Crossfades from one source into another over the given time: `from`
+/// fades out and stops, while `to` starts playing from silence and fades
+/// up to its volume.
+///
+/// ```rust,no_run
+/// # use ggez::*;
+/// # use ggez::audio::*;
+/// # fn t(ctx: &mut Context) -> GameResult {
+/// let mut town = StreamingSource::new(ctx, "/town.ogg")?;
+/// let mut battle = StreamingSource::new(ctx, "/battle.ogg")?;
+/// town.play()?;
+///
+/// // Later, when a fight starts:
+/// audio::crossfade(&mut town, &mut battle, std::time::Duration::from_secs(2))?;
+/// # Ok(())
+/// # }
+/// ```
+pub fn crossfade<A, B>(from: &mut A, to: &mut B, duration: time::Duration) -> GameResult
+where
+    A: SoundSource + ?Sized,
+    B: SoundSource + ?Sized,
+{
+    let volume = to.volume();
+    to.set_volume(0.0);
+    if let Err(e) = to.play() {
+        to.set_volume(volume);
+        return Err(e);
+    }
+    to.fade_to(volume, duration);
+    from.fade_out(duration);
+    Ok(())
 }
 
 /// Internal state used by audio sources.
 #[derive(Debug)]
 pub(crate) struct SourceState {
-    data: io::Cursor<SoundData>,
+    data: StreamData,
+    output: AudioOutput,
+    bus: Bus,
     repeat: bool,
+    loop_region: (time::Duration, Option<time::Duration>),
     fade_in: time::Duration,
+    fade_out: time::Duration,
+    volume: SourceVolume,
     speed: f32,
     query_interval: time::Duration,
     play_time: Arc<AtomicUsize>,
+    /// Where a `SpatialSource` and the listener's ears are.
+    positions: Option<Arc<Mutex<SpatialPositions>>>,
+    /// Where to start the next time the source is played, if it has
+    /// been `seek()`ed.
+    start: Cell<Option<time::Duration>>,
+    /// How long the sound is, once it has been worked out.
+    duration: Cell<Option<time::Duration>>,
 }
 
 impl SourceState {
-    /// Create a new `SourceState` based around the given `SoundData`
-    pub fn new(cursor: io::Cursor<SoundData>) -> Self {
+    /// Create a new `SourceState` for playing the given data into the
+    /// context's output, on the master bus
+    fn new(context: &Context, data: StreamData) -> Self {
         SourceState {
-            data: cursor,
+            data,
+            output: context.audio_context.output(),
+            bus: context.audio_context.mixer().master().clone(),
             repeat: false,
+            loop_region: (time::Duration::from_millis(0), None),
             fade_in: time::Duration::from_millis(0),
+            fade_out: time::Duration::from_millis(0),
+            volume: SourceVolume::new(1.0),
             speed: 1.0,
             query_interval: time::Duration::from_millis(100),
             play_time: Arc::new(AtomicUsize::new(0)),
+            positions: None,
+            start: Cell::new(None),
+            duration: Cell::new(None),
         }
     }
     /// Sets the source to repeat playback infinitely on next [`play()`](#method.play)
@@ -233,11 +949,37 @@ impl SourceState {
         self.repeat = repeat;
     }
 
+    /// Sets the part of the sound that repeats.
+    pub fn set_loop_region(
+        &mut self,
+        start: time::Duration,
+        end: Option<time::Duration>,
+    ) -> GameResult {
+        if end.map_or(false, |end| end <= start) {
+            return Err(GameError::AudioError(format!(
+                "Loop region ends at {:?}, before it starts at {:?}",
+                end, start
+            )));
+        }
+        self.loop_region = (start, end);
+        Ok(())
+    }
+
+    /// Gets the part of the sound that repeats.
+    pub fn loop_region(&self) -> (time::Duration, Option<time::Duration>) {
+        self.loop_region
+    }
+
     /// Sets the fade-in time of the source.
     pub fn set_fade_in(&mut self, dur: time::Duration) {
         self.fade_in = dur;
     }
 
+    /// Sets how long the source fades out for when it is stopped.
+    pub fn set_fade_out(&mut self, dur: time::Duration) {
+        self.fade_out = dur;
+    }
+
     /// Sets the pitch ratio (by adjusting the playback speed).
     pub fn set_pitch(&mut self, ratio: f32) {
         self.speed = ratio;
@@ -263,102 +1005,493 @@ impl SourceState {
     pub fn set_query_interval(&mut self, t: time::Duration) {
         self.query_interval = t;
     }
-}
 
-/// A source of audio data that is connected to an output
-/// channel and ready to play.  It will stop playing when
-/// dropped.
-// TODO LATER: Check and see if this matches Love2d's semantics!
-// Eventually it might read from a streaming decoder of some kind,
-// but for now it is just an in-memory SoundData structure.
-// The source of a rodio decoder must be Send, which something
-// that contains a reference to a ZipFile is not, so we are going
-// to just slurp all the data into memory for now.
-// There's really a lot of work that needs to be done here, since
-// rodio has gotten better (if still somewhat arcane) and our filesystem
-// code has done the data-slurping-from-zip's for us
-// but for now it works.
-pub struct Source {
-    sink: rodio::Sink,
-    state: SourceState,
-}
+    /// Sets the bus the source plays into.
+    pub fn set_bus(&mut self, bus: &Bus) {
+        self.bus = bus.clone();
+    }
 
-impl Source {
-    /// Create a new `Source` from the given file.
-    pub fn new<P: AsRef<path::Path>>(context: &mut Context, path: P) -> GameResult<Self> {
-        let path = path.as_ref();
-        let data = SoundData::new(context, path)?;
-        Source::from_data(context, data)
+    /// Gets the volume of the source, partway through a fade if there
+    /// is one.
+    pub fn volume(&self) -> f32 {
+        self.volume.get()
     }
 
-    /// Creates a new `Source` using the given `SoundData` object.
-    pub fn from_data(context: &mut Context, data: SoundData) -> GameResult<Self> {
-        if !data.can_play() {
-            return Err(GameError::AudioError(
-                "Could not decode the given audio data".to_string(),
-            ));
-        }
-        let sink = rodio::Sink::new(&context.audio_context.device());
-        let cursor = io::Cursor::new(data);
-        Ok(Source {
-            sink,
-            state: SourceState::new(cursor),
-        })
+    /// Sets the volume of the source, stopping any fade.
+    pub fn set_volume(&mut self, value: f32) {
+        self.volume.set(value)
     }
-}
 
-impl SoundSource for Source {
-    fn play_later(&self) -> GameResult {
-        // Creating a new Decoder each time seems a little messy,
-        // since it may do checking and data-type detection that is
-        // redundant, but it's not super expensive.
-        // See https://github.com/ggez/ggez/issues/98 for discussion
-        use rodio::Source;
-        let cursor = self.state.data.clone();
-
-        let counter = self.state.play_time.clone();
-        let period_mus = self.state.query_interval.as_secs() as usize * 1_000_000
-            + self.state.query_interval.subsec_micros() as usize;
-
-        if self.state.repeat {
-            let sound = rodio::Decoder::new(cursor)?
-                .repeat_infinite()
-                .speed(self.state.speed)
-                .fade_in(self.state.fade_in)
-                .periodic_access(self.state.query_interval, move |_| {
-                    let _ = counter.fetch_add(period_mus, Ordering::SeqCst);
-                });
-            self.sink.append(sound);
+    /// Fades the volume of the sounds playing in the sink to the given
+    /// value over the given time, or sets it if nothing is playing.
+    pub fn fade_to(&mut self, sink: &rodio::Sink, value: f32, duration: time::Duration) {
+        if sink.empty() {
+            self.set_volume(value);
         } else {
-            let sound = rodio::Decoder::new(cursor)?
-                .speed(self.state.speed)
-                .fade_in(self.state.fade_in)
-                .periodic_access(self.state.query_interval, move |_| {
-                    let _ = counter.fetch_add(period_mus, Ordering::SeqCst);
-                });
-            self.sink.append(sound);
+            self.volume.fade_to(value, duration, false);
         }
+    }
 
-        Ok(())
+    /// Fades out the sounds playing in the sink over the given time, then
+    /// stops them and puts the volume back to what it was.
+    pub fn fade_out(&mut self, sink: &mut rodio::Sink, duration: time::Duration) {
+        if sink.empty() {
+            return;
+        }
+        if duration == time::Duration::from_secs(0) {
+            self.stop(sink);
+        } else {
+            self.volume.fade_to(0.0, duration, true);
+        }
     }
 
-    fn play_detached(&mut self) -> GameResult {
-        self.stop();
-        self.play_later()?;
+    /// Gets whether the source is partway through a fade.
+    pub fn fading(&self) -> bool {
+        self.volume.fading()
+    }
 
-        let device = rodio::default_output_device().unwrap();
-        let new_sink = rodio::Sink::new(&device);
-        let old_sink = mem::replace(&mut self.sink, new_sink);
-        old_sink.detach();
+    /// Plays the sound into the given sink, after whatever is already
+    /// playing in it.
+    pub fn play_later(&self, sink: &rodio::Sink) -> GameResult {
+        let sound = self.sound()?;
+        match self.positions {
+            Some(ref positions) => sink.append(self.bus.play(spatial(positions, sound))),
+            None => sink.append(self.bus.play(sound)),
+        }
+        Ok(())
+    }
 
+    /// Stops the sink's sounds and plays the sound into it, then lets it
+    /// play out by itself with a new sink in its place.
+    pub fn play_detached(&mut self, sink: &mut rodio::Sink) -> GameResult {
+        self.stop(sink);
+        self.play_later(sink)?;
+        let old_sink = mem::replace(sink, self.output.new_sink());
+        self.detach(old_sink);
         Ok(())
     }
 
-    fn set_repeat(&mut self, repeat: bool) {
-        self.state.set_repeat(repeat)
+    /// Stops the sounds in the sink, fading them out first if the source
+    /// has a fade-out time.
+    pub fn stop(&mut self, sink: &mut rodio::Sink) {
+        // Sinks cannot be reused after calling `.stop()`. See
+        // https://github.com/tomaka/rodio/issues/171 for information.
+        // To stop the current sound we have to drop the old sink and
+        // create a new one in its place, which is what the state's
+        // `AudioOutput` is for, since we don't have a context here.
+        // The volume is kept in the state, so it carries over.
+        let old_sink = mem::replace(sink, self.output.new_sink());
+        self.release(old_sink);
+        self.play_time.store(0, Ordering::SeqCst);
     }
-    fn set_fade_in(&mut self, dur: time::Duration) {
-        self.state.set_fade_in(dur)
+
+    /// Lets go of the sink of a source being dropped, fading its sounds
+    /// out first if the source has a fade-out time.
+    pub fn drop_sink(&mut self, sink: &mut rodio::Sink) {
+        // The idle sink plays nowhere, so it doesn't need a device.
+        let (idle_sink, _) = rodio::Sink::new_idle();
+        let old_sink = mem::replace(sink, idle_sink);
+        self.release(old_sink);
+    }
+
+    /// Stops the sounds in the given sink, which the source has stopped
+    /// using, letting them fade out first if the source has a fade-out
+    /// time and they are playing.
+    fn release(&mut self, sink: rodio::Sink) {
+        let zero = time::Duration::from_secs(0);
+        if self.fade_out > zero && !sink.empty() && !sink.is_paused() {
+            self.volume.fade_to(0.0, self.fade_out, true);
+            self.detach(sink);
+        }
+    }
+
+    /// Lets the sounds in the given sink play out by themselves, so the
+    /// source's volume and `elapsed()` only follow the sounds it plays
+    /// from now on.
+    fn detach(&mut self, sink: rodio::Sink) {
+        self.volume = self.volume.split();
+        self.play_time = Arc::new(AtomicUsize::new(0));
+        sink.detach();
+    }
+
+    /// Makes the next sound played start at the given time, and if the
+    /// sink is playing or paused, carries on from there in it.
+    pub fn seek(&mut self, sink: &mut rodio::Sink, position: time::Duration) -> GameResult {
+        self.start.set(Some(position));
+        if sink.empty() {
+            return Ok(());
+        }
+        // Dropping the old sink cuts its sound off rather than fading it
+        // out, and it gets its own play time so it can't bump ours.
+        let paused = sink.is_paused();
+        *sink = self.output.new_sink();
+        self.play_time = Arc::new(AtomicUsize::new(0));
+        if paused {
+            sink.pause();
+        }
+        self.play_later(sink)
+    }
+
+    /// Gets how long the sound is, working it out the first time.
+    pub fn duration(&self) -> GameResult<time::Duration> {
+        if let Some(duration) = self.duration.get() {
+            return Ok(duration);
+        }
+        let duration = self.data.duration()?;
+        self.duration.set(Some(duration));
+        Ok(duration)
+    }
+
+    /// Decodes the sound from where it was last `seek()`ed to, or else
+    /// the beginning, with the pitch, fades, volume and looping of the state.
+    fn sound(&self) -> GameResult<impl rodio::Source<Item = i16> + Send> {
+        // Creating a new Decoder each time seems a little messy,
+        // since it may do checking and data-type detection that is
+        // redundant, but it's not super expensive.
+        // See https://github.com/ggez/ggez/issues/98 for discussion
+        use rodio::Source;
+        let start = self.start.take();
+        let looping = if self.repeat {
+            Some(self.loop_region)
+        } else {
+            None
+        };
+        let stream = Stream::new(&self.data, start.unwrap_or_default(), looping)?;
+        if let Some(start) = start {
+            self.play_time.store(micros(start), Ordering::SeqCst);
+        }
+
+        let counter = self.play_time.clone();
+        let period_mus = micros(self.query_interval);
+        let sound = stream
+            .speed(self.speed)
+            .fade_in(self.fade_in)
+            .periodic_access(self.query_interval, move |_| {
+                let _ = counter.fetch_add(period_mus, Ordering::SeqCst);
+            });
+        Ok(self.volume.play(sound))
+    }
+}
+
+fn micros(d: time::Duration) -> usize {
+    d.as_secs() as usize * 1_000_000 + d.subsec_micros() as usize
+}
+
+/// How many frames of sound at the given sample rate last the given time,
+/// worked out with integer maths so none get lost to rounding errors.
+fn duration_to_frames(d: time::Duration, sample_rate: u32) -> u64 {
+    let rate = u64::from(sample_rate);
+    d.as_secs() * rate + u64::from(d.subsec_nanos()) * rate / 1_000_000_000
+}
+
+/// How long the given number of frames of sound at the given sample
+/// rate lasts.
+fn frames_to_duration(frames: u64, sample_rate: u32) -> time::Duration {
+    let rate = u64::from(sample_rate);
+    let nanos = (frames % rate) * 1_000_000_000 / rate;
+    time::Duration::new(frames / rate, nanos as u32)
+}
+
+/// How loud a source is and how that is changing.
+#[derive(Debug, Copy, Clone)]
+struct VolumeControl {
+    /// The volume the source is at, or ends up at once it has faded.
+    volume: f32,
+    fade: Option<Fade>,
+    /// Whether the sounds stop at the end of the fade.
+    stop: bool,
+}
+
+#[derive(Debug)]
+struct SourceVolumeState {
+    control: Mutex<VolumeControl>,
+    // The audio thread checks these for every sample, so it only has to
+    // take the lock when something has changed.
+    /// Bumped every time `control` changes.
+    changes: AtomicUsize,
+    /// Bumped when a fade-out finishes, to end every sound that was
+    /// playing then.
+    stops: AtomicUsize,
+    /// How far through the fade the audio thread is, as the bits of an
+    /// `f32` from 0 to 1.
+    progress: AtomicUsize,
+}
+
+/// The volume of a source, shared with the sounds it is playing, which
+/// do its fades as they play.
+#[derive(Debug, Clone)]
+struct SourceVolume(Arc<SourceVolumeState>);
+
+impl SourceVolume {
+    fn new(volume: f32) -> Self {
+        SourceVolume(Arc::new(SourceVolumeState {
+            control: Mutex::new(VolumeControl {
+                volume,
+                fade: None,
+                stop: false,
+            }),
+            changes: AtomicUsize::new(0),
+            stops: AtomicUsize::new(0),
+            progress: AtomicUsize::new(0),
+        }))
+    }
+
+    fn control(&self) -> MutexGuard<VolumeControl> {
+        self.0.control.lock().expect("Source volume lock poisoned")
+    }
+
+    /// Changes the volume, telling the audio thread about it.
+    fn update<F>(&self, f: F)
+    where
+        F: FnOnce(&mut VolumeControl),
+    {
+        let mut control = self.control();
+        f(&mut control);
+        self.0
+            .progress
+            .store(0.0f32.to_bits() as usize, Ordering::Relaxed);
+        let _ = self.0.changes.fetch_add(1, Ordering::SeqCst);
+    }
+
+    /// Gets the volume, partway through a fade if there is one.
+    fn get(&self) -> f32 {
+        let control = self.control();
+        match control.fade {
+            Some(fade) => {
+                let t = f32::from_bits(self.0.progress.load(Ordering::Relaxed) as u32);
+                fade.from + (fade.to - fade.from) * t
+            }
+            None => control.volume,
+        }
+    }
+
+    /// Sets the volume, stopping any fade.
+    fn set(&self, value: f32) {
+        self.update(|control| {
+            *control = VolumeControl {
+                volume: value,
+                fade: None,
+                stop: false,
+            }
+        });
+    }
+
+    /// Fades the volume to the given value over the given time, or to
+    /// nothing and then stops the sounds playing if `stop` is set.
+    fn fade_to(&self, value: f32, duration: time::Duration, stop: bool) {
+        if duration == time::Duration::from_secs(0) && !stop {
+            self.set(value);
+            return;
+        }
+        let from = self.get();
+        self.update(|control| {
+            if !stop {
+                control.volume = value;
+            }
+            control.fade = Some(Fade {
+                from,
+                to: if stop { 0.0 } else { value },
+                duration,
+                elapsed: time::Duration::from_secs(0),
+            });
+            control.stop = stop;
+        });
+    }
+
+    fn fading(&self) -> bool {
+        self.control().fade.is_some()
+    }
+
+    /// Makes a new volume at the one this one ends up at, for a source to
+    /// use once the sounds playing with this one are let go of.
+    fn split(&self) -> Self {
+        SourceVolume::new(self.control().volume)
+    }
+
+    fn play<S>(&self, sound: S) -> VolumeOutput<S> {
+        VolumeOutput {
+            inner: sound,
+            volume: self.clone(),
+            stops: self.0.stops.load(Ordering::SeqCst),
+            changes: None,
+            gain: 1.0,
+            fade: None,
+        }
+    }
+}
+
+/// A fade as the audio thread works through it.
+struct FadeProgress {
+    from: f32,
+    to: f32,
+    stop: bool,
+    length: f64,
+    elapsed: f64,
+}
+
+/// A sound playing at the volume of its source, which fades it sample
+/// by sample so that it doesn't click.
+struct VolumeOutput<S> {
+    inner: S,
+    volume: SourceVolume,
+    /// What `SourceVolumeState::stops` was when the sound was played.
+    stops: usize,
+    /// What `SourceVolumeState::changes` was when the sound last looked.
+    changes: Option<usize>,
+    gain: f32,
+    fade: Option<FadeProgress>,
+}
+
+impl<S> VolumeOutput<S> {
+    fn sync(&mut self) {
+        let control = self.volume.control();
+        self.changes = Some(self.volume.0.changes.load(Ordering::SeqCst));
+        self.gain = control.volume;
+        self.fade = control.fade.map(|fade| FadeProgress {
+            from: fade.from,
+            to: fade.to,
+            stop: control.stop,
+            length: timer::duration_to_f64(fade.duration),
+            elapsed: 0.0,
+        });
+    }
+
+    /// Marks the fade as done, unless the source has started another
+    /// one in the meantime.
+    fn finish_fade(&mut self, stop: bool) {
+        let state = &self.volume.0;
+        let mut control = self.volume.control();
+        if Some(state.changes.load(Ordering::SeqCst)) != self.changes {
+            return;
+        }
+        control.fade = None;
+        control.stop = false;
+        if stop {
+            let _ = state.stops.fetch_add(1, Ordering::SeqCst);
+        }
+        self.changes = Some(state.changes.fetch_add(1, Ordering::SeqCst) + 1);
+    }
+}
+
+impl<S> Iterator for VolumeOutput<S>
+where
+    S: rodio::Source,
+    S::Item: rodio::Sample,
+{
+    type Item = S::Item;
+
+    fn next(&mut self) -> Option<S::Item> {
+        use rodio::Sample;
+        if self.volume.0.stops.load(Ordering::SeqCst) != self.stops {
+            return None;
+        }
+        if Some(self.volume.0.changes.load(Ordering::SeqCst)) != self.changes {
+            self.sync();
+        }
+        if let Some(mut fade) = self.fade.take() {
+            let t = (fade.elapsed / fade.length).min(1.0) as f32;
+            self.gain = fade.from + (fade.to - fade.from) * t;
+            self.volume
+                .0
+                .progress
+                .store(t.to_bits() as usize, Ordering::Relaxed);
+            if t >= 1.0 {
+                self.finish_fade(fade.stop);
+                if fade.stop {
+                    return None;
+                }
+            } else {
+                let rate = u32::from(self.inner.channels()) * self.inner.sample_rate();
+                fade.elapsed += 1.0 / f64::from(rate);
+                self.fade = Some(fade);
+            }
+        }
+        self.inner.next().map(|sample| sample.amplify(self.gain))
+    }
+}
+
+impl<S> rodio::Source for VolumeOutput<S>
+where
+    S: rodio::Source,
+    S::Item: rodio::Sample,
+{
+    fn current_frame_len(&self) -> Option<usize> {
+        self.inner.current_frame_len()
+    }
+
+    fn channels(&self) -> u16 {
+        self.inner.channels()
+    }
+
+    fn sample_rate(&self) -> u32 {
+        self.inner.sample_rate()
+    }
+
+    fn total_duration(&self) -> Option<time::Duration> {
+        self.inner.total_duration()
+    }
+}
+
+/// A source of audio data that is connected to an output
+/// channel and ready to play.  It will stop playing when
+/// dropped.
+// TODO LATER: Check and see if this matches Love2d's semantics!
+// It is just an in-memory SoundData structure, which is what you want
+// for short sounds that get played over and over; long ones should use
+// a StreamingSource.
+pub struct Source {
+    sink: rodio::Sink,
+    state: SourceState,
+}
+
+impl Source {
+    /// Create a new `Source` from the given file.
+    pub fn new<P: AsRef<path::Path>>(context: &mut Context, path: P) -> GameResult<Self> {
+        let path = path.as_ref();
+        let data = SoundData::new(context, path)?;
+        Source::from_data(context, data)
+    }
+
+    /// Creates a new `Source` using the given `SoundData` object.
+    pub fn from_data(context: &mut Context, data: SoundData) -> GameResult<Self> {
+        if !data.can_play() {
+            return Err(GameError::AudioError(
+                "Could not decode the given audio data".to_string(),
+            ));
+        }
+        let state = SourceState::new(context, StreamData::Memory(data));
+        let sink = state.output.new_sink();
+        Ok(Source { sink, state })
+    }
+}
+
+impl SoundSource for Source {
+    fn play_later(&self) -> GameResult {
+        self.state.play_later(&self.sink)
+    }
+
+    fn play_detached(&mut self) -> GameResult {
+        self.state.play_detached(&mut self.sink)
+    }
+
+    fn set_repeat(&mut self, repeat: bool) {
+        self.state.set_repeat(repeat)
+    }
+    fn set_loop_region(
+        &mut self,
+        start: time::Duration,
+        end: Option<time::Duration>,
+    ) -> GameResult {
+        self.state.set_loop_region(start, end)
+    }
+    fn loop_region(&self) -> (time::Duration, Option<time::Duration>) {
+        self.state.loop_region()
+    }
+    fn set_fade_in(&mut self, dur: time::Duration) {
+        self.state.set_fade_in(dur)
+    }
+    fn set_fade_out(&mut self, dur: time::Duration) {
+        self.state.set_fade_out(dur)
     }
     fn set_pitch(&mut self, ratio: f32) {
         self.state.set_pitch(ratio)
@@ -374,26 +1507,15 @@ impl SoundSource for Source {
     }
 
     fn stop(&mut self) {
-        // Sinks cannot be reused after calling `.stop()`. See
-        // https://github.com/tomaka/rodio/issues/171 for information.
-        // To stop the current sound we have to drop the old sink and
-        // create a new one in its place.
-        // This is most ugly because in order to create a new sink
-        // we need a `device`. However, we can only get the default
-        // device without having access to a context. Currently that's
-        // fine because the `RodioAudioContext` uses the default device too,
-        // but it may cause problems in the future if devices become
-        // customizable.
-
-        // We also need to carry over information from the previous sink.
-        let volume = self.volume();
+        self.state.stop(&mut self.sink)
+    }
 
-        let device = rodio::default_output_device().unwrap();
-        self.sink = rodio::Sink::new(&device);
-        self.state.play_time.store(0, Ordering::SeqCst);
+    fn seek(&mut self, position: time::Duration) -> GameResult {
+        self.state.seek(&mut self.sink, position)
+    }
 
-        // Restore information from the previous link.
-        self.set_volume(volume);
+    fn duration(&self) -> GameResult<time::Duration> {
+        self.state.duration()
     }
 
     fn stopped(&self) -> bool {
@@ -401,11 +1523,23 @@ impl SoundSource for Source {
     }
 
     fn volume(&self) -> f32 {
-        self.sink.volume()
+        self.state.volume()
     }
 
     fn set_volume(&mut self, value: f32) {
-        self.sink.set_volume(value)
+        self.state.set_volume(value)
+    }
+
+    fn fade_to(&mut self, value: f32, duration: time::Duration) {
+        self.state.fade_to(&self.sink, value, duration)
+    }
+
+    fn fade_out(&mut self, duration: time::Duration) {
+        self.state.fade_out(&mut self.sink, duration)
+    }
+
+    fn fading(&self) -> bool {
+        self.state.fading()
     }
 
     fn paused(&self) -> bool {
@@ -423,6 +1557,10 @@ impl SoundSource for Source {
     fn set_query_interval(&mut self, t: time::Duration) {
         self.state.set_query_interval(t)
     }
+
+    fn set_bus(&mut self, bus: &Bus) {
+        self.state.set_bus(bus)
+    }
 }
 
 impl fmt::Debug for Source {
@@ -431,14 +1569,379 @@ impl fmt::Debug for Source {
     }
 }
 
+impl Drop for Source {
+    fn drop(&mut self) {
+        self.state.drop_sink(&mut self.sink)
+    }
+}
+
+/// Where a source reads its sound from.
+#[derive(Debug, Clone)]
+enum StreamData {
+    /// Read from disk bit by bit as it is played.
+    File(vfs::FileLocation),
+    /// A `SoundData`, or a file that can't be read straight from disk,
+    /// such as a compressed file in a zip file, read into memory.
+    Memory(SoundData),
+}
+
+impl StreamData {
+    fn decoder(&self) -> GameResult<rodio::Decoder<StreamReader>> {
+        let reader = match *self {
+            StreamData::File(ref location) => StreamReader::File(location.open()?),
+            StreamData::Memory(ref data) => StreamReader::Memory(io::Cursor::new(data.clone())),
+        };
+        Ok(rodio::Decoder::new(reader)?)
+    }
+
+    /// Makes a decoder that has already decoded the given number of
+    /// samples.  Rodio's decoders can't jump straight to a point in the
+    /// sound, so this is the only way to get there.
+    fn decoder_at(&self, samples: u64) -> GameResult<rodio::Decoder<StreamReader>> {
+        let mut decoder = self.decoder()?;
+        for _ in 0..samples {
+            if decoder.next().is_none() {
+                break;
+            }
+        }
+        Ok(decoder)
+    }
+
+    /// Works out how long the sound is, decoding all of it if its
+    /// format doesn't say.
+    fn duration(&self) -> GameResult<time::Duration> {
+        use rodio::Source;
+        let decoder = self.decoder()?;
+        if let Some(duration) = decoder.total_duration() {
+            return Ok(duration);
+        }
+        let channels = u64::from(decoder.channels());
+        let sample_rate = decoder.sample_rate();
+        let frames = decoder.count() as u64 / channels;
+        Ok(frames_to_duration(frames, sample_rate))
+    }
+}
+
+/// Reads the data of a source; unlike a `filesystem::File` it is `Send`,
+/// so it can be decoded on the audio thread.
+enum StreamReader {
+    File(vfs::SpanFile),
+    Memory(io::Cursor<SoundData>),
+}
+
+impl io::Read for StreamReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        match *self {
+            StreamReader::File(ref mut f) => f.read(buf),
+            StreamReader::Memory(ref mut c) => c.read(buf),
+        }
+    }
+}
+
+impl io::Seek for StreamReader {
+    fn seek(&mut self, pos: io::SeekFrom) -> io::Result<u64> {
+        match *self {
+            StreamReader::File(ref mut f) => f.seek(pos),
+            StreamReader::Memory(ref mut c) => c.seek(pos),
+        }
+    }
+}
+
+type PreparedDecoder = mpsc::Receiver<Option<rodio::Decoder<StreamReader>>>;
+
+/// Decodes the sound of a source as it plays.  To repeat it starts
+/// decoding again from the start of the loop region, since
+/// `rodio::Source::repeat_infinite()` would keep every sample it has
+/// played in memory and can't loop only part of a sound.
+struct Stream {
+    data: StreamData,
+    decoder: rodio::Decoder<StreamReader>,
+    /// How many samples into the sound the decoder is.
+    position: u64,
+    /// Where the loop region starts and ends, in samples, if repeating.
+    looping: Option<(u64, Option<u64>)>,
+    /// A decoder for the next time around the loop, which is got ready
+    /// on another thread when the loop doesn't start at the beginning,
+    /// since getting to the start of it can take a while.
+    next_loop: Option<PreparedDecoder>,
+}
+
+impl Stream {
+    fn new(
+        data: &StreamData,
+        start: time::Duration,
+        looping: Option<(time::Duration, Option<time::Duration>)>,
+    ) -> GameResult<Self> {
+        use rodio::Source;
+        let decoder = data.decoder()?;
+        let channels = u64::from(decoder.channels());
+        let sample_rate = decoder.sample_rate();
+        let to_samples = |d| duration_to_frames(d, sample_rate) * channels;
+        let position = to_samples(start);
+        let mut stream = Stream {
+            data: data.clone(),
+            decoder: data.decoder_at(position)?,
+            position,
+            looping: looping.map(|(start, end)| (to_samples(start), end.map(to_samples))),
+            next_loop: None,
+        };
+        stream.prepare_loop();
+        Ok(stream)
+    }
+
+    fn prepare_loop(&mut self) {
+        let start = match self.looping {
+            Some((start, _)) if start > 0 => start,
+            _ => return,
+        };
+        let data = self.data.clone();
+        let (sender, receiver) = mpsc::channel();
+        let _ = thread::spawn(move || {
+            let _ = sender.send(data.decoder_at(start).ok());
+        });
+        self.next_loop = Some(receiver);
+    }
+
+    /// Goes back to the start of the loop region.  There is nowhere to
+    /// report an error to from the audio thread, so if the file can't be
+    /// opened again the sound just ends.
+    fn restart_loop(&mut self, start: u64) -> Option<()> {
+        self.decoder = match self.next_loop.take() {
+            Some(prepared) => prepared.recv().ok()??,
+            None => self.data.decoder_at(start).ok()?,
+        };
+        self.position = start;
+        self.prepare_loop();
+        Some(())
+    }
+}
+
+impl Iterator for Stream {
+    type Item = i16;
+
+    fn next(&mut self) -> Option<i16> {
+        if let Some((start, Some(end))) = self.looping {
+            if self.position >= end {
+                self.restart_loop(start)?;
+            }
+        }
+        if let Some(sample) = self.decoder.next() {
+            self.position += 1;
+            return Some(sample);
+        }
+        // The end of the file is the end of a loop region without one.
+        let (start, _) = self.looping?;
+        self.restart_loop(start)?;
+        let sample = self.decoder.next()?;
+        self.position += 1;
+        Some(sample)
+    }
+}
+
+impl rodio::Source for Stream {
+    fn current_frame_len(&self) -> Option<usize> {
+        // Rodio takes an empty frame to mean the sound is over, and a
+        // loop can end partway through a frame, but it's all the same
+        // file so the channels and sample rate won't change anyway.
+        if self.looping.is_some() {
+            None
+        } else {
+            self.decoder.current_frame_len()
+        }
+    }
+
+    fn channels(&self) -> u16 {
+        self.decoder.channels()
+    }
+
+    fn sample_rate(&self) -> u32 {
+        self.decoder.sample_rate()
+    }
+
+    fn total_duration(&self) -> Option<time::Duration> {
+        if self.looping.is_some() {
+            None
+        } else {
+            self.decoder.total_duration()
+        }
+    }
+}
+
+/// A source of audio data that is read from its file bit by bit as it
+/// plays, instead of all at once like a [`Source`](struct.Source.html).
+/// Long sounds such as music start playing straight away and don't use
+/// up memory this way.  It will stop playing when dropped.
+///
+/// Files in the physical filesystem are read straight from disk, and so
+/// are files stored without compression in a zip file; since sound files
+/// are already compressed, that is the way to put music in one.  Other
+/// files are read into memory when the `StreamingSource` is created, and
+/// a warning is logged saying so.
+pub struct StreamingSource {
+    sink: rodio::Sink,
+    state: SourceState,
+}
+
+impl StreamingSource {
+    /// Create a new `StreamingSource` that plays the given file.
+    pub fn new<P: AsRef<path::Path>>(context: &mut Context, path: P) -> GameResult<Self> {
+        let path = path.as_ref();
+        let data = match context.filesystem.locate(path) {
+            Some(location) => StreamData::File(location),
+            None => {
+                // Most likely a compressed file in a zip, which can't be
+                // read a bit at a time without decompressing all of it.
+                warn!(
+                    "Can't stream {:?} straight from disk, reading all of it into memory instead",
+                    path
+                );
+                StreamData::Memory(SoundData::new(context, path)?)
+            }
+        };
+        if data.decoder().is_err() {
+            return Err(GameError::AudioError(
+                "Could not decode the given audio data".to_string(),
+            ));
+        }
+        let state = SourceState::new(context, data);
+        let sink = state.output.new_sink();
+        Ok(StreamingSource { sink, state })
+    }
+}
+
+impl SoundSource for StreamingSource {
+    fn play_later(&self) -> GameResult {
+        self.state.play_later(&self.sink)
+    }
+
+    fn play_detached(&mut self) -> GameResult {
+        self.state.play_detached(&mut self.sink)
+    }
+
+    fn set_repeat(&mut self, repeat: bool) {
+        self.state.set_repeat(repeat)
+    }
+
+    fn set_loop_region(
+        &mut self,
+        start: time::Duration,
+        end: Option<time::Duration>,
+    ) -> GameResult {
+        self.state.set_loop_region(start, end)
+    }
+
+    fn loop_region(&self) -> (time::Duration, Option<time::Duration>) {
+        self.state.loop_region()
+    }
+
+    fn set_fade_in(&mut self, dur: time::Duration) {
+        self.state.set_fade_in(dur)
+    }
+
+    fn set_fade_out(&mut self, dur: time::Duration) {
+        self.state.set_fade_out(dur)
+    }
+
+    fn set_pitch(&mut self, ratio: f32) {
+        self.state.set_pitch(ratio)
+    }
+
+    fn repeat(&self) -> bool {
+        self.state.repeat()
+    }
+
+    fn pause(&self) {
+        self.sink.pause()
+    }
+
+    fn resume(&self) {
+        self.sink.play()
+    }
+
+    fn stop(&mut self) {
+        self.state.stop(&mut self.sink)
+    }
+
+    fn seek(&mut self, position: time::Duration) -> GameResult {
+        self.state.seek(&mut self.sink, position)
+    }
+
+    fn duration(&self) -> GameResult<time::Duration> {
+        self.state.duration()
+    }
+
+    fn stopped(&self) -> bool {
+        self.sink.empty()
+    }
+
+    fn volume(&self) -> f32 {
+        self.state.volume()
+    }
+
+    fn set_volume(&mut self, value: f32) {
+        self.state.set_volume(value)
+    }
+
+    fn fade_to(&mut self, value: f32, duration: time::Duration) {
+        self.state.fade_to(&self.sink, value, duration)
+    }
+
+    fn fade_out(&mut self, duration: time::Duration) {
+        self.state.fade_out(&mut self.sink, duration)
+    }
+
+    fn fading(&self) -> bool {
+        self.state.fading()
+    }
+
+    fn paused(&self) -> bool {
+        self.sink.is_paused()
+    }
+
+    fn playing(&self) -> bool {
+        !self.paused() && !self.stopped()
+    }
+
+    fn elapsed(&self) -> time::Duration {
+        self.state.elapsed()
+    }
+
+    fn set_query_interval(&mut self, t: time::Duration) {
+        self.state.set_query_interval(t)
+    }
+
+    fn set_bus(&mut self, bus: &Bus) {
+        self.state.set_bus(bus)
+    }
+}
+
+impl fmt::Debug for StreamingSource {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "<Streaming audio source: {:p}>", self)
+    }
+}
+
+impl Drop for StreamingSource {
+    fn drop(&mut self) {
+        self.state.drop_sink(&mut self.sink)
+    }
+}
+
+/// Where a `SpatialSource` and the listener's ears are, shared with the
+/// sounds it is playing so they move along with it.
+#[derive(Debug, Copy, Clone, PartialEq)]
+struct SpatialPositions {
+    emitter: [f32; 3],
+    left_ear: [f32; 3],
+    right_ear: [f32; 3],
+}
+
 /// A source of audio data located in space relative to a listener's ears.
 /// Will stop playing when dropped.
 pub struct SpatialSource {
-    sink: rodio::SpatialSink,
+    sink: rodio::Sink,
     state: SourceState,
-    left_ear: mint::Point3<f32>,
-    right_ear: mint::Point3<f32>,
-    emitter_position: mint::Point3<f32>,
+    positions: Arc<Mutex<SpatialPositions>>,
 }
 
 impl SpatialSource {
@@ -456,86 +1959,87 @@ impl SpatialSource {
                 "Could not decode the given audio data".to_string(),
             ));
         }
-        let sink = rodio::SpatialSink::new(
-            &context.audio_context.device(),
-            [0.0, 0.0, 0.0],
-            [-1.0, 0.0, 0.0],
-            [1.0, 0.0, 0.0],
-        );
-
-        let cursor = io::Cursor::new(data);
+        let mut state = SourceState::new(context, StreamData::Memory(data));
+        let sink = state.output.new_sink();
+        let positions = Arc::new(Mutex::new(SpatialPositions {
+            emitter: [0.0, 0.0, 0.0],
+            left_ear: [-1.0, 0.0, 0.0],
+            right_ear: [1.0, 0.0, 0.0],
+        }));
+        state.positions = Some(positions.clone());
 
         Ok(SpatialSource {
             sink,
-            state: SourceState::new(cursor),
-            left_ear: [-1.0, 0.0, 0.0].into(),
-            right_ear: [1.0, 0.0, 0.0].into(),
-            emitter_position: [0.0, 0.0, 0.0].into(),
+            state,
+            positions,
         })
     }
+
+    fn positions(&self) -> MutexGuard<SpatialPositions> {
+        self.positions
+            .lock()
+            .expect("SpatialSource positions lock poisoned")
+    }
+}
+
+/// Places a sound in space the way `rodio::SpatialSink` does, but
+/// for playing on a plain sink from any `AudioOutput`.
+fn spatial<S>(
+    positions: &Arc<Mutex<SpatialPositions>>,
+    sound: S,
+) -> impl rodio::Source<Item = S::Item> + Send
+where
+    S: rodio::Source + Send + 'static,
+    S::Item: rodio::Sample + Send + fmt::Debug,
+{
+    use rodio::Source;
+    let start = *positions
+        .lock()
+        .expect("SpatialSource positions lock poisoned");
+    let positions = positions.clone();
+    rodio::source::Spatial::new(sound, start.emitter, start.left_ear, start.right_ear)
+        .periodic_access(time::Duration::from_millis(10), move |spatial| {
+            let p = positions
+                .lock()
+                .expect("SpatialSource positions lock poisoned");
+            spatial.set_positions(p.emitter, p.left_ear, p.right_ear);
+        })
 }
 
 impl SoundSource for SpatialSource {
     /// Plays the `SpatialSource`; waits until done if the sound is currently playing.
     fn play_later(&self) -> GameResult {
-        // Creating a new Decoder each time seems a little messy,
-        // since it may do checking and data-type detection that is
-        // redundant, but it's not super expensive.
-        // See https://github.com/ggez/ggez/issues/98 for discussion
-        use rodio::Source;
-        let cursor = self.state.data.clone();
-
-        let counter = self.state.play_time.clone();
-        let period_mus = self.state.query_interval.as_secs() as usize * 1_000_000
-            + self.state.query_interval.subsec_micros() as usize;
-
-        if self.state.repeat {
-            let sound = rodio::Decoder::new(cursor)?
-                .repeat_infinite()
-                .speed(self.state.speed)
-                .fade_in(self.state.fade_in)
-                .periodic_access(self.state.query_interval, move |_| {
-                    let _ = counter.fetch_add(period_mus, Ordering::SeqCst);
-                });
-            self.sink.append(sound);
-        } else {
-            let sound = rodio::Decoder::new(cursor)?
-                .speed(self.state.speed)
-                .fade_in(self.state.fade_in)
-                .periodic_access(self.state.query_interval, move |_| {
-                    let _ = counter.fetch_add(period_mus, Ordering::SeqCst);
-                });
-            self.sink.append(sound);
-        }
-
-        Ok(())
+        self.state.play_later(&self.sink)
     }
 
     fn play_detached(&mut self) -> GameResult {
-        self.stop();
-        self.play_later()?;
-
-        let device = rodio::default_output_device().unwrap();
-        let new_sink = rodio::SpatialSink::new(
-            &device,
-            self.emitter_position.into(),
-            self.left_ear.into(),
-            self.right_ear.into(),
-        );
-        let old_sink = mem::replace(&mut self.sink, new_sink);
-        old_sink.detach();
-
-        Ok(())
+        self.state.play_detached(&mut self.sink)
     }
 
     fn set_repeat(&mut self, repeat: bool) {
         self.state.set_repeat(repeat)
     }
 
+    fn set_loop_region(
+        &mut self,
+        start: time::Duration,
+        end: Option<time::Duration>,
+    ) -> GameResult {
+        self.state.set_loop_region(start, end)
+    }
+
+    fn loop_region(&self) -> (time::Duration, Option<time::Duration>) {
+        self.state.loop_region()
+    }
+
     fn set_fade_in(&mut self, dur: time::Duration) {
         self.state.set_fade_in(dur)
     }
 
+    fn set_fade_out(&mut self, dur: time::Duration) {
+        self.state.set_fade_out(dur)
+    }
+
     fn set_pitch(&mut self, ratio: f32) {
         self.state.set_pitch(ratio)
     }
@@ -553,31 +2057,15 @@ impl SoundSource for SpatialSource {
     }
 
     fn stop(&mut self) {
-        // Sinks cannot be reused after calling `.stop()`. See
-        // https://github.com/tomaka/rodio/issues/171 for information.
-        // To stop the current sound we have to drop the old sink and
-        // create a new one in its place.
-        // This is most ugly because in order to create a new sink
-        // we need a `device`. However, we can only get the default
-        // device without having access to a context. Currently that's
-        // fine because the `RodioAudioContext` uses the default device too,
-        // but it may cause problems in the future if devices become
-        // customizable.
-
-        // We also need to carry over information from the previous sink.
-        let volume = self.volume();
-
-        let device = rodio::default_output_device().unwrap();
-        self.sink = rodio::SpatialSink::new(
-            &device,
-            self.emitter_position.into(),
-            self.left_ear.into(),
-            self.right_ear.into(),
-        );
-        self.state.play_time.store(0, Ordering::SeqCst);
-
-        // Restore information from the previous link.
-        self.set_volume(volume);
+        self.state.stop(&mut self.sink)
+    }
+
+    fn seek(&mut self, position: time::Duration) -> GameResult {
+        self.state.seek(&mut self.sink, position)
+    }
+
+    fn duration(&self) -> GameResult<time::Duration> {
+        self.state.duration()
     }
 
     fn stopped(&self) -> bool {
@@ -585,11 +2073,23 @@ impl SoundSource for SpatialSource {
     }
 
     fn volume(&self) -> f32 {
-        self.sink.volume()
+        self.state.volume()
     }
 
     fn set_volume(&mut self, value: f32) {
-        self.sink.set_volume(value)
+        self.state.set_volume(value)
+    }
+
+    fn fade_to(&mut self, value: f32, duration: time::Duration) {
+        self.state.fade_to(&self.sink, value, duration)
+    }
+
+    fn fade_out(&mut self, duration: time::Duration) {
+        self.state.fade_out(&mut self.sink, duration)
+    }
+
+    fn fading(&self) -> bool {
+        self.state.fading()
     }
 
     fn paused(&self) -> bool {
@@ -607,6 +2107,10 @@ impl SoundSource for SpatialSource {
     fn set_query_interval(&mut self, t: time::Duration) {
         self.state.set_query_interval(t)
     }
+
+    fn set_bus(&mut self, bus: &Bus) {
+        self.state.set_bus(bus)
+    }
 }
 
 impl SpatialSource {
@@ -615,8 +2119,8 @@ impl SpatialSource {
     where
         P: Into<mint::Point3<f32>>,
     {
-        self.emitter_position = pos.into();
-        self.sink.set_emitter_position(self.emitter_position.into());
+        let pos: mint::Point3<f32> = pos.into();
+        self.positions().emitter = pos.into();
     }
 
     /// Set locations of the listener's ears
@@ -624,10 +2128,11 @@ impl SpatialSource {
     where
         P: Into<mint::Point3<f32>>,
     {
-        self.left_ear = left.into();
-        self.right_ear = right.into();
-        self.sink.set_left_ear_position(self.left_ear.into());
-        self.sink.set_right_ear_position(self.right_ear.into());
+        let left: mint::Point3<f32> = left.into();
+        let right: mint::Point3<f32> = right.into();
+        let mut positions = self.positions();
+        positions.left_ear = left.into();
+        positions.right_ear = right.into();
     }
 }
 
@@ -636,3 +2141,9 @@ impl fmt::Debug for SpatialSource {
         write!(f, "<Spatial audio source: {:p}>", self)
     }
 }
+
+impl Drop for SpatialSource {
+    fn drop(&mut self) {
+        self.state.drop_sink(&mut self.sink)
+    }
+}
@@ -15,7 +15,7 @@
 use std::io;
 use toml;
 
-use crate::error::GameResult;
+use crate::error::{GameError, GameResult};
 
 /// Possible fullscreen modes.
 #[derive(Debug, Copy, Clone, Serialize, Deserialize, PartialEq, Eq)]
@@ -362,6 +362,126 @@ impl ModuleConf {
     }
 }
 
+/// How [`event::run()`](../event/fn.run.html) schedules calls to
+/// [`EventHandler::update()`](../event/trait.EventHandler.html#tymethod.update)
+/// and [`EventHandler::draw()`](../event/trait.EventHandler.html#tymethod.draw).
+///
+/// With a fixed timestep, frame time is saved up in an accumulator and
+/// `update()` is called once for every full step in it, so the game logic
+/// always advances by the same amount of time no matter the framerate.
+/// If the game falls too far behind (say, because the window was dragged
+/// or the machine hitched), it runs at most `max_updates_per_frame`
+/// updates and throws away the rest of the backlog rather than trying
+/// to catch up forever.
+///
+/// Defaults:
+///
+/// ```rust
+/// # use ggez::conf::*;
+/// # fn main() { assert_eq!(
+/// LoopMode::Variable
+/// # , LoopMode::default()); }
+/// ```
+#[derive(Debug, Copy, Clone, Serialize, Deserialize, PartialEq, Eq, SmartDefault)]
+#[serde(tag = "type")]
+pub enum LoopMode {
+    /// Call `update()` and then `draw()` once per frame, as fast as
+    /// possible.  Use [`timer::delta()`](../timer/fn.delta.html) to find out
+    /// how much time passed.
+    #[default]
+    Variable,
+    /// Call `update()` at a fixed rate, zero or more times per frame,
+    /// then `draw()` once.
+    Fixed {
+        /// How many times per second to call `update()`.  Must be at
+        /// least 1.
+        updates_per_second: u32,
+        /// The most `update()` calls to make in a single frame.  Must be
+        /// at least 1.
+        max_updates_per_frame: u32,
+    },
+    /// Like `Fixed`, but calls
+    /// [`EventHandler::draw_interpolated()`](../event/trait.EventHandler.html#method.draw_interpolated)
+    /// with how far the game is between the last update and the next,
+    /// so drawing can be smoothed out by interpolating between them.
+    Interpolated {
+        /// How many times per second to call `update()`.  Must be at
+        /// least 1.
+        updates_per_second: u32,
+        /// The most `update()` calls to make in a single frame.  Must be
+        /// at least 1.
+        max_updates_per_frame: u32,
+    },
+}
+
+impl LoopMode {
+    /// A `LoopMode::Fixed` with the given update rate, allowing up to
+    /// 5 updates per frame.
+    pub fn fixed(updates_per_second: u32) -> Self {
+        LoopMode::Fixed {
+            updates_per_second,
+            max_updates_per_frame: 5,
+        }
+    }
+
+    /// A `LoopMode::Interpolated` with the given update rate, allowing
+    /// up to 5 updates per frame.
+    pub fn interpolated(updates_per_second: u32) -> Self {
+        LoopMode::Interpolated {
+            updates_per_second,
+            max_updates_per_frame: 5,
+        }
+    }
+
+    /// Set the most updates to run in a single frame before
+    /// dropping the rest.  Does nothing for `LoopMode::Variable`.
+    pub fn max_updates_per_frame(self, max: u32) -> Self {
+        match self {
+            LoopMode::Variable => LoopMode::Variable,
+            LoopMode::Fixed {
+                updates_per_second, ..
+            } => LoopMode::Fixed {
+                updates_per_second,
+                max_updates_per_frame: max,
+            },
+            LoopMode::Interpolated {
+                updates_per_second, ..
+            } => LoopMode::Interpolated {
+                updates_per_second,
+                max_updates_per_frame: max,
+            },
+        }
+    }
+
+    /// Returns an error if the update rate or the most updates per
+    /// frame is 0, either of which would never update.
+    pub(crate) fn validate(&self) -> GameResult {
+        match *self {
+            LoopMode::Variable => Ok(()),
+            LoopMode::Fixed {
+                updates_per_second,
+                max_updates_per_frame,
+            }
+            | LoopMode::Interpolated {
+                updates_per_second,
+                max_updates_per_frame,
+            } => {
+                if updates_per_second == 0 {
+                    Err(GameError::ConfigError(String::from(
+                        "loop_mode.updates_per_second must be at least 1",
+                    )))
+                } else if max_updates_per_frame == 0 {
+                    Err(GameError::ConfigError(String::from(
+                        "loop_mode.max_updates_per_frame must be at least 1",
+                    )))
+                } else {
+                    Ok(())
+                }
+            }
+        }
+    }
+}
+
 /// A structure containing configuration data
 /// for the game engine.
 ///
@@ -371,15 +491,25 @@ impl ModuleConf {
 /// # use ggez::conf::*;
 /// # fn main() { assert_eq!(
 /// Conf {
+///     headless: false,
 ///     window_mode: WindowMode::default(),
 ///     window_setup: WindowSetup::default(),
 ///     backend: Backend::default(),
 ///     modules: ModuleConf::default(),
+///     loop_mode: LoopMode::default(),
 /// }
 /// # , Conf::default()); }
 /// ```
 #[derive(Serialize, Deserialize, Debug, PartialEq, SmartDefault, Clone)]
 pub struct Conf {
+    // The TOML serializer can't write plain values after tables,
+    // so those have to go first.
+    /// Whether to run without a window, rendering into an offscreen
+    /// target instead.  See
+    /// [`ContextBuilder::build_headless()`](../struct.ContextBuilder.html#method.build_headless).
+    #[serde(default)]
+    #[default = false]
+    pub headless: bool,
     /// Window setting information that can be set at runtime
     pub window_mode: WindowMode,
     /// Window setting information that must be set at init-time
@@ -388,6 +518,9 @@ pub struct Conf {
     pub backend: Backend,
     /// Which modules to enable.
     pub modules: ModuleConf,
+    /// How the main loop calls `update()` and `draw()`.
+    #[serde(default)]
+    pub loop_mode: LoopMode,
 }
 
 impl Conf {
@@ -430,6 +563,18 @@ impl Conf {
         self.modules = modules;
         self
     }
+
+    /// Sets the loop mode
+    pub fn loop_mode(mut self, loop_mode: LoopMode) -> Self {
+        self.loop_mode = loop_mode;
+        self
+    }
+
+    /// Sets whether to run headless, without a window
+    pub fn headless(mut self, headless: bool) -> Self {
+        self.headless = headless;
+        self
+    }
 }
 
 #[cfg(test)]
@@ -447,4 +592,15 @@ mod tests {
         let c2 = conf::Conf::from_toml_file(&mut reader).unwrap();
         assert_eq!(c1, c2);
     }
+
+    #[test]
+    fn loop_mode_encode_round_trip() {
+        let mode = conf::LoopMode::interpolated(30).max_updates_per_frame(2);
+        let c1 = conf::Conf::new().loop_mode(mode);
+        let mut writer = Vec::new();
+        c1.to_toml_file(&mut writer).unwrap();
+        let mut reader = writer.as_slice();
+        let c2 = conf::Conf::from_toml_file(&mut reader).unwrap();
+        assert_eq!(c1, c2);
+    }
 }
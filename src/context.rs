@@ -3,13 +3,14 @@ use std::fmt;
 /// without having to mess around figuring it out.
 pub use winit;
 
+use crate::assets;
 use crate::audio;
 use crate::conf;
-use crate::error::GameResult;
+use crate::error::{GameError, GameResult};
 use crate::event::winit_event;
 use crate::filesystem::Filesystem;
 use crate::graphics::{self, Point2};
-use crate::input::{gamepad, keyboard, mouse};
+use crate::input::{gamepad, keyboard, mouse, replay};
 use crate::timer;
 
 /// A `Context` is an object that holds on to global resources.
@@ -50,6 +51,14 @@ pub struct Context {
     pub mouse_context: mouse::MouseContext,
     /// Gamepad context
     pub gamepad_context: Box<dyn gamepad::GamepadContext>,
+    /// Gamepad buttons pressed or released this frame
+    pub(crate) gamepad_transitions: gamepad::GamepadTransitions,
+    /// Input recording in progress, if any
+    pub(crate) recorder: Option<replay::Recorder>,
+    /// Assets to reload when their files change
+    pub(crate) asset_watcher: assets::AssetWatcher,
+    /// Assets being loaded in the background
+    pub(crate) asset_loader: assets::AssetLoader,
 
     /// The Conf object the Context was created with.
     /// It's here just so that we can see the original settings,
@@ -75,19 +84,25 @@ impl fmt::Debug for Context {
 impl Context {
     /// Tries to create a new Context using settings from the given [`Conf`](../conf/struct.Conf.html) object.
     /// Usually called by [`ContextBuilder::build()`](struct.ContextBuilder.html#method.build).
-    fn from_conf(conf: conf::Conf, mut fs: Filesystem) -> GameResult<(Context, winit::EventsLoop)> {
+    /// A headless context is made without an events loop.
+    fn from_conf(
+        conf: conf::Conf,
+        mut fs: Filesystem,
+        events_loop: Option<&winit::EventsLoop>,
+    ) -> GameResult<Context> {
         let debug_id = DebugId::new();
-        let audio_context: Box<dyn audio::AudioContext> = if conf.modules.audio {
+        // A headless context shouldn't need a sound card any more than
+        // it needs a screen, so its sounds play silently.
+        let audio_context: Box<dyn audio::AudioContext> = if conf.modules.audio && !conf.headless {
             Box::new(audio::RodioAudioContext::new()?)
         } else {
             Box::new(audio::NullAudioContext::default())
         };
-        let events_loop = winit::EventsLoop::new();
         let timer_context = timer::TimeContext::new();
         let backend_spec = graphics::GlBackendSpec::from(conf.backend);
         let graphics_context = graphics::context::GraphicsContext::new(
             &mut fs,
-            &events_loop,
+            events_loop,
             &conf.window_setup,
             conf.window_mode,
             backend_spec,
@@ -110,12 +125,16 @@ impl Context {
             audio_context,
             keyboard_context,
             gamepad_context,
+            gamepad_transitions: gamepad::GamepadTransitions::default(),
             mouse_context,
+            recorder: None,
+            asset_watcher: assets::AssetWatcher::default(),
+            asset_loader: assets::AssetLoader::default(),
 
             debug_id,
         };
 
-        Ok((ctx, events_loop))
+        Ok(ctx)
     }
 
     // TODO LATER: This should be a function in `ggez::event`, per the
@@ -129,17 +148,18 @@ impl Context {
         match event.clone() {
             winit_event::Event::WindowEvent { event, .. } => match event {
                 winit_event::WindowEvent::Resized(logical_size) => {
-                    let hidpi_factor = self.gfx_context.window.get_hidpi_factor();
-                    let physical_size = logical_size.to_physical(hidpi_factor as f64);
-                    self.gfx_context.window.resize(physical_size);
+                    if let Some(window) = self.gfx_context.window.as_ref() {
+                        let hidpi_factor = window.get_hidpi_factor();
+                        let physical_size = logical_size.to_physical(hidpi_factor as f64);
+                        window.resize(physical_size);
+                    }
                     self.gfx_context.resize_viewport();
                 }
                 winit_event::WindowEvent::CursorMoved {
                     position: logical_position,
                     ..
                 } => {
-                    let window = &self.gfx_context.window;
-                    let dpi = window.get_hidpi_factor();
+                    let dpi = self.gfx_context.hidpi_factor();
 
                     self.mouse_context.set_last_position(Point2::new(
                         (logical_position.x * dpi) as f32,
@@ -186,6 +206,23 @@ impl Context {
             _ => (),
         };
     }
+
+    /// Like [`process_event()`](#method.process_event), but for the
+    /// events of the `gamepad_context`, so functions like
+    /// `gamepad::button_just_pressed()` work.  If you are rolling your
+    /// own event loop, call this on each event you take from
+    /// `gamepad_context.next_event()`.
+    pub fn process_gamepad_event(&mut self, event: &gamepad::GamepadEvent) {
+        match *event {
+            gamepad::GamepadEvent::ButtonPressed(id, button) => {
+                self.gamepad_transitions.set_button(id, button, true);
+            }
+            gamepad::GamepadEvent::ButtonReleased(id, button) => {
+                self.gamepad_transitions.set_button(id, button, false);
+            }
+            _ => (),
+        }
+    }
 }
 
 use std::borrow::Cow;
@@ -239,6 +276,13 @@ impl ContextBuilder {
         self
     }
 
+    /// Sets how [`event::run()`](event/fn.run.html) calls `update()`
+    /// and `draw()`; see [`conf::LoopMode`](conf/enum.LoopMode.html).
+    pub fn loop_mode(mut self, loop_mode: conf::LoopMode) -> Self {
+        self.conf.loop_mode = loop_mode;
+        self
+    }
+
     /// Sets all the config options, overriding any previous
     /// ones from [`window_setup()`](#method.window_setup),
     /// [`window_mode()`](#method.window_mode), and
@@ -287,7 +331,51 @@ impl ContextBuilder {
     }
 
     /// Build the `Context`.
+    ///
+    /// Returns an error if the config asks for a headless context, since
+    /// that has no events loop; build one with
+    /// [`build_headless()`](#method.build_headless) instead.
     pub fn build(self) -> GameResult<(Context, winit::EventsLoop)> {
+        let (conf, fs) = self.load()?;
+        if conf.headless {
+            return Err(GameError::ConfigError(
+                "The config asks for a headless Context, which has no events loop; \
+                 use ContextBuilder::build_headless() to build it"
+                    .to_string(),
+            ));
+        }
+        let events_loop = winit::EventsLoop::new();
+        let ctx = Context::from_conf(conf, fs, Some(&events_loop))?;
+        Ok((ctx, events_loop))
+    }
+
+    /// Build a headless `Context`, which opens no window and needs no
+    /// display server.  Everything is drawn into an offscreen render
+    /// target the size of [`WindowMode`](conf/struct.WindowMode.html)'s
+    /// dimensions, which you can read back with
+    /// [`graphics::screenshot()`](graphics/fn.screenshot.html).
+    ///
+    /// This is mainly useful for running rendering tests on machines
+    /// with no monitor or GPU.  It still needs an OpenGL implementation,
+    /// though a software one such as Mesa's `llvmpipe` works fine; on
+    /// Linux and the BSDs the context comes from EGL, without connecting
+    /// to an X or Wayland server.  Functions that only make sense with a
+    /// window, such as [`graphics::window()`](graphics/fn.window.html),
+    /// will panic, and there are no window events, so run the game with
+    /// [`event::run_headless()`](event/fn.run_headless.html).
+    ///
+    /// It doesn't need a sound card either: sounds play silently as the
+    /// game clock moves on.  To hear what was played, swap in an
+    /// [`audio::OfflineAudioContext`](audio/struct.OfflineAudioContext.html).
+    pub fn build_headless(self) -> GameResult<Context> {
+        let (mut conf, fs) = self.load()?;
+        conf.headless = true;
+        Context::from_conf(conf, fs, None)
+    }
+
+    /// Sets up the filesystem and reads the config file, if there is one,
+    /// and checks the resulting config makes sense.
+    fn load(self) -> GameResult<(conf::Conf, Filesystem)> {
         let mut fs = Filesystem::new(self.game_id.as_ref(), self.author.as_ref())?;
 
         for path in &self.paths {
@@ -303,8 +391,9 @@ impl ContextBuilder {
         } else {
             self.conf
         };
+        config.loop_mode.validate()?;
 
-        Context::from_conf(config, fs)
+        Ok((config, fs))
     }
 }
 
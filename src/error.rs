@@ -103,6 +103,13 @@ impl From<toml::ser::Error> for GameError {
     }
 }
 
+impl From<serde_json::Error> for GameError {
+    fn from(e: serde_json::Error) -> GameError {
+        let errstr = format!("JSON decode error: {}", e);
+        GameError::ResourceLoadError(errstr)
+    }
+}
+
 impl From<zip::result::ZipError> for GameError {
     fn from(e: zip::result::ZipError) -> GameError {
         let errstr = format!("Zip error: {}", e.description());
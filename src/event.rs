@@ -11,6 +11,7 @@
 //! example](https://github.com/ggez/ggez/blob/master/examples/eventloop.rs).
 
 use gilrs;
+use std::path::Path;
 use winit::{self, dpi};
 
 // TODO LATER: I kinda hate all these re-exports.  I kinda hate
@@ -32,6 +33,7 @@ pub mod winit_event {
         TouchPhase, WindowEvent,
     };
 }
+use crate::input::gamepad::GamepadEvent;
 pub use crate::input::gamepad::GamepadId;
 pub use crate::input::keyboard::{KeyCode, KeyMods};
 
@@ -39,8 +41,15 @@ use self::winit_event::*;
 /// `winit` event loop.
 pub use winit::EventsLoop;
 
+use crate::assets;
+use crate::audio;
+use crate::conf::LoopMode;
 use crate::context::Context;
 use crate::error::GameResult;
+use crate::graphics;
+use crate::input;
+use crate::input::replay::InputEvent;
+use crate::timer;
 
 /// A trait defining event callbacks.  This is your primary interface with
 /// `ggez`'s event loop.  Implement this trait for a type and
@@ -64,6 +73,18 @@ pub trait EventHandler {
     /// maybe [`timer::yield_now()`](../timer/fn.yield_now.html).
     fn draw(&mut self, _ctx: &mut Context) -> GameResult;
 
+    /// Called instead of [`draw()`](#tymethod.draw) when running with
+    /// [`LoopMode::Interpolated`](../conf/enum.LoopMode.html#variant.Interpolated).
+    /// `alpha` is how far along the game is between the last
+    /// `update()` and the next one, from 0.0 to 1.0, so you can draw
+    /// objects at `previous + (current - previous) * alpha` and get
+    /// smooth motion even when the update rate is lower than the framerate.
+    ///
+    /// The default implementation just calls `draw()`.
+    fn draw_interpolated(&mut self, ctx: &mut Context, _alpha: f32) -> GameResult {
+        self.draw(ctx)
+    }
+
     /// A mouse button was pressed
     fn mouse_button_down_event(
         &mut self,
@@ -133,6 +154,12 @@ pub trait EventHandler {
     fn gamepad_axis_event(&mut self, _ctx: &mut Context, _axis: Axis, _value: f32, _id: GamepadId) {
     }
 
+    /// A gamepad was plugged in; `id` identifies which gamepad.
+    fn gamepad_connected_event(&mut self, _ctx: &mut Context, _id: GamepadId) {}
+
+    /// A gamepad was unplugged; `id` identifies which gamepad.
+    fn gamepad_disconnected_event(&mut self, _ctx: &mut Context, _id: GamepadId) {}
+
     /// Called when the window is shown or hidden.
     fn focus_event(&mut self, _ctx: &mut Context, _gained: bool) {}
 
@@ -146,6 +173,12 @@ pub trait EventHandler {
     /// Called when the user resizes the window, or when it is resized
     /// via [`graphics::set_mode()`](../graphics/fn.set_mode.html).
     fn resize_event(&mut self, _ctx: &mut Context, _width: f32, _height: f32) {}
+
+    /// Called when an asset loaded through an
+    /// [`assets::Handle`](../assets/struct.Handle.html) has been
+    /// reloaded because its file changed.  `path` is the path it
+    /// was loaded from.
+    fn asset_reloaded_event(&mut self, _ctx: &mut Context, _path: &Path) {}
 }
 
 /// Terminates the [`ggez::event::run()`](fn.run.html) loop by setting
@@ -160,128 +193,275 @@ pub fn quit(ctx: &mut Context) {
 ///
 /// It does not try to do any type of framerate limiting.  See the
 /// documentation for the [`timer`](../timer/index.html) module for more info.
+///
+/// How often `update()` gets called is set by the
+/// [`LoopMode`](../conf/enum.LoopMode.html) in the `Context`'s `Conf`.
 pub fn run<S>(ctx: &mut Context, events_loop: &mut EventsLoop, state: &mut S) -> GameResult
 where
     S: EventHandler,
 {
-    use crate::input::{keyboard, mouse};
+    run_loop(ctx, state, |ctx, state| {
+        events_loop.poll_events(|event| {
+            ctx.process_event(&event);
+            if let Some(input_event) = translate_event(ctx, &event) {
+                dispatch_event(ctx, state, input_event);
+            }
+        });
+    })
+}
 
+/// Runs the main loop of a headless `Context`, made with
+/// [`ContextBuilder::build_headless()`](../struct.ContextBuilder.html#method.build_headless),
+/// the same way as [`run()`](fn.run.html) does, except that with no
+/// window there are no window events.
+pub fn run_headless<S>(ctx: &mut Context, state: &mut S) -> GameResult
+where
+    S: EventHandler,
+{
+    run_loop(ctx, state, |_, _| ())
+}
+
+/// The main loop, which calls `poll_events` every frame to pass on the
+/// events of the window, if there is one.
+fn run_loop<S, F>(ctx: &mut Context, state: &mut S, mut poll_events: F) -> GameResult
+where
+    S: EventHandler,
+    F: FnMut(&mut Context, &mut S),
+{
     while ctx.continuing {
         // If you are writing your own event loop, make sure
-        // you include `timer_context.tick()` and
-        // `ctx.process_event()` calls.  These update ggez's
+        // you include `timer_context.tick()`,
+        // `ctx.process_event()` and `ctx.process_gamepad_event()` calls.  These update ggez's
         // internal state however necessary.
-        ctx.timer_context.tick();
-        events_loop.poll_events(|event| {
-            ctx.process_event(&event);
-            match event {
-                Event::WindowEvent { event, .. } => match event {
-                    WindowEvent::Resized(logical_size) => {
-                        let window = &ctx.gfx_context.window;
-                        let dpi = window.get_hidpi_factor();
-
-                        state.resize_event(
-                            ctx,
-                            (logical_size.width * dpi) as f32,
-                            (logical_size.height * dpi) as f32,
-                        );
-                    }
-                    WindowEvent::CloseRequested => {
-                        if !state.quit_event(ctx) {
-                            quit(ctx);
-                        }
-                    }
-                    WindowEvent::Focused(gained) => {
-                        state.focus_event(ctx, gained);
-                    }
-                    WindowEvent::ReceivedCharacter(ch) => {
-                        state.text_input_event(ctx, ch);
-                    }
-                    WindowEvent::KeyboardInput {
-                        input:
-                            KeyboardInput {
-                                state: ElementState::Pressed,
-                                virtual_keycode: Some(keycode),
-                                modifiers,
-                                ..
-                            },
-                        ..
-                    } => {
-                        let repeat = keyboard::is_key_repeated(ctx);
-                        state.key_down_event(ctx, keycode, modifiers.into(), repeat);
-                    }
-                    WindowEvent::KeyboardInput {
-                        input:
-                            KeyboardInput {
-                                state: ElementState::Released,
-                                virtual_keycode: Some(keycode),
-                                modifiers,
-                                ..
-                            },
+        begin_frame(ctx, state);
+        poll_events(ctx, state);
+        // Handle gamepad events.
+        while let Some(event) = ctx.gamepad_context.next_event() {
+            ctx.process_gamepad_event(&event);
+            let input_event = match event {
+                GamepadEvent::Connected(id) => InputEvent::GamepadConnected { id },
+                GamepadEvent::Disconnected(id) => InputEvent::GamepadDisconnected { id },
+                GamepadEvent::ButtonPressed(id, button) => {
+                    InputEvent::GamepadButtonDown { button, id }
+                }
+                GamepadEvent::ButtonReleased(id, button) => {
+                    InputEvent::GamepadButtonUp { button, id }
+                }
+                GamepadEvent::AxisChanged(id, axis, value) => {
+                    InputEvent::GamepadAxis { axis, value, id }
+                }
+            };
+            dispatch_event(ctx, state, input_event);
+        }
+        run_frame(ctx, state)?;
+    }
+
+    Ok(())
+}
+
+/// Does everything that happens at the start of a frame, before any
+/// events are dispatched: moving the timer along, forgetting last
+/// frame's key and button transitions, reloading changed shaders and
+/// assets, finishing background loading and moving audio along.
+pub(crate) fn begin_frame<S>(ctx: &mut Context, state: &mut S)
+where
+    S: EventHandler,
+{
+    ctx.timer_context.tick();
+    input::reset_transitions(ctx);
+    graphics::reload_changed_shaders(ctx);
+    for path in assets::reload_changed_assets(ctx) {
+        state.asset_reloaded_event(ctx, &path);
+    }
+    assets::update_loading(ctx);
+    audio::update(ctx);
+}
+
+/// Turns a `winit` event into the `InputEvent` that should be passed
+/// on to the `EventHandler`, if any.  Expects `ctx.process_event()` to
+/// have been called on the event already.
+fn translate_event(ctx: &Context, event: &Event) -> Option<InputEvent> {
+    use crate::input::{keyboard, mouse};
+
+    match *event {
+        Event::WindowEvent { ref event, .. } => match *event {
+            WindowEvent::Resized(logical_size) => {
+                let dpi = ctx.gfx_context.hidpi_factor();
+                Some(InputEvent::Resize {
+                    width: (logical_size.width * dpi) as f32,
+                    height: (logical_size.height * dpi) as f32,
+                })
+            }
+            WindowEvent::CloseRequested => Some(InputEvent::Quit),
+            WindowEvent::Focused(gained) => Some(InputEvent::Focus { gained }),
+            WindowEvent::ReceivedCharacter(character) => Some(InputEvent::TextInput { character }),
+            WindowEvent::KeyboardInput {
+                input:
+                    KeyboardInput {
+                        state: ElementState::Pressed,
+                        virtual_keycode: Some(keycode),
+                        modifiers,
                         ..
-                    } => {
-                        state.key_up_event(ctx, keycode, modifiers.into());
-                    }
-                    WindowEvent::MouseWheel { delta, .. } => {
-                        let (x, y) = match delta {
-                            MouseScrollDelta::LineDelta(x, y) => (x, y),
-                            MouseScrollDelta::PixelDelta(dpi::LogicalPosition { x, y }) => {
-                                (x as f32, y as f32)
-                            }
-                        };
-                        state.mouse_wheel_event(ctx, x, y);
-                    }
-                    WindowEvent::MouseInput {
-                        state: element_state,
-                        button,
+                    },
+                ..
+            } => Some(InputEvent::KeyDown {
+                keycode,
+                keymods: modifiers.into(),
+                repeat: keyboard::is_key_repeated(ctx),
+            }),
+            WindowEvent::KeyboardInput {
+                input:
+                    KeyboardInput {
+                        state: ElementState::Released,
+                        virtual_keycode: Some(keycode),
+                        modifiers,
                         ..
-                    } => {
-                        let position = mouse::position(ctx);
-                        match element_state {
-                            ElementState::Pressed => {
-                                state.mouse_button_down_event(ctx, button, position.x, position.y)
-                            }
-                            ElementState::Released => {
-                                state.mouse_button_up_event(ctx, button, position.x, position.y)
-                            }
-                        }
-                    }
-                    WindowEvent::CursorMoved { .. } => {
-                        let position = mouse::position(ctx);
-                        let delta = mouse::delta(ctx);
-                        state.mouse_motion_event(ctx, position.x, position.y, delta.x, delta.y);
+                    },
+                ..
+            } => Some(InputEvent::KeyUp {
+                keycode,
+                keymods: modifiers.into(),
+            }),
+            WindowEvent::MouseWheel { delta, .. } => {
+                let (x, y) = match delta {
+                    MouseScrollDelta::LineDelta(x, y) => (x, y),
+                    MouseScrollDelta::PixelDelta(dpi::LogicalPosition { x, y }) => {
+                        (x as f32, y as f32)
                     }
-                    _x => {
-                        // trace!("ignoring window event {:?}", x);
-                    }
-                },
-                Event::DeviceEvent { event, .. } => match event {
-                    _ => (),
-                },
-                Event::Awakened => (),
-                Event::Suspended(_) => (),
+                };
+                Some(InputEvent::MouseWheel { x, y })
             }
-        });
-        // Handle gamepad events if necessary.
-        if ctx.conf.modules.gamepad {
-            while let Some(gilrs::Event { id, event, .. }) = ctx.gamepad_context.next_event() {
-                match event {
-                    gilrs::EventType::ButtonPressed(button, _) => {
-                        state.gamepad_button_down_event(ctx, button, GamepadId(id));
-                    }
-                    gilrs::EventType::ButtonReleased(button, _) => {
-                        state.gamepad_button_up_event(ctx, button, GamepadId(id));
-                    }
-                    gilrs::EventType::AxisChanged(axis, value, _) => {
-                        state.gamepad_axis_event(ctx, axis, value, GamepadId(id));
-                    }
-                    _ => {}
+            WindowEvent::MouseInput {
+                state: element_state,
+                button,
+                ..
+            } => {
+                let position = mouse::position(ctx);
+                let (x, y) = (position.x, position.y);
+                match element_state {
+                    ElementState::Pressed => Some(InputEvent::MouseButtonDown { button, x, y }),
+                    ElementState::Released => Some(InputEvent::MouseButtonUp { button, x, y }),
                 }
             }
+            WindowEvent::CursorMoved { .. } => {
+                let position = mouse::position(ctx);
+                let delta = mouse::delta(ctx);
+                Some(InputEvent::MouseMotion {
+                    x: position.x,
+                    y: position.y,
+                    dx: delta.x,
+                    dy: delta.y,
+                })
+            }
+            _ => {
+                // trace!("ignoring window event {:?}", x);
+                None
+            }
+        },
+        Event::DeviceEvent { .. } => None,
+        Event::Awakened => None,
+        Event::Suspended(_) => None,
+    }
+}
+
+/// Passes an `InputEvent` on to the matching `EventHandler` callback,
+/// recording it first if an input recording is in progress.
+pub(crate) fn dispatch_event<S>(ctx: &mut Context, state: &mut S, event: InputEvent)
+where
+    S: EventHandler,
+{
+    let tick = timer::ticks(ctx);
+    if let Some(recorder) = ctx.recorder.as_mut() {
+        recorder.record(tick, event);
+    }
+    match event {
+        InputEvent::KeyDown {
+            keycode,
+            keymods,
+            repeat,
+        } => state.key_down_event(ctx, keycode, keymods, repeat),
+        InputEvent::KeyUp { keycode, keymods } => state.key_up_event(ctx, keycode, keymods),
+        InputEvent::TextInput { character } => state.text_input_event(ctx, character),
+        InputEvent::MouseButtonDown { button, x, y } => {
+            state.mouse_button_down_event(ctx, button, x, y)
+        }
+        InputEvent::MouseButtonUp { button, x, y } => {
+            state.mouse_button_up_event(ctx, button, x, y)
+        }
+        InputEvent::MouseMotion { x, y, dx, dy } => state.mouse_motion_event(ctx, x, y, dx, dy),
+        InputEvent::MouseWheel { x, y } => state.mouse_wheel_event(ctx, x, y),
+        InputEvent::GamepadButtonDown { button, id } => {
+            state.gamepad_button_down_event(ctx, button, id)
+        }
+        InputEvent::GamepadButtonUp { button, id } => {
+            state.gamepad_button_up_event(ctx, button, id)
+        }
+        InputEvent::GamepadAxis { axis, value, id } => {
+            state.gamepad_axis_event(ctx, axis, value, id)
+        }
+        InputEvent::GamepadConnected { id } => state.gamepad_connected_event(ctx, id),
+        InputEvent::GamepadDisconnected { id } => state.gamepad_disconnected_event(ctx, id),
+        InputEvent::Resize { width, height } => state.resize_event(ctx, width, height),
+        InputEvent::Focus { gained } => state.focus_event(ctx, gained),
+        InputEvent::Quit => {
+            if !state.quit_event(ctx) {
+                quit(ctx);
+            }
+        }
+    }
+}
+
+/// Calls `update()` and `draw()` for one frame, as many times
+/// as the `Context`'s `LoopMode` says to.
+pub(crate) fn run_frame<S>(ctx: &mut Context, state: &mut S) -> GameResult
+where
+    S: EventHandler,
+{
+    match ctx.conf.loop_mode {
+        LoopMode::Variable => {
+            state.update(ctx)?;
+            state.draw(ctx)?;
+        }
+        LoopMode::Fixed {
+            updates_per_second,
+            max_updates_per_frame,
+        } => {
+            run_fixed_updates(ctx, state, updates_per_second, max_updates_per_frame)?;
+            state.draw(ctx)?;
+        }
+        LoopMode::Interpolated {
+            updates_per_second,
+            max_updates_per_frame,
+        } => {
+            run_fixed_updates(ctx, state, updates_per_second, max_updates_per_frame)?;
+            let remaining = timer::duration_to_f64(timer::remaining_update_time(ctx));
+            let alpha = (remaining * f64::from(updates_per_second)).min(1.0);
+            state.draw_interpolated(ctx, alpha as f32)?;
         }
-        state.update(ctx)?;
-        state.draw(ctx)?;
     }
+    Ok(())
+}
 
+/// Calls `update()` once for every whole timestep that has built up
+/// since the last frame, dropping the backlog if there's more than
+/// `max_updates` of them.
+fn run_fixed_updates<S>(
+    ctx: &mut Context,
+    state: &mut S,
+    updates_per_second: u32,
+    max_updates: u32,
+) -> GameResult
+where
+    S: EventHandler,
+{
+    let mut updates = 0;
+    while timer::check_update_time(ctx, updates_per_second) {
+        if updates >= max_updates {
+            ctx.timer_context.discard_residual_update_time();
+            break;
+        }
+        state.update(ctx)?;
+        updates += 1;
+    }
     Ok(())
 }
@@ -30,6 +30,7 @@ use std::env;
 use std::fmt;
 use std::io;
 use std::path;
+use std::time::SystemTime;
 
 use directories::ProjectDirs;
 
@@ -244,6 +245,33 @@ impl Filesystem {
             .unwrap_or(false)
     }
 
+    /// Returns when the file at the given path was last modified and how
+    /// long it is, or `None` if it doesn't exist or isn't on the physical
+    /// filesystem, such as files in `resources.zip`.  Some filesystems
+    /// only keep modification times to the second, so the length helps
+    /// tell apart changes made within one.
+    pub(crate) fn stamp<P: AsRef<path::Path>>(&self, path: P) -> Option<(SystemTime, u64)> {
+        let metadata = self.vfs.metadata(path.as_ref()).ok()?;
+        metadata
+            .modified()
+            .map(|modified| (modified, metadata.len()))
+    }
+
+    /// Returns where the file at the given path really is on disk, if
+    /// it is opened from the physical filesystem rather than from a zip
+    /// file, so it can be read without going through the `Filesystem`.
+    pub(crate) fn physical_path<P: AsRef<path::Path>>(&self, path: P) -> Option<path::PathBuf> {
+        self.vfs.physical_path(path.as_ref())
+    }
+
+    /// Returns where the bytes of the file at the given path are on
+    /// disk, if they can be read straight from there, which is true for
+    /// files in the physical filesystem and for files stored without
+    /// compression in a zip file.
+    pub(crate) fn locate<P: AsRef<path::Path>>(&self, path: P) -> Option<vfs::FileLocation> {
+        self.vfs.locate(path.as_ref())
+    }
+
     /// Returns a list of all files and directories in the resource directory,
     /// in no particular order.
     ///
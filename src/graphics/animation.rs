@@ -0,0 +1,599 @@
+//! Sprite sheet animation.
+//!
+//! An [`Animation`](struct.Animation.html) is a list of frames, each one a
+//! `src` rectangle in an image and how long to show it for.  An
+//! [`AnimatedSprite`](struct.AnimatedSprite.html) plays an `Animation`
+//! over time and draws the current frame:
+//!
+//! ```rust,no_run
+//! # use ggez::*;
+//! # use ggez::graphics::animation::{AnimatedSprite, Animation, AnimationMode};
+//! # use std::time::Duration;
+//! # fn t(ctx: &mut Context) -> GameResult {
+//! let image = graphics::Image::new(ctx, "/walk.png")?;
+//! let walk = Animation::from_grid(&image, 32, 32, Duration::from_millis(100))
+//!     .mode(AnimationMode::PingPong);
+//! let mut sprite = AnimatedSprite::new(image, walk);
+//!
+//! // Each update...
+//! for event in sprite.update(timer::delta(ctx)) {
+//!     println!("{:?}", event);
+//! }
+//! // ...and each draw.
+//! graphics::draw(ctx, &sprite, graphics::DrawParam::new())?;
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! Animations exported from [Aseprite](https://www.aseprite.org/) as a
+//! sprite sheet with JSON data can be loaded with
+//! [`AsepriteSheet::load()`](struct.AsepriteSheet.html#method.load).
+
+use std::collections::BTreeMap;
+use std::io::Read;
+use std::path;
+use std::time::Duration;
+
+use crate::context::Context;
+use crate::error::{GameError, GameResult};
+use crate::filesystem;
+use crate::graphics::shader::BlendMode;
+use crate::graphics::spritebatch::{SpriteBatch, SpriteIdx};
+use crate::graphics::{DrawParam, Drawable, Image, Rect};
+
+/// What an animation does when it reaches its last frame.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Serialize, Deserialize, SmartDefault)]
+pub enum AnimationMode {
+    /// Start again from the first frame.
+    #[default]
+    Loop,
+    /// Play backwards to the first frame, then forwards again, and so on.
+    PingPong,
+    /// Stop on the last frame.
+    Once,
+}
+
+/// One frame of an animation.
+#[derive(Debug, Copy, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Frame {
+    /// The part of the image to draw, as a fraction of the image
+    /// size, the same as [`DrawParam::src`](../struct.DrawParam.html#structfield.src).
+    pub src: Rect,
+    /// How long to show the frame for.
+    pub duration: Duration,
+}
+
+impl Frame {
+    /// Creates a new `Frame`.
+    pub fn new(src: Rect, duration: Duration) -> Self {
+        Self { src, duration }
+    }
+}
+
+/// A list of frames and how to play them.  This is just the
+/// description of an animation; an
+/// [`AnimatedSprite`](struct.AnimatedSprite.html) keeps track of
+/// playing one.
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+pub struct Animation {
+    /// The frames, in order.
+    pub frames: Vec<Frame>,
+    /// What to do after the last frame.
+    pub mode: AnimationMode,
+}
+
+impl Animation {
+    /// Creates a new looping `Animation` from the given frames.
+    pub fn new(frames: Vec<Frame>) -> Self {
+        Self {
+            frames,
+            mode: AnimationMode::default(),
+        }
+    }
+
+    /// Creates a new looping `Animation` from `src` rectangles that
+    /// are all shown for the same amount of time.
+    pub fn from_rects<I>(rects: I, frame_duration: Duration) -> Self
+    where
+        I: IntoIterator<Item = Rect>,
+    {
+        Self::new(
+            rects
+                .into_iter()
+                .map(|src| Frame::new(src, frame_duration))
+                .collect(),
+        )
+    }
+
+    /// Creates a new looping `Animation` by slicing the image into a
+    /// grid of frames of the given size in pixels, read left to right
+    /// and then top to bottom.  Any leftover pixels at the right and
+    /// bottom edges of the image are ignored.
+    pub fn from_grid(
+        image: &Image,
+        frame_width: u16,
+        frame_height: u16,
+        frame_duration: Duration,
+    ) -> Self {
+        let (width, height) = (image.width(), image.height());
+        let columns = if frame_width == 0 {
+            0
+        } else {
+            width / frame_width
+        };
+        let rows = if frame_height == 0 {
+            0
+        } else {
+            height / frame_height
+        };
+        let w = f32::from(frame_width) / f32::from(width);
+        let h = f32::from(frame_height) / f32::from(height);
+        let rects = (0..rows).flat_map(|row| {
+            (0..columns)
+                .map(move |column| Rect::new(f32::from(column) * w, f32::from(row) * h, w, h))
+        });
+        Self::from_rects(rects, frame_duration)
+    }
+
+    /// Sets what to do after the last frame.
+    pub fn mode(mut self, mode: AnimationMode) -> Self {
+        self.mode = mode;
+        self
+    }
+
+    /// Keeps only the frames in the given range, for picking one
+    /// animation out of a sprite sheet that has several.  The parts
+    /// of the range past the last frame are ignored.
+    pub fn frames(mut self, range: std::ops::Range<usize>) -> Self {
+        let end = range.end.min(self.frames.len());
+        let start = range.start.min(end);
+        self.frames = self.frames.drain(start..end).collect();
+        self
+    }
+
+    /// Sets how long to show the frame with the given index for.
+    /// Does nothing if there is no such frame.
+    pub fn frame_duration(mut self, index: usize, duration: Duration) -> Self {
+        if let Some(frame) = self.frames.get_mut(index) {
+            frame.duration = duration;
+        }
+        self
+    }
+
+    /// The total length of the animation, played once through.
+    pub fn total_duration(&self) -> Duration {
+        self.frames
+            .iter()
+            .fold(Duration::new(0, 0), |total, frame| total + frame.duration)
+    }
+}
+
+/// Something that happened while an
+/// [`AnimatedSprite`](struct.AnimatedSprite.html) was updated.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum AnimationEvent {
+    /// The frame with the given index is now being shown.
+    Frame(usize),
+    /// A looping or ping-pong animation got back to its first frame.
+    Looped,
+    /// An animation played with `AnimationMode::Once` finished
+    /// showing its last frame.
+    Finished,
+}
+
+/// An image and an `Animation` being played on it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AnimatedSprite {
+    image: Image,
+    animation: Animation,
+    current: usize,
+    elapsed: Duration,
+    forwards: bool,
+    playing: bool,
+    finished: bool,
+    blend_mode: Option<BlendMode>,
+}
+
+impl AnimatedSprite {
+    /// Creates a new `AnimatedSprite`, playing from the first frame.
+    pub fn new(image: Image, animation: Animation) -> Self {
+        Self {
+            image,
+            animation,
+            current: 0,
+            elapsed: Duration::new(0, 0),
+            forwards: true,
+            playing: true,
+            finished: false,
+            blend_mode: None,
+        }
+    }
+
+    /// Advances the animation by the given amount of time, usually
+    /// [`timer::delta()`](../../timer/fn.delta.html), and returns what
+    /// happened in the meantime.  If that was long enough to go
+    /// through several frames, you get an event for each of them.
+    pub fn update(&mut self, dt: Duration) -> Vec<AnimationEvent> {
+        let mut events = Vec::new();
+        let zero = Duration::new(0, 0);
+        if !self.playing || self.finished || self.animation.total_duration() == zero {
+            return events;
+        }
+        self.elapsed += dt;
+        while self.elapsed >= self.animation.frames[self.current].duration {
+            self.elapsed -= self.animation.frames[self.current].duration;
+            self.step(&mut events);
+            if self.finished {
+                self.elapsed = zero;
+                break;
+            }
+        }
+        events
+    }
+
+    /// Moves to the next frame.
+    fn step(&mut self, events: &mut Vec<AnimationEvent>) {
+        let last = self.animation.frames.len() - 1;
+        match self.animation.mode {
+            AnimationMode::Loop => {
+                if self.current < last {
+                    self.current += 1;
+                } else {
+                    self.current = 0;
+                    events.push(AnimationEvent::Looped);
+                }
+            }
+            AnimationMode::Once => {
+                if self.current < last {
+                    self.current += 1;
+                } else {
+                    self.finished = true;
+                    events.push(AnimationEvent::Finished);
+                    return;
+                }
+            }
+            AnimationMode::PingPong => {
+                if last == 0 {
+                    events.push(AnimationEvent::Looped);
+                } else if self.forwards {
+                    if self.current < last {
+                        self.current += 1;
+                    } else {
+                        self.forwards = false;
+                        self.current -= 1;
+                    }
+                } else {
+                    self.current -= 1;
+                    if self.current == 0 {
+                        self.forwards = true;
+                        events.push(AnimationEvent::Looped);
+                    }
+                }
+            }
+        }
+        events.push(AnimationEvent::Frame(self.current));
+    }
+
+    /// Resumes playing the animation.
+    pub fn play(&mut self) {
+        self.playing = true;
+    }
+
+    /// Pauses the animation on its current frame.
+    pub fn pause(&mut self) {
+        self.playing = false;
+    }
+
+    /// Returns whether the animation is playing, that is, it hasn't
+    /// been paused.  A finished animation still counts as playing.
+    pub fn is_playing(&self) -> bool {
+        self.playing
+    }
+
+    /// Returns whether an animation played with `AnimationMode::Once`
+    /// has finished.
+    pub fn is_finished(&self) -> bool {
+        self.finished
+    }
+
+    /// Goes back to the start of the first frame.
+    pub fn reset(&mut self) {
+        self.set_frame(0);
+    }
+
+    /// Jumps to the start of the frame with the given index.
+    /// Indices past the last frame are clamped to it.
+    pub fn set_frame(&mut self, index: usize) {
+        self.current = index.min(self.animation.frames.len().saturating_sub(1));
+        self.elapsed = Duration::new(0, 0);
+        self.forwards = true;
+        self.finished = false;
+    }
+
+    /// Returns the index of the frame being shown.
+    pub fn current_frame(&self) -> usize {
+        self.current
+    }
+
+    /// Switches to a different animation, starting from its first frame.
+    pub fn set_animation(&mut self, animation: Animation) {
+        self.animation = animation;
+        self.reset();
+    }
+
+    /// Returns the animation being played.
+    pub fn animation(&self) -> &Animation {
+        &self.animation
+    }
+
+    /// Returns the image the animation is drawn from.
+    pub fn image(&self) -> &Image {
+        &self.image
+    }
+
+    /// Returns the `src` rectangle of the frame being shown, or the
+    /// whole image if the animation has no frames.
+    pub fn src(&self) -> Rect {
+        self.animation
+            .frames
+            .get(self.current)
+            .map(|frame| frame.src)
+            .unwrap_or_else(|| Rect::new(0.0, 0.0, 1.0, 1.0))
+    }
+
+    /// Returns the given `DrawParam` with its `src` set to the
+    /// current frame.
+    pub fn draw_param(&self, param: DrawParam) -> DrawParam {
+        param.src(self.src())
+    }
+
+    /// Adds the current frame to a `SpriteBatch`, which should be
+    /// drawing with the same image.
+    pub fn add_to(&self, batch: &mut SpriteBatch, param: DrawParam) -> SpriteIdx {
+        batch.add(self.draw_param(param))
+    }
+}
+
+impl Drawable for AnimatedSprite {
+    fn draw(&self, ctx: &mut Context, param: DrawParam) -> GameResult {
+        let mut image = self.image.clone();
+        image.set_blend_mode(self.blend_mode);
+        image.draw(ctx, self.draw_param(param))
+    }
+
+    fn dimensions(&self, _ctx: &mut Context) -> Option<Rect> {
+        let src = self.src();
+        let w = src.w * f32::from(self.image.width());
+        let h = src.h * f32::from(self.image.height());
+        Some(Rect::new(0.0, 0.0, w, h))
+    }
+
+    fn set_blend_mode(&mut self, mode: Option<BlendMode>) {
+        self.blend_mode = mode;
+    }
+
+    fn blend_mode(&self) -> Option<BlendMode> {
+        self.blend_mode
+    }
+}
+
+/// Animations loaded from an [Aseprite](https://www.aseprite.org/)
+/// sprite sheet export.  Both the "Hash" and "Array" JSON data formats
+/// work.  Each tag in the file becomes an `Animation`; "Reverse" tags
+/// get their frames reversed, and "Ping-pong" tags use
+/// `AnimationMode::PingPong`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AsepriteSheet {
+    /// The sheet's image file, as written in the JSON file.  This is
+    /// relative to wherever the JSON was exported to, so you will
+    /// probably need to turn it into a `filesystem` path yourself.
+    pub image: Option<String>,
+    /// Every frame in the sheet, as one looping animation.
+    pub animation: Animation,
+    /// The animations for each tag, by name.
+    pub tags: BTreeMap<String, Animation>,
+}
+
+impl AsepriteSheet {
+    /// Loads a sprite sheet's JSON data from the given path.  The
+    /// documentation for the [`filesystem`](../../filesystem/index.html)
+    /// module explains how the path must be specified.
+    pub fn load<P: AsRef<path::Path>>(ctx: &mut Context, path: P) -> GameResult<Self> {
+        let mut s = String::new();
+        let mut file = filesystem::open(ctx, path)?;
+        let _ = file.read_to_string(&mut s)?;
+        Self::from_json(&s)
+    }
+
+    /// Parses a sprite sheet's JSON data.
+    pub fn from_json(json: &str) -> GameResult<Self> {
+        let sheet: aseprite::Sheet = serde_json::from_str(json)?;
+        let (width, height) = (f32::from(sheet.meta.size.w), f32::from(sheet.meta.size.h));
+        if width == 0.0 || height == 0.0 {
+            let msg = String::from("Aseprite sheet has no size");
+            return Err(GameError::ResourceLoadError(msg));
+        }
+        let frames: Vec<Frame> = sheet
+            .frames
+            .frames()
+            .iter()
+            .map(|f| {
+                let r = &f.frame;
+                let src = Rect::new(
+                    f32::from(r.x) / width,
+                    f32::from(r.y) / height,
+                    f32::from(r.w) / width,
+                    f32::from(r.h) / height,
+                );
+                Frame::new(src, Duration::from_millis(f.duration))
+            })
+            .collect();
+
+        let mut tags = BTreeMap::new();
+        for tag in sheet.meta.frame_tags {
+            if tag.from > tag.to || tag.to >= frames.len() {
+                let msg = format!(
+                    "Aseprite tag {:?} has frames {} to {}, but there are only {} frames",
+                    tag.name,
+                    tag.from,
+                    tag.to,
+                    frames.len()
+                );
+                return Err(GameError::ResourceLoadError(msg));
+            }
+            let mut tag_frames = frames[tag.from..=tag.to].to_vec();
+            let mode = match tag.direction.as_str() {
+                "reverse" => {
+                    tag_frames.reverse();
+                    AnimationMode::Loop
+                }
+                "pingpong" => AnimationMode::PingPong,
+                _ => AnimationMode::Loop,
+            };
+            let _ = tags.insert(tag.name, Animation::new(tag_frames).mode(mode));
+        }
+
+        Ok(Self {
+            image: sheet.meta.image,
+            animation: Animation::new(frames),
+            tags,
+        })
+    }
+
+    /// Returns the animation for the tag with the given name.
+    pub fn tag(&self, name: &str) -> Option<&Animation> {
+        self.tags.get(name)
+    }
+}
+
+/// The parts of Aseprite's JSON format we care about.
+mod aseprite {
+    use std::fmt;
+
+    use serde::de::{self, Deserialize, Deserializer, MapAccess, Visitor};
+
+    #[derive(Debug, Deserialize)]
+    pub struct Sheet {
+        pub frames: Frames,
+        pub meta: Meta,
+    }
+
+    #[derive(Debug, Deserialize)]
+    #[serde(untagged)]
+    pub enum Frames {
+        Array(Vec<AseFrame>),
+        Hash(OrderedFrames),
+    }
+
+    impl Frames {
+        pub fn frames(&self) -> &[AseFrame] {
+            match self {
+                Frames::Array(frames) => frames,
+                Frames::Hash(frames) => &frames.0,
+            }
+        }
+    }
+
+    #[derive(Debug, Deserialize)]
+    pub struct AseFrame {
+        pub frame: AseRect,
+        pub duration: u64,
+    }
+
+    #[derive(Debug, Deserialize)]
+    pub struct AseRect {
+        pub x: u16,
+        pub y: u16,
+        pub w: u16,
+        pub h: u16,
+    }
+
+    #[derive(Debug, Deserialize)]
+    pub struct Size {
+        pub w: u16,
+        pub h: u16,
+    }
+
+    #[derive(Debug, Deserialize)]
+    #[serde(rename_all = "camelCase")]
+    pub struct Meta {
+        pub image: Option<String>,
+        pub size: Size,
+        #[serde(default)]
+        pub frame_tags: Vec<Tag>,
+    }
+
+    #[derive(Debug, Deserialize)]
+    pub struct Tag {
+        pub name: String,
+        pub from: usize,
+        pub to: usize,
+        #[serde(default)]
+        pub direction: String,
+    }
+
+    /// The frames of a "Hash" export, in the order they appear in the
+    /// file rather than sorted by name, since "walk 10" sorts before
+    /// "walk 2".
+    #[derive(Debug)]
+    pub struct OrderedFrames(pub Vec<AseFrame>);
+
+    impl<'de> Deserialize<'de> for OrderedFrames {
+        fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+            struct FramesVisitor;
+
+            impl<'de> Visitor<'de> for FramesVisitor {
+                type Value = OrderedFrames;
+
+                fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                    f.write_str("a map of frame names to frames")
+                }
+
+                fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Self::Value, A::Error> {
+                    let mut frames = Vec::new();
+                    while let Some((_, frame)) = map.next_entry::<de::IgnoredAny, AseFrame>()? {
+                        frames.push(frame);
+                    }
+                    Ok(OrderedFrames(frames))
+                }
+            }
+
+            deserializer.deserialize_map(FramesVisitor)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use approx::assert_relative_eq;
+
+    #[test]
+    fn aseprite_hash() {
+        let json = r#"{
+            "frames": {
+                "walk 0.aseprite": { "frame": { "x": 0, "y": 0, "w": 16, "h": 16 }, "duration": 100 },
+                "walk 1.aseprite": { "frame": { "x": 16, "y": 0, "w": 16, "h": 16 }, "duration": 150 },
+                "walk 2.aseprite": { "frame": { "x": 32, "y": 0, "w": 16, "h": 16 }, "duration": 100 },
+                "walk 10.aseprite": { "frame": { "x": 48, "y": 0, "w": 16, "h": 16 }, "duration": 200 }
+            },
+            "meta": {
+                "image": "walk.png",
+                "size": { "w": 64, "h": 16 },
+                "frameTags": [
+                    { "name": "start", "from": 0, "to": 1, "direction": "reverse" },
+                    { "name": "bounce", "from": 1, "to": 3, "direction": "pingpong" }
+                ]
+            }
+        }"#;
+        let sheet = AsepriteSheet::from_json(json).unwrap();
+        assert_eq!(sheet.image.as_ref().map(String::as_str), Some("walk.png"));
+        assert_eq!(sheet.animation.frames.len(), 4);
+        assert_relative_eq!(sheet.animation.frames[3].src.x, 0.75);
+        assert_eq!(sheet.animation.total_duration(), Duration::from_millis(550));
+
+        let start = sheet.tag("start").unwrap();
+        assert_eq!(start.frames[0].duration, Duration::from_millis(150));
+        assert_eq!(sheet.tag("bounce").unwrap().mode, AnimationMode::PingPong);
+    }
+}
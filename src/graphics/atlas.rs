@@ -0,0 +1,516 @@
+//! A texture atlas packs lots of little images into one big `Image`, so
+//! they can all be drawn with a single
+//! [`SpriteBatch`](../spritebatch/struct.SpriteBatch.html) by picking
+//! each one out with [`DrawParam::src`](../struct.DrawParam.html#method.src).
+//!
+//! Build one at runtime with an [`AtlasBuilder`](struct.AtlasBuilder.html):
+//!
+//! ```rust,no_run
+//! # use ggez::*;
+//! # use ggez::graphics::atlas::AtlasBuilder;
+//! # fn t(ctx: &mut Context) -> GameResult {
+//! let atlas = AtlasBuilder::new()
+//!     .padding(1)
+//!     .extrude(1)
+//!     .add_file(ctx, "player", "/player.png")?
+//!     .add_file(ctx, "tile", "/tile.png")?
+//!     .build(ctx)?;
+//!
+//! let mut batch = atlas.sprite_batch();
+//! let _ = batch.add(graphics::DrawParam::new().src(atlas.region("tile").unwrap()));
+//! graphics::draw(ctx, &batch, graphics::DrawParam::new())?;
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! Packing is quick, but you can also do it ahead of time: pack the
+//! atlas and save it with [`PackedAtlas::save()`](struct.PackedAtlas.html#method.save),
+//! then load the image and its layout at runtime with
+//! [`Atlas::load()`](struct.Atlas.html#method.load).
+
+use std::collections::BTreeMap;
+use std::io::{self, Read, Write};
+use std::path;
+
+use ::image;
+
+use crate::context::Context;
+use crate::error::{GameError, GameResult};
+use crate::filesystem;
+use crate::graphics::spritebatch::SpriteBatch;
+use crate::graphics::{Image, Rect};
+
+/// Where each image ended up in an atlas, in pixels.
+/// This is what gets saved alongside an atlas image.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AtlasLayout {
+    /// Width of the atlas image.
+    pub width: u16,
+    /// Height of the atlas image.
+    pub height: u16,
+    /// The region each named image occupies, in pixels.
+    pub regions: BTreeMap<String, Rect>,
+}
+
+impl AtlasLayout {
+    /// Returns the region with the given name in pixels.
+    pub fn pixel_region(&self, name: &str) -> Option<Rect> {
+        self.regions.get(name).cloned()
+    }
+
+    /// Returns the region with the given name as a fraction of the
+    /// atlas size, ready to pass to
+    /// [`DrawParam::src()`](../struct.DrawParam.html#method.src).
+    pub fn region(&self, name: &str) -> Option<Rect> {
+        let w = f32::from(self.width);
+        let h = f32::from(self.height);
+        self.pixel_region(name)
+            .map(|r| Rect::new(r.x / w, r.y / h, r.w / w, r.h / h))
+    }
+
+    /// Loads a layout from a TOML file at the given path.  The
+    /// documentation for the [`filesystem`](../../filesystem/index.html)
+    /// module explains how the path must be specified.
+    pub fn load<P: AsRef<path::Path>>(ctx: &mut Context, path: P) -> GameResult<Self> {
+        let mut s = String::new();
+        let mut file = filesystem::open(ctx, path)?;
+        let _ = file.read_to_string(&mut s)?;
+        Ok(toml::from_str(&s)?)
+    }
+
+    /// Saves the layout as a TOML file at the given
+    /// path in the user directory.
+    pub fn save<P: AsRef<path::Path>>(&self, ctx: &mut Context, path: P) -> GameResult {
+        let s = toml::to_vec(self)?;
+        let mut file = filesystem::create(ctx, path)?;
+        file.write_all(&s)?;
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Entry {
+    name: String,
+    width: u16,
+    height: u16,
+    rgba: Vec<u8>,
+}
+
+/// Collects images and packs them into an atlas.
+///
+/// Each image is surrounded by `padding` pixels of empty space so
+/// filtering doesn't bleed neighbors into each other, and its edge pixels
+/// are copied outwards `extrude` pixels so sampling right at the edge of a
+/// region (say, when drawing tiles at fractional positions) doesn't pick
+/// up the empty space either.
+#[derive(Debug, Clone)]
+pub struct AtlasBuilder {
+    entries: Vec<Entry>,
+    padding: u16,
+    extrude: u16,
+    max_size: u16,
+}
+
+impl Default for AtlasBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl AtlasBuilder {
+    /// Creates a new, empty `AtlasBuilder` with no padding or
+    /// extrusion and a maximum atlas size of 4096x4096.
+    pub fn new() -> Self {
+        Self {
+            entries: vec![],
+            padding: 0,
+            extrude: 0,
+            max_size: 4096,
+        }
+    }
+
+    /// Sets how many pixels of empty space to leave around each image.
+    pub fn padding(mut self, padding: u16) -> Self {
+        self.padding = padding;
+        self
+    }
+
+    /// Sets how many pixels to copy each image's edges outwards.
+    pub fn extrude(mut self, extrude: u16) -> Self {
+        self.extrude = extrude;
+        self
+    }
+
+    /// Sets the largest width and height the atlas may have.
+    pub fn max_size(mut self, max_size: u16) -> Self {
+        self.max_size = max_size;
+        self
+    }
+
+    /// Adds an image from a buffer of RGBA pixels, laid out the same
+    /// way as for [`Image::from_rgba8()`](../struct.Image.html#method.from_rgba8).
+    /// Adding a second image with the same name replaces the first.
+    /// Images with no pixels can't be added.
+    pub fn add_rgba8(
+        mut self,
+        name: &str,
+        width: u16,
+        height: u16,
+        rgba: &[u8],
+    ) -> GameResult<Self> {
+        if width == 0 || height == 0 {
+            let msg = format!("Atlas image {:?} is {}x{}", name, width, height);
+            return Err(GameError::ResourceLoadError(msg));
+        }
+        if width as usize * height as usize * 4 != rgba.len() {
+            let msg = format!(
+                "Atlas image {:?} is {}x{}, but has {} bytes of data (expected {})",
+                name,
+                width,
+                height,
+                rgba.len(),
+                width as usize * height as usize * 4
+            );
+            return Err(GameError::ResourceLoadError(msg));
+        }
+        self.entries.retain(|e| e.name != name);
+        self.entries.push(Entry {
+            name: name.to_owned(),
+            width,
+            height,
+            rgba: rgba.to_vec(),
+        });
+        Ok(self)
+    }
+
+    /// Loads an image file through the [`filesystem`](../../filesystem/index.html)
+    /// and adds it.
+    pub fn add_file<P: AsRef<path::Path>>(
+        self,
+        ctx: &mut Context,
+        name: &str,
+        path: P,
+    ) -> GameResult<Self> {
+        let img = {
+            let mut buf = Vec::new();
+            let mut reader = filesystem::open(ctx, path)?;
+            let _ = reader.read_to_end(&mut buf)?;
+            image::load_from_memory(&buf)?.to_rgba()
+        };
+        let (width, height) = img.dimensions();
+        if width > u32::from(u16::max_value()) || height > u32::from(u16::max_value()) {
+            let msg = format!(
+                "Atlas image {:?} of size {}x{} is too big",
+                name, width, height
+            );
+            return Err(GameError::ResourceLoadError(msg));
+        }
+        self.add_rgba8(name, width as u16, height as u16, &img)
+    }
+
+    /// Packs all the images into one RGBA buffer, without
+    /// touching the graphics card.
+    pub fn pack(&self) -> GameResult<PackedAtlas> {
+        let mut order: Vec<&Entry> = self.entries.iter().collect();
+        // Tallest first makes the shelves waste less space.
+        order.sort_by(|a, b| b.height.cmp(&a.height).then(b.width.cmp(&a.width)));
+
+        // Done in u64 so lots of large entries can't overflow it.
+        let border = u64::from(self.extrude) * 2 + u64::from(self.padding);
+        let area: u64 = order
+            .iter()
+            .map(|e| (u64::from(e.width) + border) * (u64::from(e.height) + border))
+            .sum();
+        // Try power-of-two sizes, then the largest size allowed.
+        let max_size = u32::from(self.max_size);
+        let mut size = 64.min(max_size);
+        while u64::from(size) * u64::from(size) < area && size < max_size {
+            size = (size * 2).min(max_size);
+        }
+        loop {
+            if let Some(positions) = self.shelf_pack(&order, size) {
+                return Ok(self.blit(&order, &positions, size as u16));
+            }
+            if size >= max_size {
+                break;
+            }
+            size = (size * 2).min(max_size);
+        }
+        let msg = format!(
+            "Couldn't fit {} images into a {}x{} atlas",
+            order.len(),
+            self.max_size,
+            self.max_size
+        );
+        Err(GameError::ResourceLoadError(msg))
+    }
+
+    /// Packs the images and uploads the result to an `Image`.
+    pub fn build(&self, ctx: &mut Context) -> GameResult<Atlas> {
+        self.pack()?.build(ctx)
+    }
+
+    /// Places each entry on horizontal shelves in a square of the given
+    /// size, returning where each slot starts, or `None` if they don't fit.
+    fn shelf_pack(&self, order: &[&Entry], size: u32) -> Option<Vec<(u32, u32)>> {
+        let padding = u32::from(self.padding);
+        let extrude2 = u32::from(self.extrude) * 2;
+        let mut positions = Vec::with_capacity(order.len());
+        let (mut x, mut y) = (padding, padding);
+        let mut shelf_height = 0;
+        for entry in order {
+            let w = u32::from(entry.width) + extrude2;
+            let h = u32::from(entry.height) + extrude2;
+            if x + w + padding > size {
+                x = padding;
+                y += shelf_height + padding;
+                shelf_height = 0;
+            }
+            if x + w + padding > size || y + h + padding > size {
+                return None;
+            }
+            positions.push((x, y));
+            x += w + padding;
+            shelf_height = shelf_height.max(h);
+        }
+        Some(positions)
+    }
+
+    /// Copies each entry into its slot, extruding the edges.
+    fn blit(&self, order: &[&Entry], positions: &[(u32, u32)], size: u16) -> PackedAtlas {
+        let stride = usize::from(size) * 4;
+        let mut rgba = vec![0; stride * usize::from(size)];
+        let mut regions = BTreeMap::new();
+        let extrude = i64::from(self.extrude);
+        for (entry, &(slot_x, slot_y)) in order.iter().zip(positions) {
+            let (w, h) = (i64::from(entry.width), i64::from(entry.height));
+            for dy in -extrude..h + extrude {
+                for dx in -extrude..w + extrude {
+                    // Pixels outside the image repeat the nearest edge pixel.
+                    let sx = dx.max(0).min(w - 1) as usize;
+                    let sy = dy.max(0).min(h - 1) as usize;
+                    let src = (sy * usize::from(entry.width) + sx) * 4;
+                    let tx = (i64::from(slot_x) + extrude + dx) as usize;
+                    let ty = (i64::from(slot_y) + extrude + dy) as usize;
+                    let dst = ty * stride + tx * 4;
+                    rgba[dst..dst + 4].copy_from_slice(&entry.rgba[src..src + 4]);
+                }
+            }
+            let region = Rect::new(
+                (i64::from(slot_x) + extrude) as f32,
+                (i64::from(slot_y) + extrude) as f32,
+                w as f32,
+                h as f32,
+            );
+            let _ = regions.insert(entry.name.clone(), region);
+        }
+        PackedAtlas {
+            rgba,
+            layout: AtlasLayout {
+                width: size,
+                height: size,
+                regions,
+            },
+        }
+    }
+}
+
+/// An atlas that has been packed but not uploaded to the
+/// graphics card yet.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PackedAtlas {
+    /// The atlas image as RGBA pixels.
+    pub rgba: Vec<u8>,
+    /// Where everything is in it.
+    pub layout: AtlasLayout,
+}
+
+impl PackedAtlas {
+    /// Uploads the atlas to an `Image`.
+    pub fn build(self, ctx: &mut Context) -> GameResult<Atlas> {
+        let image = Image::from_rgba8(ctx, self.layout.width, self.layout.height, &self.rgba)?;
+        Ok(Atlas {
+            image,
+            layout: self.layout,
+        })
+    }
+
+    /// Saves the atlas image as a PNG and its layout as TOML to the
+    /// given paths in the user directory, so it can be loaded later
+    /// with [`Atlas::load()`](struct.Atlas.html#method.load).
+    pub fn save<P, Q>(&self, ctx: &mut Context, image_path: P, layout_path: Q) -> GameResult
+    where
+        P: AsRef<path::Path>,
+        Q: AsRef<path::Path>,
+    {
+        {
+            let f = filesystem::create(ctx, image_path)?;
+            let writer = &mut io::BufWriter::new(f);
+            image::png::PNGEncoder::new(writer).encode(
+                &self.rgba,
+                u32::from(self.layout.width),
+                u32::from(self.layout.height),
+                image::ColorType::RGBA(8),
+            )?;
+        }
+        self.layout.save(ctx, layout_path)
+    }
+}
+
+/// An `Image` full of smaller images, and where each of them is.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Atlas {
+    image: Image,
+    layout: AtlasLayout,
+}
+
+impl Atlas {
+    /// Creates an `Atlas` from an existing image and its layout.
+    pub fn new(image: Image, layout: AtlasLayout) -> GameResult<Self> {
+        if (image.width(), image.height()) != (layout.width, layout.height) {
+            let msg = format!(
+                "Atlas layout is for a {}x{} image, but the image is {}x{}",
+                layout.width,
+                layout.height,
+                image.width(),
+                image.height()
+            );
+            return Err(GameError::ResourceLoadError(msg));
+        }
+        Ok(Self { image, layout })
+    }
+
+    /// Loads an atlas image and its layout, as saved by
+    /// [`PackedAtlas::save()`](struct.PackedAtlas.html#method.save).
+    pub fn load<P, Q>(ctx: &mut Context, image_path: P, layout_path: Q) -> GameResult<Self>
+    where
+        P: AsRef<path::Path>,
+        Q: AsRef<path::Path>,
+    {
+        let image = Image::new(ctx, image_path)?;
+        let layout = AtlasLayout::load(ctx, layout_path)?;
+        Self::new(image, layout)
+    }
+
+    /// Returns the atlas image.
+    pub fn image(&self) -> &Image {
+        &self.image
+    }
+
+    /// Returns the atlas layout.
+    pub fn layout(&self) -> &AtlasLayout {
+        &self.layout
+    }
+
+    /// Returns the region with the given name as a fraction of the
+    /// atlas size, ready to pass to
+    /// [`DrawParam::src()`](../struct.DrawParam.html#method.src).
+    pub fn region(&self, name: &str) -> Option<Rect> {
+        self.layout.region(name)
+    }
+
+    /// Creates a new, empty `SpriteBatch` that draws from the atlas.
+    pub fn sprite_batch(&self) -> SpriteBatch {
+        SpriteBatch::new(self.image.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn solid(color: [u8; 4], w: u16, h: u16) -> Vec<u8> {
+        color
+            .iter()
+            .cloned()
+            .cycle()
+            .take(w as usize * h as usize * 4)
+            .collect()
+    }
+
+    #[test]
+    fn pack_with_padding_and_extrusion() {
+        let packed = AtlasBuilder::new()
+            .padding(1)
+            .extrude(2)
+            .add_rgba8("red", 10, 20, &solid([255, 0, 0, 255], 10, 20))
+            .unwrap()
+            .add_rgba8("blue", 30, 5, &solid([0, 0, 255, 255], 30, 5))
+            .unwrap()
+            .pack()
+            .unwrap();
+        let layout = &packed.layout;
+        assert_eq!((layout.width, layout.height), (64, 64));
+
+        let red = layout.pixel_region("red").unwrap();
+        let blue = layout.pixel_region("blue").unwrap();
+        assert_eq!(red, Rect::new(3.0, 3.0, 10.0, 20.0));
+        assert_eq!(blue, Rect::new(18.0, 3.0, 30.0, 5.0));
+        assert!(!red.overlaps(&blue));
+
+        let pixel = |x: usize, y: usize| {
+            let i = (y * 64 + x) * 4;
+            &packed.rgba[i..i + 4]
+        };
+        // Extruded edge, and the padding just past it.
+        assert_eq!(pixel(1, 1), &[255, 0, 0, 255]);
+        assert_eq!(pixel(0, 0), &[0, 0, 0, 0]);
+        assert_eq!(pixel(16, 3), &[0, 0, 255, 255]);
+        assert_eq!(pixel(15, 3), &[0, 0, 0, 0]);
+
+        let src = layout.region("blue").unwrap();
+        assert_eq!(
+            src,
+            Rect::new(18.0 / 64.0, 3.0 / 64.0, 30.0 / 64.0, 5.0 / 64.0)
+        );
+    }
+
+    #[test]
+    fn too_big() {
+        let result = AtlasBuilder::new()
+            .max_size(64)
+            .add_rgba8("big", 100, 1, &solid([0, 0, 0, 0], 100, 1))
+            .unwrap()
+            .pack();
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn too_big_to_add_up() {
+        // Enough area between them to overflow a u32; the pixels are
+        // never looked at since packing fails first.
+        let big = |name: &str| Entry {
+            name: name.to_string(),
+            width: u16::max_value(),
+            height: u16::max_value(),
+            rgba: vec![],
+        };
+        let builder = AtlasBuilder {
+            entries: vec![big("a"), big("b")],
+            ..AtlasBuilder::new().max_size(u16::max_value())
+        };
+        assert!(builder.pack().is_err());
+    }
+
+    #[test]
+    fn wrong_size() {
+        assert!(AtlasBuilder::new()
+            .add_rgba8("oops", 2, 2, &[0; 4])
+            .is_err());
+        assert!(AtlasBuilder::new()
+            .extrude(1)
+            .add_rgba8("empty", 0, 4, &[])
+            .is_err());
+    }
+
+    #[test]
+    fn small_max_size() {
+        let packed = AtlasBuilder::new()
+            .max_size(40)
+            .add_rgba8("tile", 32, 32, &solid([0, 0, 0, 0], 32, 32))
+            .unwrap()
+            .pack()
+            .unwrap();
+        assert_eq!((packed.layout.width, packed.layout.height), (40, 40));
+    }
+}
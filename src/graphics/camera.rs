@@ -0,0 +1,188 @@
+use mint;
+
+use crate::graphics::context::ortho_projection;
+use crate::graphics::*;
+
+type Vec3 = na::Vector3<f32>;
+
+/// A 2D camera looking at a world that is bigger than the screen,
+/// which can scroll, zoom and rotate.
+///
+/// The camera shows the part of the world around its `position` in its
+/// `viewport`, the part of the window it draws to.  Everything is in
+/// pixels of the window's drawable, the same as
+/// [`mouse::position()`](../input/mouse/fn.position.html) gives, so on a
+/// hidpi display one world unit at a zoom of 1 is one physical pixel.
+///
+/// ```rust,no_run
+/// # use ggez::*;
+/// # use ggez::graphics::*;
+/// # fn t(ctx: &mut Context, player: &Image) -> GameResult {
+/// let camera = Camera2D::new(ctx).position([500.0, 300.0]).zoom(2.0);
+/// camera.push(ctx)?;
+/// graphics::draw(ctx, player, DrawParam::new().dest([480.0, 280.0]))?;
+/// Camera2D::pop(ctx)?;
+///
+/// // What's under the mouse?
+/// let target = camera.screen_to_world(input::mouse::position(ctx));
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Camera2D {
+    /// The point in the world at the center of the viewport.
+    pub position: mint::Point2<f32>,
+    /// How many pixels one world unit takes up.
+    pub zoom: f32,
+    /// The rotation of the camera in radians.  The world
+    /// appears rotated the other way.
+    pub rotation: f32,
+    /// The part of the window the camera draws to, in pixels.
+    pub viewport: Rect,
+}
+
+impl Camera2D {
+    /// Creates a camera that fills the window, looking at the center
+    /// of it, so that to begin with world coordinates are the same as
+    /// pixels.
+    pub fn new(ctx: &Context) -> Self {
+        let (width, height) = drawable_size(ctx);
+        Self::with_viewport(Rect::new(0.0, 0.0, width, height))
+    }
+
+    /// Creates a camera that draws to the given part of the window,
+    /// looking at the point in the world at its center, so that to
+    /// begin with world coordinates are the same as pixels.
+    pub fn with_viewport(viewport: Rect) -> Self {
+        Camera2D {
+            position: mint::Point2 {
+                x: viewport.x + viewport.w / 2.0,
+                y: viewport.y + viewport.h / 2.0,
+            },
+            zoom: 1.0,
+            rotation: 0.0,
+            viewport,
+        }
+    }
+
+    /// Set the point in the world at the center of the viewport
+    pub fn position<P>(mut self, position: P) -> Self
+    where
+        P: Into<mint::Point2<f32>>,
+    {
+        self.position = position.into();
+        self
+    }
+
+    /// Set the zoom
+    pub fn zoom(mut self, zoom: f32) -> Self {
+        self.zoom = zoom;
+        self
+    }
+
+    /// Set the rotation
+    pub fn rotation(mut self, rotation: f32) -> Self {
+        self.rotation = rotation;
+        self
+    }
+
+    /// Set the viewport
+    pub fn viewport(mut self, viewport: Rect) -> Self {
+        self.viewport = viewport;
+        self
+    }
+
+    fn viewport_center(&self) -> Vector2 {
+        Vector2::new(
+            self.viewport.x + self.viewport.w / 2.0,
+            self.viewport.y + self.viewport.h / 2.0,
+        )
+    }
+
+    fn to_na_matrix(&self) -> Matrix4 {
+        let center = self.viewport_center();
+        Matrix4::new_translation(&Vec3::new(center.x, center.y, 0.0))
+            * Matrix4::new_nonuniform_scaling(&Vec3::new(self.zoom, self.zoom, 1.0))
+            * Matrix4::new_rotation(Vec3::z() * -self.rotation)
+            * Matrix4::new_translation(&Vec3::new(-self.position.x, -self.position.y, 0.0))
+    }
+
+    /// Returns the transform from world coordinates to window
+    /// pixels that [`push()`](#method.push) applies.
+    pub fn to_matrix(&self) -> mint::ColumnMatrix4<f32> {
+        self.to_na_matrix().into()
+    }
+
+    /// Converts a point in the world to where it is in the window,
+    /// in pixels.
+    pub fn world_to_screen<P>(&self, point: P) -> mint::Point2<f32>
+    where
+        P: Into<mint::Point2<f32>>,
+    {
+        let point: mint::Point2<f32> = point.into();
+        let point = Point2::from(point);
+        let position = Point2::from(self.position);
+        let rotation = na::Rotation2::new(-self.rotation);
+        let screen = (rotation * (point - position)) * self.zoom + self.viewport_center();
+        mint::Point2 {
+            x: screen.x,
+            y: screen.y,
+        }
+    }
+
+    /// Converts a point in the window, in pixels, to where it is in the
+    /// world.  Works with [`mouse::position()`](../input/mouse/fn.position.html)
+    /// for picking what's under the cursor.
+    pub fn screen_to_world<P>(&self, point: P) -> mint::Point2<f32>
+    where
+        P: Into<mint::Point2<f32>>,
+    {
+        let point: mint::Point2<f32> = point.into();
+        let point = Point2::from(point);
+        let rotation = na::Rotation2::new(self.rotation);
+        let offset = rotation * (point.coords - self.viewport_center()) / self.zoom;
+        mint::Point2 {
+            x: self.position.x + offset.x,
+            y: self.position.y + offset.y,
+        }
+    }
+
+    /// Starts drawing through the camera: pushes its transform onto the
+    /// transform stack, sets a projection that maps the window's pixels
+    /// onto the screen and pushes a scissor rect that clips drawing to the
+    /// viewport.  Stop with [`Camera2D::pop()`](#method.pop).
+    ///
+    /// This is meant for drawing to the screen; to draw to a `Canvas`
+    /// through a camera, make one the same size as the window.
+    pub fn push(&self, ctx: &mut Context) -> GameResult {
+        let (width, height) = drawable_size(ctx);
+        let screen = screen_coordinates(ctx);
+        // The scissor rect is in screen coordinates, so the viewport
+        // has to be converted from pixels.
+        let scale_x = screen.w / width;
+        let scale_y = screen.h / height;
+        let scissor = Rect::new(
+            screen.x + self.viewport.x * scale_x,
+            screen.y + self.viewport.y * scale_y,
+            self.viewport.w * scale_x,
+            self.viewport.h * scale_y,
+        );
+        push_transform(ctx, None::<mint::ColumnMatrix4<f32>>);
+        mul_transform(ctx, self.to_matrix());
+        push_scissor(ctx, scissor);
+        ctx.gfx_context
+            .set_projection(ortho_projection(Rect::new(0.0, 0.0, width, height)));
+        apply_transformations(ctx)
+    }
+
+    /// Stops drawing through the camera most recently started with
+    /// [`push()`](#method.push), popping its transform and scissor rect
+    /// and going back to the projection set with
+    /// [`set_screen_coordinates()`](fn.set_screen_coordinates.html).
+    pub fn pop(ctx: &mut Context) -> GameResult {
+        pop_transform(ctx);
+        pop_scissor(ctx);
+        let screen = screen_coordinates(ctx);
+        set_screen_coordinates(ctx, screen)
+    }
+}
@@ -1,7 +1,7 @@
 //! I guess these docs will never appear since we re-export the canvas
 //! module from graphics...
 use gfx::format::Swizzle;
-use gfx::handle::RawRenderTargetView;
+use gfx::handle::{RawDepthStencilView, RawRenderTargetView};
 use gfx::memory::{Bind, Usage};
 use gfx::texture::{AaMode, Kind};
 use gfx::Factory;
@@ -9,6 +9,7 @@ use gfx::Factory;
 use crate::conf;
 use crate::context::DebugId;
 use crate::error::*;
+use crate::graphics::context::create_depth_stencil_target;
 use crate::graphics::*;
 use crate::Context;
 
@@ -21,10 +22,19 @@ where
     Spec: BackendSpec,
 {
     target: RawRenderTargetView<Spec::Resources>,
+    depth_stencil: Option<RawDepthStencilView<Spec::Resources>>,
     image: Image,
+    /// Color attachments besides the first one, which is
+    /// `target` and `image`.
+    attachments: Vec<(RawRenderTargetView<Spec::Resources>, Image)>,
+    depth_image: Option<Image>,
     debug_id: DebugId,
 }
 
+/// The most color attachments a [`Canvas`](type.Canvas.html) can
+/// have, and so the most outputs a shader can write to at once.
+pub const MAX_COLOR_ATTACHMENTS: usize = 4;
+
 /// A canvas that can be rendered to instead of the screen (sometimes referred
 /// to as "render target" or "render to texture"). Set the canvas with the
 /// [`graphics::set_canvas()`](fn.set_canvas.html) function, and then anything you
@@ -35,52 +45,118 @@ where
 /// A `Canvas` allows graphics to be rendered to images off-screen
 /// in order to do things like saving to an image file or creating cool effects
 /// by using shaders that render to an image.
+///
+/// A canvas built with [`CanvasBuilder`](struct.CanvasBuilder.html) can
+/// have several color attachments, which a custom shader writes to with
+/// the outputs `Target0`, `Target1` and so on, and a depth buffer for
+/// [`graphics::set_depth_mode()`](fn.set_depth_mode.html).
 /// If you just want to draw multiple things efficiently, look at
 /// [`SpriteBatch`](spritebatch/struct.Spritebatch.html).
 pub type Canvas = CanvasGeneric<GlBackendSpec>;
 
-impl Canvas {
-    /// Create a new `Canvas` with the given size and number of samples.
-    pub fn new(
-        ctx: &mut Context,
-        width: u16,
-        height: u16,
-        samples: conf::NumSamples,
-    ) -> GameResult<Canvas> {
+/// Creates a [`Canvas`](type.Canvas.html) with more options than
+/// [`Canvas::new()`](type.Canvas.html#method.new) has.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct CanvasBuilder {
+    width: u16,
+    height: u16,
+    samples: conf::NumSamples,
+    color_attachments: usize,
+    depth: bool,
+    stencil: bool,
+}
+
+impl CanvasBuilder {
+    /// Starts building a `Canvas` of the given size, with no
+    /// multisampling, one color attachment and no depth or
+    /// stencil buffer.
+    pub fn new(width: u16, height: u16) -> Self {
+        Self {
+            width,
+            height,
+            samples: conf::NumSamples::One,
+            color_attachments: 1,
+            depth: false,
+            stencil: false,
+        }
+    }
+
+    /// Sets the number of samples to use for multisampling.
+    pub fn samples(mut self, samples: conf::NumSamples) -> Self {
+        self.samples = samples;
+        self
+    }
+
+    /// Sets whether the canvas gets a stencil buffer, so that
+    /// [`graphics::set_stencil_mode()`](fn.set_stencil_mode.html)
+    /// can be used while drawing to it.
+    pub fn stencil(mut self, stencil: bool) -> Self {
+        self.stencil = stencil;
+        self
+    }
+
+    /// Sets how many color attachments the canvas has, from 1 up to
+    /// [`MAX_COLOR_ATTACHMENTS`](constant.MAX_COLOR_ATTACHMENTS.html).
+    /// A shader writes to attachment `n` through its output named
+    /// `Target<n>`; see [`Canvas::attachment()`](type.Canvas.html#method.attachment).
+    pub fn color_attachments(mut self, count: usize) -> Self {
+        self.color_attachments = count;
+        self
+    }
+
+    /// Sets whether the canvas gets a depth buffer, so that
+    /// [`graphics::set_depth_mode()`](fn.set_depth_mode.html)
+    /// can be used while drawing to it.  The depth and stencil
+    /// buffers are really the same buffer, so asking for either
+    /// one gets you both.
+    pub fn depth(mut self, depth: bool) -> Self {
+        self.depth = depth;
+        self
+    }
+
+    /// Creates the `Canvas`.
+    pub fn build(self, ctx: &mut Context) -> GameResult<Canvas> {
+        let CanvasBuilder {
+            width,
+            height,
+            samples,
+            color_attachments,
+            depth,
+            stencil,
+        } = self;
+        if color_attachments == 0 || color_attachments > MAX_COLOR_ATTACHMENTS {
+            let msg = format!(
+                "A canvas needs between 1 and {} color attachments, not {}",
+                MAX_COLOR_ATTACHMENTS, color_attachments
+            );
+            return Err(GameError::RenderError(msg));
+        }
         let debug_id = DebugId::get(ctx);
         let aa = match samples {
             conf::NumSamples::One => AaMode::Single,
             s => AaMode::Multi(s as u8),
         };
         let kind = Kind::D2(width, height, aa);
-        let levels = 1;
-        let color_format = ctx.gfx_context.color_format();
-        let factory = &mut ctx.gfx_context.factory;
-        let texture_create_info = gfx::texture::Info {
-            kind,
-            levels,
-            format: color_format.0,
-            bind: Bind::SHADER_RESOURCE | Bind::RENDER_TARGET | Bind::TRANSFER_SRC,
-            usage: Usage::Data,
-        };
-        let tex = factory.create_texture_raw(texture_create_info, Some(color_format.1), None)?;
-        let resource_desc = gfx::texture::ResourceDesc {
-            channel: color_format.1,
-            layer: None,
-            min: 0,
-            max: levels - 1,
-            swizzle: Swizzle::new(),
-        };
-        let resource = factory.view_texture_as_shader_resource_raw(&tex, resource_desc)?;
-        let render_desc = gfx::texture::RenderDesc {
-            channel: color_format.1,
-            level: 0,
-            layer: None,
-        };
-        let target = factory.view_texture_as_render_target_raw(&tex, render_desc)?;
-        Ok(Canvas {
-            target,
-            image: Image {
+        let mut attachments = (0..color_attachments)
+            .map(|_| create_color_attachment(ctx, kind, debug_id))
+            .collect::<GameResult<Vec<_>>>()?;
+        let (target, image) = attachments.remove(0);
+
+        let (depth_stencil, depth_image) = if depth || stencil {
+            let depth_format = ctx.gfx_context.depth_format();
+            let factory = &mut *ctx.gfx_context.factory;
+            let bind = Bind::SHADER_RESOURCE | Bind::DEPTH_STENCIL;
+            let (tex, view) =
+                create_depth_stencil_target::<GlBackendSpec>(factory, kind, depth_format, bind)?;
+            let resource_desc = gfx::texture::ResourceDesc {
+                channel: depth_format.1,
+                layer: None,
+                min: 0,
+                max: 0,
+                swizzle: Swizzle::new(),
+            };
+            let resource = factory.view_texture_as_shader_resource_raw(&tex, resource_desc)?;
+            let image = Image {
                 texture: resource,
                 texture_handle: tex,
                 sampler_info: ctx.gfx_context.default_sampler_info,
@@ -88,10 +164,80 @@ impl Canvas {
                 width,
                 height,
                 debug_id,
-            },
+            };
+            (Some(view), Some(image))
+        } else {
+            (None, None)
+        };
+        Ok(Canvas {
+            target,
+            depth_stencil,
+            image,
+            attachments,
+            depth_image,
             debug_id,
         })
     }
+}
+
+/// Creates one color attachment of a `Canvas`, as a render
+/// target and an `Image` that views the same texture.
+fn create_color_attachment(
+    ctx: &mut Context,
+    kind: Kind,
+    debug_id: DebugId,
+) -> GameResult<(RawRenderTargetView<gfx_device_gl::Resources>, Image)> {
+    let (width, height, _, _) = kind.get_dimensions();
+    let levels = 1;
+    let color_format = ctx.gfx_context.color_format();
+    let factory = &mut ctx.gfx_context.factory;
+    let texture_create_info = gfx::texture::Info {
+        kind,
+        levels,
+        format: color_format.0,
+        bind: Bind::SHADER_RESOURCE | Bind::RENDER_TARGET | Bind::TRANSFER_SRC,
+        usage: Usage::Data,
+    };
+    let tex = factory.create_texture_raw(texture_create_info, Some(color_format.1), None)?;
+    let resource_desc = gfx::texture::ResourceDesc {
+        channel: color_format.1,
+        layer: None,
+        min: 0,
+        max: levels - 1,
+        swizzle: Swizzle::new(),
+    };
+    let resource = factory.view_texture_as_shader_resource_raw(&tex, resource_desc)?;
+    let render_desc = gfx::texture::RenderDesc {
+        channel: color_format.1,
+        level: 0,
+        layer: None,
+    };
+    let target = factory.view_texture_as_render_target_raw(&tex, render_desc)?;
+    let image = Image {
+        texture: resource,
+        texture_handle: tex,
+        sampler_info: ctx.gfx_context.default_sampler_info,
+        blend_mode: None,
+        width,
+        height,
+        debug_id,
+    };
+    Ok((target, image))
+}
+
+impl Canvas {
+    /// Create a new `Canvas` with the given size and number of samples.
+    /// See [`CanvasBuilder`](struct.CanvasBuilder.html) for more options.
+    pub fn new(
+        ctx: &mut Context,
+        width: u16,
+        height: u16,
+        samples: conf::NumSamples,
+    ) -> GameResult<Canvas> {
+        CanvasBuilder::new(width, height)
+            .samples(samples)
+            .build(ctx)
+    }
 
     /// Create a new `Canvas` with the current window dimensions.
     pub fn with_window_size(ctx: &mut Context) -> GameResult<Canvas> {
@@ -101,19 +247,47 @@ impl Canvas {
         Canvas::new(ctx, w as u16, h as u16, conf::NumSamples::One)
     }
 
-    /// Gets the backend `Image` that is being rendered to.
+    /// Gets the backend `Image` that is being rendered to.  For a
+    /// canvas with several color attachments, this is the first one.
     pub fn image(&self) -> &Image {
         &self.image
     }
 
+    /// Gets the `Image` of the color attachment with the given index,
+    /// which shaders write to through the output `Target<index>`, or
+    /// `None` if the canvas doesn't have that many attachments.
+    pub fn attachment(&self, index: usize) -> Option<&Image> {
+        match index {
+            0 => Some(&self.image),
+            i => self.attachments.get(i - 1).map(|(_, image)| image),
+        }
+    }
+
+    /// Returns how many color attachments the canvas has.
+    pub fn attachment_count(&self) -> usize {
+        self.attachments.len() + 1
+    }
+
+    /// Gets the depth buffer as an `Image`, if the canvas has one.
+    /// The depth is in the red channel, from 0.0 at the front to 1.0
+    /// at the back.  It is meant to be sampled in shaders; reading it
+    /// back with [`Image::to_rgba8()`](type.Image.html#method.to_rgba8)
+    /// doesn't work.
+    pub fn depth_image(&self) -> Option<&Image> {
+        self.depth_image.as_ref()
+    }
+
     /// Get the filter mode for the image.
     pub fn filter(&self) -> FilterMode {
         self.image.filter()
     }
 
-    /// Set the filter mode for the canvas.
+    /// Set the filter mode for the canvas, and all its color attachments.
     pub fn set_filter(&mut self, mode: FilterMode) {
-        self.image.set_filter(mode)
+        self.image.set_filter(mode);
+        for (_, image) in &mut self.attachments {
+            image.set_filter(mode);
+        }
     }
 
     /// Destroys the `Canvas` and returns the `Image` it contains.
@@ -155,10 +329,20 @@ pub fn set_canvas(ctx: &mut Context, target: Option<&Canvas>) {
     match target {
         Some(surface) => {
             surface.debug_id.assert(ctx);
-            ctx.gfx_context.data.out = surface.target.clone();
-        }
-        None => {
-            ctx.gfx_context.data.out = ctx.gfx_context.screen_render_target.clone();
+            // Outputs past the canvas's last attachment just get the
+            // first one; shaders can't write to them anyway.
+            let target = |i: usize| match surface.attachments.get(i) {
+                Some((target, _)) => target.clone(),
+                None => surface.target.clone(),
+            };
+            let gfx = &mut ctx.gfx_context;
+            gfx.data.out = surface.target.clone();
+            gfx.data.out1 = target(0);
+            gfx.data.out2 = target(1);
+            gfx.data.out3 = target(2);
+            gfx.color_attachments = surface.attachment_count();
+            gfx.current_depth_stencil = surface.depth_stencil.clone();
         }
+        None => ctx.gfx_context.set_screen_target(),
     };
 }
@@ -26,6 +26,7 @@ where
     shader_globals: Globals,
     pub(crate) projection: Matrix4,
     pub(crate) modelview_stack: Vec<Matrix4>,
+    pub(crate) scissor_stack: Vec<Option<Rect>>,
     pub(crate) white_image: ImageGeneric<B>,
     pub(crate) screen_rect: Rect,
     color_format: gfx::format::Format,
@@ -33,14 +34,26 @@ where
     srgb: bool,
 
     pub(crate) backend_spec: B,
-    pub(crate) window: glutin::WindowedContext,
+    /// The window we draw to, or `None` if we are running headless.
+    pub(crate) window: Option<glutin::WindowedContext>,
+    /// The windowless GL context we draw with if we are running headless.
+    /// We never touch it again after setup, but it must outlive the device.
+    #[allow(dead_code)]
+    pub(crate) headless_context: Option<HeadlessContext>,
     pub(crate) multisample_samples: u8,
     pub(crate) device: Box<B::Device>,
     pub(crate) factory: Box<B::Factory>,
     pub(crate) encoder: gfx::Encoder<B::Resources, B::CommandBuffer>,
     pub(crate) screen_render_target: gfx::handle::RawRenderTargetView<B::Resources>,
-    #[allow(dead_code)]
     pub(crate) depth_view: gfx::handle::RawDepthStencilView<B::Resources>,
+    /// The depth and stencil buffer of whatever we are drawing to,
+    /// if it has one.  The screen always does.
+    pub(crate) current_depth_stencil: Option<gfx::handle::RawDepthStencilView<B::Resources>>,
+    pub(crate) stencil_mode: StencilMode,
+    pub(crate) depth_mode: DepthMode,
+    /// How many color attachments whatever we are drawing to has,
+    /// set in `data.out` through `data.out3`.  The screen has one.
+    pub(crate) color_attachments: usize,
 
     pub(crate) data: pipe::Data<B::Resources>,
     pub(crate) quad_slice: gfx::Slice<B::Resources>,
@@ -52,6 +65,7 @@ where
     default_shader: ShaderId,
     pub(crate) current_shader: Rc<RefCell<Option<ShaderId>>>,
     pub(crate) shaders: Vec<Box<dyn ShaderHandle<B>>>,
+    pub(crate) shader_watcher: ShaderWatcher,
 
     pub(crate) glyph_brush: Rc<RefCell<GlyphBrush<'static, DrawParam>>>,
     pub(crate) glyph_cache: ImageGeneric<B>,
@@ -71,10 +85,11 @@ where
 pub(crate) type GraphicsContext = GraphicsContextGeneric<GlBackendSpec>;
 
 impl GraphicsContextGeneric<GlBackendSpec> {
-    /// Create a new GraphicsContext
+    /// Create a new GraphicsContext, with a window if there is an
+    /// events loop to make it with, or else headless.
     pub(crate) fn new(
         filesystem: &mut Filesystem,
-        events_loop: &winit::EventsLoop,
+        events_loop: Option<&winit::EventsLoop>,
         window_setup: &WindowSetup,
         window_mode: WindowMode,
         backend: GlBackendSpec,
@@ -98,81 +113,118 @@ impl GraphicsContextGeneric<GlBackendSpec> {
         );
 
         // WINDOW SETUP
-        let gl_builder = glutin::ContextBuilder::new()
-            .with_gl(glutin::GlRequest::Specific(
-                backend.api(),
-                backend.version_tuple(),
-            ))
-            .with_gl_profile(glutin::GlProfile::Core)
-            .with_multisampling(window_setup.samples as u16)
-            // 24 color bits, 8 alpha bits
-            .with_pixel_format(24, 8)
-            .with_vsync(window_setup.vsync);
-
-        let dpi = events_loop.get_primary_monitor().get_hidpi_factor();
-
-        let width = f64::from(window_mode.width) / dpi;
-        let height = f64::from(window_mode.height) / dpi;
-
-        let window_size = dpi::LogicalSize::from((width, height));
-
-        let mut window_builder = winit::WindowBuilder::new()
-            .with_title(window_setup.title.clone())
-            .with_dimensions(window_size)
-            .with_resizable(window_mode.resizable);
-
-        window_builder = if !window_setup.icon.is_empty() {
-            let icon = load_icon(window_setup.icon.as_ref(), filesystem)?;
-            window_builder.with_window_icon(Some(icon))
-        } else {
-            window_builder
-        };
-
-        let (window, device, mut factory, screen_render_target, depth_view) = backend.init(
-            window_builder,
-            gl_builder,
-            events_loop,
-            color_format,
-            depth_format,
-        )?;
-
-        // see winit #548 about DPI.
-        // We basically ignore it and if it's wrong, that's a winit bug
-        // since we have no good control over it.
-        {
-            // Log a bunch of OpenGL state info pulled out of winit and gfx
-            let dpi::LogicalSize {
-                width: w,
-                height: h,
-            } = window
-                .get_outer_size()
-                .ok_or_else(|| GameError::VideoError("Window doesn't exist!".to_owned()))?;
-            let dpi::LogicalSize {
-                width: dw,
-                height: dh,
-            } = window
-                .get_inner_size()
-                .ok_or_else(|| GameError::VideoError("Window doesn't exist!".to_owned()))?;
-            let hidpi_factor = window.get_hidpi_factor();
-            debug!(
-                "Window created, desired size {}x{}, hidpi factor {}.",
-                window_mode.width, window_mode.height, hidpi_factor
-            );
-            let (major, minor) = backend.version_tuple();
-            debug!(
-                "  Window logical outer size: {}x{}, logical drawable size: {}x{}",
-                w, h, dw, dh
-            );
-            let device_info = backend.info(&device);
-            debug!(
-                "  Asked for   : {:?} {}.{} Core, vsync: {}",
-                backend.api(),
-                major,
-                minor,
-                window_setup.vsync
-            );
-            debug!("  Actually got: {}", device_info);
-        }
+        let (window, headless_context, device, mut factory, screen_render_target, depth_view) =
+            match events_loop {
+                None => {
+                    let (context, device, factory, screen_render_target, depth_view) = backend
+                        .init_headless(
+                            window_mode.width as u16,
+                            window_mode.height as u16,
+                            color_format,
+                            depth_format,
+                        )?;
+                    let device_info = backend.info(&device);
+                    debug!(
+                        "Headless context created, offscreen target size {}x{}.",
+                        window_mode.width, window_mode.height
+                    );
+                    debug!("  Actually got: {}", device_info);
+                    (
+                        None,
+                        Some(context),
+                        device,
+                        factory,
+                        screen_render_target,
+                        depth_view,
+                    )
+                }
+                Some(events_loop) => {
+                    let gl_builder = glutin::ContextBuilder::new()
+                        .with_gl(glutin::GlRequest::Specific(
+                            backend.api(),
+                            backend.version_tuple(),
+                        ))
+                        .with_gl_profile(glutin::GlProfile::Core)
+                        .with_multisampling(window_setup.samples as u16)
+                        // 24 color bits, 8 alpha bits
+                        .with_pixel_format(24, 8)
+                        .with_vsync(window_setup.vsync);
+
+                    let dpi = events_loop.get_primary_monitor().get_hidpi_factor();
+
+                    let width = f64::from(window_mode.width) / dpi;
+                    let height = f64::from(window_mode.height) / dpi;
+
+                    let window_size = dpi::LogicalSize::from((width, height));
+
+                    let mut window_builder = winit::WindowBuilder::new()
+                        .with_title(window_setup.title.clone())
+                        .with_dimensions(window_size)
+                        .with_resizable(window_mode.resizable);
+
+                    window_builder = if !window_setup.icon.is_empty() {
+                        let icon = load_icon(window_setup.icon.as_ref(), filesystem)?;
+                        window_builder.with_window_icon(Some(icon))
+                    } else {
+                        window_builder
+                    };
+
+                    let (window, device, factory, screen_render_target, depth_view) = backend
+                        .init(
+                            window_builder,
+                            gl_builder,
+                            events_loop,
+                            color_format,
+                            depth_format,
+                        )?;
+
+                    // see winit #548 about DPI.
+                    // We basically ignore it and if it's wrong, that's a winit bug
+                    // since we have no good control over it.
+                    {
+                        // Log a bunch of OpenGL state info pulled out of winit and gfx
+                        let dpi::LogicalSize {
+                            width: w,
+                            height: h,
+                        } = window.get_outer_size().ok_or_else(|| {
+                            GameError::VideoError("Window doesn't exist!".to_owned())
+                        })?;
+                        let dpi::LogicalSize {
+                            width: dw,
+                            height: dh,
+                        } = window.get_inner_size().ok_or_else(|| {
+                            GameError::VideoError("Window doesn't exist!".to_owned())
+                        })?;
+                        let hidpi_factor = window.get_hidpi_factor();
+                        debug!(
+                            "Window created, desired size {}x{}, hidpi factor {}.",
+                            window_mode.width, window_mode.height, hidpi_factor
+                        );
+                        let (major, minor) = backend.version_tuple();
+                        debug!(
+                            "  Window logical outer size: {}x{}, logical drawable size: {}x{}",
+                            w, h, dw, dh
+                        );
+                        let device_info = backend.info(&device);
+                        debug!(
+                            "  Asked for   : {:?} {}.{} Core, vsync: {}",
+                            backend.api(),
+                            major,
+                            minor,
+                            window_setup.vsync
+                        );
+                        debug!("  Actually got: {}", device_info);
+                    }
+                    (
+                        Some(window),
+                        None,
+                        device,
+                        factory,
+                        screen_render_target,
+                        depth_view,
+                    )
+                }
+            };
 
         // GFX SETUP
         let mut encoder = GlBackendSpec::encoder(&mut factory);
@@ -231,12 +283,22 @@ impl GraphicsContextGeneric<GlBackendSpec> {
         let texture = white_image.texture.clone();
         let typed_thingy = backend.raw_to_typed_shader_resource(texture);
 
+        let (target_width, target_height, _, _) = screen_render_target.get_dimensions();
         let data = pipe::Data {
             vbuf: quad_vertex_buffer.clone(),
             tex: (typed_thingy, sampler),
             rect_instance_properties: rect_inst_props,
+            scissor: gfx::Rect {
+                x: 0,
+                y: 0,
+                w: target_width,
+                h: target_height,
+            },
             globals: globals_buffer,
             out: screen_render_target.clone(),
+            out1: screen_render_target.clone(),
+            out2: screen_render_target.clone(),
+            out3: screen_render_target.clone(),
         };
 
         // Glyph cache stuff.
@@ -273,6 +335,7 @@ impl GraphicsContextGeneric<GlBackendSpec> {
             shader_globals: globals,
             projection: initial_projection,
             modelview_stack: vec![initial_transform],
+            scissor_stack: vec![None],
             white_image,
             screen_rect: Rect::new(left, top, right - left, bottom - top),
             color_format,
@@ -281,12 +344,17 @@ impl GraphicsContextGeneric<GlBackendSpec> {
 
             backend_spec: backend,
             window,
+            headless_context,
             multisample_samples,
             device: Box::new(device as <GlBackendSpec as BackendSpec>::Device),
             factory: Box::new(factory as <GlBackendSpec as BackendSpec>::Factory),
             encoder,
             screen_render_target,
+            current_depth_stencil: Some(depth_view.clone()),
             depth_view,
+            stencil_mode: StencilMode::Off,
+            depth_mode: DepthMode::Off,
+            color_attachments: 1,
 
             data,
             quad_slice,
@@ -298,6 +366,7 @@ impl GraphicsContextGeneric<GlBackendSpec> {
             default_shader: shader.shader_id(),
             current_shader: Rc::new(RefCell::new(None)),
             shaders: vec![draw],
+            shader_watcher: ShaderWatcher::new(),
 
             glyph_brush: Rc::new(RefCell::new(glyph_brush)),
             glyph_cache,
@@ -321,6 +390,81 @@ impl GraphicsContextGeneric<GlBackendSpec> {
     }
 }
 
+/// Creates a color and depth target for headless contexts to render to
+/// in place of a window's framebuffer.
+pub(crate) fn create_offscreen_target<B>(
+    factory: &mut B::Factory,
+    width: u16,
+    height: u16,
+    color_format: gfx::format::Format,
+    depth_format: gfx::format::Format,
+) -> GameResult<(
+    gfx::handle::RawRenderTargetView<B::Resources>,
+    gfx::handle::RawDepthStencilView<B::Resources>,
+)>
+where
+    B: BackendSpec,
+{
+    use gfx::memory::{Bind, Usage};
+    use gfx::texture;
+
+    let kind = texture::Kind::D2(width, height, texture::AaMode::Single);
+    let gfx::format::Format(color_surface, color_channel) = color_format;
+    let color_info = texture::Info {
+        kind,
+        levels: 1,
+        format: color_surface,
+        bind: Bind::SHADER_RESOURCE | Bind::RENDER_TARGET | Bind::TRANSFER_SRC,
+        usage: Usage::Data,
+    };
+    let color_tex = factory.create_texture_raw(color_info, Some(color_channel), None)?;
+    let render_desc = texture::RenderDesc {
+        channel: color_channel,
+        level: 0,
+        layer: None,
+    };
+    let color_view = factory.view_texture_as_render_target_raw(&color_tex, render_desc)?;
+    let (_, depth_view) =
+        create_depth_stencil_target::<B>(factory, kind, depth_format, Bind::DEPTH_STENCIL)?;
+    Ok((color_view, depth_view))
+}
+
+/// Creates a depth and stencil buffer to go with a color
+/// target of the given kind.  `bind` should include
+/// `Bind::DEPTH_STENCIL`, plus anything else the texture
+/// will be used for.
+pub(crate) fn create_depth_stencil_target<B>(
+    factory: &mut B::Factory,
+    kind: texture::Kind,
+    depth_format: gfx::format::Format,
+    bind: gfx::memory::Bind,
+) -> GameResult<(
+    gfx::handle::RawTexture<B::Resources>,
+    gfx::handle::RawDepthStencilView<B::Resources>,
+)>
+where
+    B: BackendSpec,
+{
+    use gfx::memory::Usage;
+
+    let gfx::format::Format(depth_surface, depth_channel) = depth_format;
+    let depth_info = texture::Info {
+        kind,
+        levels: 1,
+        format: depth_surface,
+        bind,
+        usage: Usage::Data,
+    };
+    let depth_tex = factory.create_texture_raw(depth_info, Some(depth_channel), None)?;
+    let depth_desc = texture::DepthStencilDesc {
+        level: 0,
+        layer: None,
+        flags: texture::DepthStencilFlags::empty(),
+    };
+    let depth_view = factory.view_texture_as_depth_stencil_raw(&depth_tex, depth_desc)?;
+    Ok((depth_tex, depth_view))
+}
+
 // This is kinda awful 'cause it copies a couple times,
 // but still better than
 // having `winit` try to do the image loading for us.
@@ -406,6 +550,74 @@ where
         *last
     }
 
+    /// Pushes a scissor rect to the top of the scissor stack.
+    pub(crate) fn push_scissor(&mut self, rect: Option<Rect>) {
+        self.scissor_stack.push(rect);
+    }
+
+    /// Pops the current scissor rect off the top of the scissor
+    /// stack.  Will never pop the last one.
+    pub(crate) fn pop_scissor(&mut self) {
+        if self.scissor_stack.len() > 1 {
+            let _ = self.scissor_stack.pop();
+        }
+    }
+
+    /// Sets the scissor rect at the top of the scissor stack.
+    pub(crate) fn set_scissor(&mut self, rect: Option<Rect>) {
+        let last = self
+            .scissor_stack
+            .last_mut()
+            .expect("Scissor stack empty; should never happen!");
+        *last = rect;
+    }
+
+    /// Gets the scissor rect at the top of the scissor stack.
+    pub(crate) fn scissor(&self) -> Option<Rect> {
+        *self
+            .scissor_stack
+            .last()
+            .expect("Scissor stack empty; should never happen!")
+    }
+
+    /// Maps the current scissor rect from screen coordinates onto the
+    /// pixels of the current render target, with the origin at the
+    /// bottom-left like OpenGL wants it.  With no scissor rect this is
+    /// the whole target.
+    fn scissor_target_rect(&self) -> gfx::Rect {
+        let (width, height, _, _) = self.data.out.get_dimensions();
+        let rect = match self.scissor() {
+            Some(rect) => rect,
+            None => {
+                return gfx::Rect {
+                    x: 0,
+                    y: 0,
+                    w: width,
+                    h: height,
+                }
+            }
+        };
+        let screen = self.screen_rect;
+        let (w, h) = (f32::from(width), f32::from(height));
+        // The screen rect may be flipped either way, so work out
+        // where both edges land and sort them out afterwards.
+        let x0 = (rect.left() - screen.x) / screen.w * w;
+        let x1 = (rect.right() - screen.x) / screen.w * w;
+        let y0 = (1.0 - (rect.top() - screen.y) / screen.h) * h;
+        let y1 = (1.0 - (rect.bottom() - screen.y) / screen.h) * h;
+        let clamp = |v: f32, max: f32| v.round().max(0.0).min(max) as u16;
+        let left = clamp(x0.min(x1), w);
+        let right = clamp(x0.max(x1), w);
+        let bottom = clamp(y0.min(y1), h);
+        let top = clamp(y0.max(y1), h);
+        gfx::Rect {
+            x: left,
+            y: bottom,
+            w: right - left,
+            h: top - bottom,
+        }
+    }
+
     /// Converts the given `DrawParam` into an `InstanceProperties` object and
     /// sends it to the graphics card at the front of the instance buffer.
     pub(crate) fn update_instance_properties(&mut self, draw_params: DrawTransform) -> GameResult {
@@ -420,14 +632,55 @@ where
     /// Draws with the current encoder, slice, and pixel shader. Prefer calling
     /// this method from `Drawables` so that the pixel shader gets used
     pub(crate) fn draw(&mut self, slice: Option<&gfx::Slice<B::Resources>>) -> GameResult {
+        let attributes = AttributeBuffers {
+            vertices: None,
+            instances: None,
+        };
+        self.draw_with_attributes(slice, attributes)
+    }
+
+    /// Like `draw()`, but with buffers of custom vertex or instance
+    /// attributes for shaders that use them.
+    pub(crate) fn draw_with_attributes(
+        &mut self,
+        slice: Option<&gfx::Slice<B::Resources>>,
+        attributes: AttributeBuffers<B::Resources>,
+    ) -> GameResult {
+        self.data.scissor = self.scissor_target_rect();
         let slice = slice.unwrap_or(&self.quad_slice);
         let id = (*self.current_shader.borrow()).unwrap_or(self.default_shader);
-        let shader_handle = &self.shaders[id];
+        let shader_handle = &mut self.shaders[id];
 
-        shader_handle.draw(&mut self.encoder, slice, &self.data)?;
+        let target = TargetState {
+            stencil: self.stencil_mode,
+            depth: self.depth_mode,
+            depth_stencil: self.current_depth_stencil.as_ref(),
+            color_attachments: self.color_attachments,
+        };
+
+        shader_handle.draw(
+            &mut self.encoder,
+            &mut *self.factory,
+            slice,
+            &self.data,
+            &target,
+            &attributes,
+        )?;
         Ok(())
     }
 
+    /// Makes the screen the target of drawing again, rather than
+    /// whatever canvas was set.
+    pub(crate) fn set_screen_target(&mut self) {
+        let target = &self.screen_render_target;
+        self.data.out = target.clone();
+        self.data.out1 = target.clone();
+        self.data.out2 = target.clone();
+        self.data.out3 = target.clone();
+        self.color_attachments = 1;
+        self.current_depth_stencil = Some(self.depth_view.clone());
+    }
+
     /// Sets the blend mode of the active shader
     pub(crate) fn set_blend_mode(&mut self, mode: BlendMode) -> GameResult {
         let id = (*self.current_shader.borrow()).unwrap_or(self.default_shader);
@@ -447,57 +700,8 @@ where
     ///
     /// Call `update_globals()` to apply it after calling this.
     pub(crate) fn set_projection_rect(&mut self, rect: Rect) {
-        /// Creates an orthographic projection matrix.
-        /// Because nalgebra gets frumple when you try to make
-        /// one that is upside-down.
-        /// This is fixed now (issue here: https://github.com/rustsim/nalgebra/issues/365)
-        /// but removing this kinda isn't worth it.
-        fn ortho(
-            left: f32,
-            right: f32,
-            top: f32,
-            bottom: f32,
-            far: f32,
-            near: f32,
-        ) -> [[f32; 4]; 4] {
-            let c0r0 = 2.0 / (right - left);
-            let c0r1 = 0.0;
-            let c0r2 = 0.0;
-            let c0r3 = 0.0;
-
-            let c1r0 = 0.0;
-            let c1r1 = 2.0 / (top - bottom);
-            let c1r2 = 0.0;
-            let c1r3 = 0.0;
-
-            let c2r0 = 0.0;
-            let c2r1 = 0.0;
-            let c2r2 = -2.0 / (far - near);
-            let c2r3 = 0.0;
-
-            let c3r0 = -(right + left) / (right - left);
-            let c3r1 = -(top + bottom) / (top - bottom);
-            let c3r2 = -(far + near) / (far - near);
-            let c3r3 = 1.0;
-
-            // our matrices are column-major, so here we are.
-            [
-                [c0r0, c0r1, c0r2, c0r3],
-                [c1r0, c1r1, c1r2, c1r3],
-                [c2r0, c2r1, c2r2, c2r3],
-                [c3r0, c3r1, c3r2, c3r3],
-            ]
-        }
-
         self.screen_rect = rect;
-        self.projection = Matrix4::from(ortho(
-            rect.x,
-            rect.x + rect.w,
-            rect.y,
-            rect.y + rect.h,
-            -1.0,
-            1.0,
-        ));
+        self.projection = ortho_projection(rect);
     }
 
     /// Sets the raw projection matrix to the given Matrix.
@@ -513,8 +717,16 @@ where
     }
 
     /// Sets window mode from a WindowMode object.
+    ///
+    /// If we are headless, this just resizes the offscreen target.
     pub(crate) fn set_window_mode(&mut self, mode: WindowMode) -> GameResult {
-        let window = &self.window;
+        if self.window.is_none() {
+            return self.resize_offscreen_target(mode.width as u16, mode.height as u16);
+        }
+        let window = self
+            .window
+            .as_ref()
+            .expect("Window doesn't exist; should never happen");
         let dpi = window.get_hidpi_factor();
 
         let mode = WindowMode {
@@ -587,18 +799,70 @@ where
     /// so it may cause squirrelliness to
     /// happen with canvases or other things that touch it.
     pub(crate) fn resize_viewport(&mut self) {
+        let window = match self.window.as_ref() {
+            Some(window) => window,
+            // Offscreen targets only change size through `set_window_mode()`.
+            None => return,
+        };
         if let Some((cv, dv)) = self.backend_spec.resize_viewport(
             &self.screen_render_target,
             &self.depth_view,
             self.color_format(),
             self.depth_format(),
-            &self.window,
+            window,
         ) {
             self.screen_render_target = cv;
             self.depth_view = dv;
         }
     }
 
+    /// Replaces the offscreen screen target of a headless context
+    /// with a new one of the given size, if the size actually changed.
+    ///
+    /// Like `resize_viewport()` this replaces gfx.screen_render_target
+    /// and gfx.depth_view.  If we are drawing to the screen rather than
+    /// a canvas we carry on drawing to the new target.
+    fn resize_offscreen_target(&mut self, width: u16, height: u16) -> GameResult {
+        let (w, h, _, _) = self.screen_render_target.get_dimensions();
+        if (w, h) == (width, height) {
+            return Ok(());
+        }
+        let (cv, dv) = create_offscreen_target::<B>(
+            &mut *self.factory,
+            width,
+            height,
+            self.color_format(),
+            self.depth_format(),
+        )?;
+        let drawing_to_screen = self.data.out == self.screen_render_target;
+        self.screen_render_target = cv;
+        self.depth_view = dv;
+        if drawing_to_screen {
+            self.set_screen_target();
+        }
+        Ok(())
+    }
+
+    /// Returns the hidpi factor of the window, or 1.0 if
+    /// there is no window.
+    pub(crate) fn hidpi_factor(&self) -> f64 {
+        self.window
+            .as_ref()
+            .map(|window| window.get_hidpi_factor())
+            .unwrap_or(1.0)
+    }
+
+    /// Returns the size of the offscreen target we draw to when headless,
+    /// in pixels, or `None` if we have a real window.
+    pub(crate) fn offscreen_size(&self) -> Option<(f32, f32)> {
+        if self.window.is_some() {
+            None
+        } else {
+            let (w, h, _, _) = self.screen_render_target.get_dimensions();
+            Some((f32::from(w), f32::from(h)))
+        }
+    }
+
     /// Returns the screen color format used by the context.
     pub(crate) fn color_format(&self) -> gfx::format::Format {
         self.color_format
@@ -620,3 +884,51 @@ where
         }
     }
 }
+
+/// Creates an orthographic projection matrix that maps the given `Rect`
+/// onto whatever is being drawn to, like `set_screen_coordinates()` does.
+pub(crate) fn ortho_projection(rect: Rect) -> Matrix4 {
+    /// Creates an orthographic projection matrix.
+    /// Because nalgebra gets frumple when you try to make
+    /// one that is upside-down.
+    /// This is fixed now (issue here: https://github.com/rustsim/nalgebra/issues/365)
+    /// but removing this kinda isn't worth it.
+    fn ortho(left: f32, right: f32, top: f32, bottom: f32, far: f32, near: f32) -> [[f32; 4]; 4] {
+        let c0r0 = 2.0 / (right - left);
+        let c0r1 = 0.0;
+        let c0r2 = 0.0;
+        let c0r3 = 0.0;
+
+        let c1r0 = 0.0;
+        let c1r1 = 2.0 / (top - bottom);
+        let c1r2 = 0.0;
+        let c1r3 = 0.0;
+
+        let c2r0 = 0.0;
+        let c2r1 = 0.0;
+        let c2r2 = -2.0 / (far - near);
+        let c2r3 = 0.0;
+
+        let c3r0 = -(right + left) / (right - left);
+        let c3r1 = -(top + bottom) / (top - bottom);
+        let c3r2 = -(far + near) / (far - near);
+        let c3r3 = 1.0;
+
+        // our matrices are column-major, so here we are.
+        [
+            [c0r0, c0r1, c0r2, c0r3],
+            [c1r0, c1r1, c1r2, c1r3],
+            [c2r0, c2r1, c2r2, c2r3],
+            [c3r0, c3r1, c3r2, c3r3],
+        ]
+    }
+
+    Matrix4::from(ortho(
+        rect.x,
+        rect.x + rect.w,
+        rect.y,
+        rect.y + rect.h,
+        -1.0,
+        1.0,
+    ))
+}
@@ -0,0 +1,396 @@
+//! The GL context a headless `Context` draws with.
+//!
+//! glutin can only make a GL context with an `EventsLoop`, which on Linux
+//! means connecting to an X or Wayland server, so on Linux and the BSDs
+//! we ask EGL for a context that isn't attached to any display at all.
+//! That works with Mesa's software renderer on machines with no GPU and
+//! no display server, such as CI machines.  Elsewhere there is always a
+//! window system to ask, so glutin makes a hidden one.
+
+// Talking to EGL is all FFI.
+#![allow(unsafe_code)]
+
+use std::fmt;
+use std::os::raw::c_void;
+
+use glutin;
+
+use crate::error::GameResult;
+
+/// A GL context with no window, for a headless `Context` to draw with.
+/// It has to outlive the gfx device made from it.
+pub struct HeadlessContext(imp::Context);
+
+impl HeadlessContext {
+    /// Creates a GL context of the given API and version and makes it
+    /// current on this thread.
+    pub(crate) fn new(api: glutin::Api, version: (u8, u8)) -> GameResult<Self> {
+        imp::Context::new(api, version).map(HeadlessContext)
+    }
+
+    /// Looks up a GL function, for gfx to load them with.
+    pub(crate) fn get_proc_address(&self, name: &str) -> *const c_void {
+        self.0.get_proc_address(name)
+    }
+}
+
+impl fmt::Debug for HeadlessContext {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "<HeadlessContext: {:p}>", self)
+    }
+}
+
+#[cfg(all(unix, not(any(target_os = "macos", target_os = "ios"))))]
+mod imp {
+    use std::ffi::{CStr, CString};
+    use std::os::raw::{c_char, c_void};
+    use std::ptr;
+
+    use glutin;
+    use libloading::Library;
+
+    use crate::error::{GameError, GameResult};
+
+    type EGLBoolean = u32;
+    type EGLenum = u32;
+    type EGLint = i32;
+    type EGLDisplay = *mut c_void;
+    type EGLConfig = *mut c_void;
+    type EGLContext = *mut c_void;
+    type EGLSurface = *mut c_void;
+
+    const EGL_FALSE: EGLBoolean = 0;
+    const EGL_NONE: EGLint = 0x3038;
+    const EGL_EXTENSIONS: EGLint = 0x3055;
+    const EGL_SURFACE_TYPE: EGLint = 0x3033;
+    const EGL_PBUFFER_BIT: EGLint = 0x0001;
+    const EGL_RENDERABLE_TYPE: EGLint = 0x3040;
+    const EGL_OPENGL_BIT: EGLint = 0x0008;
+    const EGL_OPENGL_ES2_BIT: EGLint = 0x0004;
+    const EGL_OPENGL_ES3_BIT_KHR: EGLint = 0x0040;
+    const EGL_WIDTH: EGLint = 0x3057;
+    const EGL_HEIGHT: EGLint = 0x3056;
+    const EGL_OPENGL_API: EGLenum = 0x30A2;
+    const EGL_OPENGL_ES_API: EGLenum = 0x30A0;
+    const EGL_CONTEXT_MAJOR_VERSION_KHR: EGLint = 0x3098;
+    const EGL_CONTEXT_MINOR_VERSION_KHR: EGLint = 0x30FB;
+    const EGL_CONTEXT_OPENGL_PROFILE_MASK_KHR: EGLint = 0x30FD;
+    const EGL_CONTEXT_OPENGL_CORE_PROFILE_BIT_KHR: EGLint = 0x0001;
+    const EGL_PLATFORM_SURFACELESS_MESA: EGLenum = 0x31DD;
+
+    type GetProcAddress = unsafe extern "C" fn(*const c_char) -> *const c_void;
+    type GetError = unsafe extern "C" fn() -> EGLint;
+    type QueryString = unsafe extern "C" fn(EGLDisplay, EGLint) -> *const c_char;
+    type GetDisplay = unsafe extern "C" fn(*mut c_void) -> EGLDisplay;
+    type GetPlatformDisplay =
+        unsafe extern "C" fn(EGLenum, *mut c_void, *const EGLint) -> EGLDisplay;
+    type Initialize = unsafe extern "C" fn(EGLDisplay, *mut EGLint, *mut EGLint) -> EGLBoolean;
+    type Terminate = unsafe extern "C" fn(EGLDisplay) -> EGLBoolean;
+    type BindApi = unsafe extern "C" fn(EGLenum) -> EGLBoolean;
+    type ChooseConfig = unsafe extern "C" fn(
+        EGLDisplay,
+        *const EGLint,
+        *mut EGLConfig,
+        EGLint,
+        *mut EGLint,
+    ) -> EGLBoolean;
+    type CreateContext =
+        unsafe extern "C" fn(EGLDisplay, EGLConfig, EGLContext, *const EGLint) -> EGLContext;
+    type DestroyContext = unsafe extern "C" fn(EGLDisplay, EGLContext) -> EGLBoolean;
+    type CreatePbufferSurface =
+        unsafe extern "C" fn(EGLDisplay, EGLConfig, *const EGLint) -> EGLSurface;
+    type DestroySurface = unsafe extern "C" fn(EGLDisplay, EGLSurface) -> EGLBoolean;
+    type MakeCurrent =
+        unsafe extern "C" fn(EGLDisplay, EGLSurface, EGLSurface, EGLContext) -> EGLBoolean;
+
+    /// The EGL functions we use, loaded from `libEGL` at runtime so that
+    /// building ggez doesn't need it.
+    struct Egl {
+        get_proc_address: GetProcAddress,
+        get_error: GetError,
+        query_string: QueryString,
+        get_display: GetDisplay,
+        initialize: Initialize,
+        terminate: Terminate,
+        bind_api: BindApi,
+        choose_config: ChooseConfig,
+        create_context: CreateContext,
+        destroy_context: DestroyContext,
+        create_pbuffer_surface: CreatePbufferSurface,
+        destroy_surface: DestroySurface,
+        make_current: MakeCurrent,
+        // Declared last so the functions above don't outlive it.
+        _library: Library,
+    }
+
+    impl Egl {
+        fn load() -> GameResult<Self> {
+            let library = Library::new("libEGL.so.1")
+                .or_else(|_| Library::new("libEGL.so"))
+                .map_err(|e| {
+                    GameError::VideoError(format!(
+                        "Could not load libEGL for a headless context: {}",
+                        e
+                    ))
+                })?;
+            // The symbols are copied out as plain function pointers,
+            // which stay valid as long as the library is loaded.
+            macro_rules! load {
+                ($type:ty, $name:expr) => {
+                    unsafe {
+                        *library.get::<$type>($name).map_err(|e| {
+                            GameError::VideoError(format!("Could not load an EGL function: {}", e))
+                        })?
+                    }
+                };
+            }
+            Ok(Egl {
+                get_proc_address: load!(GetProcAddress, b"eglGetProcAddress\0"),
+                get_error: load!(GetError, b"eglGetError\0"),
+                query_string: load!(QueryString, b"eglQueryString\0"),
+                get_display: load!(GetDisplay, b"eglGetDisplay\0"),
+                initialize: load!(Initialize, b"eglInitialize\0"),
+                terminate: load!(Terminate, b"eglTerminate\0"),
+                bind_api: load!(BindApi, b"eglBindAPI\0"),
+                choose_config: load!(ChooseConfig, b"eglChooseConfig\0"),
+                create_context: load!(CreateContext, b"eglCreateContext\0"),
+                destroy_context: load!(DestroyContext, b"eglDestroyContext\0"),
+                create_pbuffer_surface: load!(CreatePbufferSurface, b"eglCreatePbufferSurface\0"),
+                destroy_surface: load!(DestroySurface, b"eglDestroySurface\0"),
+                make_current: load!(MakeCurrent, b"eglMakeCurrent\0"),
+                _library: library,
+            })
+        }
+
+        fn error(&self, doing: &str) -> GameError {
+            let code = unsafe { (self.get_error)() };
+            GameError::VideoError(format!(
+                "EGL failed to {} for a headless context (error 0x{:x})",
+                doing, code
+            ))
+        }
+
+        /// Gets the extensions of the display, or of EGL itself for
+        /// no display.
+        fn extensions(&self, display: EGLDisplay) -> Vec<String> {
+            let extensions = unsafe { (self.query_string)(display, EGL_EXTENSIONS) };
+            if extensions.is_null() {
+                return vec![];
+            }
+            let extensions = unsafe { CStr::from_ptr(extensions) };
+            extensions
+                .to_string_lossy()
+                .split_whitespace()
+                .map(str::to_string)
+                .collect()
+        }
+
+        fn proc_address(&self, name: &str) -> *const c_void {
+            let name = match CString::new(name) {
+                Ok(name) => name,
+                Err(_) => return ptr::null(),
+            };
+            unsafe { (self.get_proc_address)(name.as_ptr()) }
+        }
+
+        /// Gets a display that needs no display server: Mesa's surfaceless
+        /// platform if there is one, otherwise the default display, which
+        /// drivers such as NVIDIA's can open without one too.
+        fn headless_display(&self) -> EGLDisplay {
+            let client_extensions = self.extensions(ptr::null_mut());
+            let has = |name: &str| client_extensions.iter().any(|e| e == name);
+            if has("EGL_MESA_platform_surfaceless") && has("EGL_EXT_platform_base") {
+                let get_platform_display = self.proc_address("eglGetPlatformDisplayEXT");
+                if !get_platform_display.is_null() {
+                    let get_platform_display: GetPlatformDisplay =
+                        unsafe { std::mem::transmute(get_platform_display) };
+                    let display = unsafe {
+                        get_platform_display(
+                            EGL_PLATFORM_SURFACELESS_MESA,
+                            ptr::null_mut(),
+                            ptr::null(),
+                        )
+                    };
+                    if !display.is_null() {
+                        return display;
+                    }
+                }
+            }
+            unsafe { (self.get_display)(ptr::null_mut()) }
+        }
+    }
+
+    pub(super) struct Context {
+        egl: Egl,
+        display: EGLDisplay,
+        context: EGLContext,
+        /// A 1x1 surface to make the context current with, if the driver
+        /// can't do without one.  We never draw to it.
+        surface: EGLSurface,
+    }
+
+    impl Context {
+        pub fn new(api: glutin::Api, (major, minor): (u8, u8)) -> GameResult<Self> {
+            let egl = Egl::load()?;
+            let display = egl.headless_display();
+            if display.is_null() {
+                return Err(egl.error("open a display"));
+            }
+            if unsafe { (egl.initialize)(display, ptr::null_mut(), ptr::null_mut()) } == EGL_FALSE {
+                return Err(egl.error("initialize the display"));
+            }
+            // From here on, dropping the context cleans up.
+            let mut context = Context {
+                egl,
+                display,
+                context: ptr::null_mut(),
+                surface: ptr::null_mut(),
+            };
+            context.create(api, major, minor)?;
+            Ok(context)
+        }
+
+        fn create(&mut self, api: glutin::Api, major: u8, minor: u8) -> GameResult {
+            let egl = &self.egl;
+            let surfaceless = egl
+                .extensions(self.display)
+                .iter()
+                .any(|e| e == "EGL_KHR_surfaceless_context");
+            let (egl_api, renderable_type) = match api {
+                glutin::Api::OpenGlEs if major >= 3 => (EGL_OPENGL_ES_API, EGL_OPENGL_ES3_BIT_KHR),
+                glutin::Api::OpenGlEs => (EGL_OPENGL_ES_API, EGL_OPENGL_ES2_BIT),
+                _ => (EGL_OPENGL_API, EGL_OPENGL_BIT),
+            };
+            if unsafe { (egl.bind_api)(egl_api) } == EGL_FALSE {
+                return Err(egl.error("bind the GL API"));
+            }
+
+            // We draw into our own offscreen target, so the config only
+            // needs to be able to make the right kind of context.
+            let surface_type = if surfaceless { 0 } else { EGL_PBUFFER_BIT };
+            let config_attributes = [
+                EGL_SURFACE_TYPE,
+                surface_type,
+                EGL_RENDERABLE_TYPE,
+                renderable_type,
+                EGL_NONE,
+            ];
+            let mut config = ptr::null_mut();
+            let mut num_configs = 0;
+            let chose = unsafe {
+                (egl.choose_config)(
+                    self.display,
+                    config_attributes.as_ptr(),
+                    &mut config,
+                    1,
+                    &mut num_configs,
+                )
+            };
+            if chose == EGL_FALSE || num_configs == 0 {
+                return Err(egl.error("find a config"));
+            }
+
+            let mut context_attributes = vec![
+                EGL_CONTEXT_MAJOR_VERSION_KHR,
+                EGLint::from(major),
+                EGL_CONTEXT_MINOR_VERSION_KHR,
+                EGLint::from(minor),
+            ];
+            if egl_api == EGL_OPENGL_API {
+                context_attributes.push(EGL_CONTEXT_OPENGL_PROFILE_MASK_KHR);
+                context_attributes.push(EGL_CONTEXT_OPENGL_CORE_PROFILE_BIT_KHR);
+            }
+            context_attributes.push(EGL_NONE);
+            self.context = unsafe {
+                (egl.create_context)(
+                    self.display,
+                    config,
+                    ptr::null_mut(),
+                    context_attributes.as_ptr(),
+                )
+            };
+            if self.context.is_null() {
+                return Err(egl.error("create a GL context"));
+            }
+
+            if !surfaceless {
+                let surface_attributes = [EGL_WIDTH, 1, EGL_HEIGHT, 1, EGL_NONE];
+                self.surface = unsafe {
+                    (egl.create_pbuffer_surface)(self.display, config, surface_attributes.as_ptr())
+                };
+                if self.surface.is_null() {
+                    return Err(egl.error("create a surface"));
+                }
+            }
+            let made_current = unsafe {
+                (egl.make_current)(self.display, self.surface, self.surface, self.context)
+            };
+            if made_current == EGL_FALSE {
+                return Err(egl.error("make the GL context current"));
+            }
+            Ok(())
+        }
+
+        pub fn get_proc_address(&self, name: &str) -> *const c_void {
+            self.egl.proc_address(name)
+        }
+    }
+
+    impl Drop for Context {
+        fn drop(&mut self) {
+            let egl = &self.egl;
+            unsafe {
+                let _ = (egl.make_current)(
+                    self.display,
+                    ptr::null_mut(),
+                    ptr::null_mut(),
+                    ptr::null_mut(),
+                );
+                if !self.surface.is_null() {
+                    let _ = (egl.destroy_surface)(self.display, self.surface);
+                }
+                if !self.context.is_null() {
+                    let _ = (egl.destroy_context)(self.display, self.context);
+                }
+                let _ = (egl.terminate)(self.display);
+            }
+        }
+    }
+}
+
+#[cfg(not(all(unix, not(any(target_os = "macos", target_os = "ios")))))]
+mod imp {
+    use std::os::raw::c_void;
+
+    use glutin::{self, ContextTrait};
+
+    use crate::error::GameResult;
+
+    pub(super) struct Context {
+        context: glutin::Context,
+        // glutin's hidden window belongs to this, so it has to live as
+        // long as the context does.
+        _events_loop: glutin::EventsLoop,
+    }
+
+    impl Context {
+        pub fn new(api: glutin::Api, version: (u8, u8)) -> GameResult<Self> {
+            let events_loop = glutin::EventsLoop::new();
+            let gl_builder = glutin::ContextBuilder::new()
+                .with_gl(glutin::GlRequest::Specific(api, version))
+                .with_gl_profile(glutin::GlProfile::Core);
+            let context = glutin::Context::new(&events_loop, gl_builder, false)?;
+            unsafe {
+                context.make_current()?;
+            }
+            Ok(Context {
+                context,
+                _events_loop: events_loop,
+            })
+        }
+
+        pub fn get_proc_address(&self, name: &str) -> *const c_void {
+            self.context.get_proc_address(name) as *const c_void
+        }
+    }
+}
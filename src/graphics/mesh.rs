@@ -1,7 +1,9 @@
 use crate::context::DebugId;
 use crate::error::GameError;
 use crate::graphics::*;
-use gfx::traits::FactoryExt;
+use gfx::memory::Typed;
+use gfx::pso::buffer::Structure;
+use gfx::traits::{FactoryExt, Pod};
 use lyon;
 use lyon::tessellation as t;
 
@@ -413,6 +415,9 @@ impl t::VertexConstructor<t::StrokeVertex, Vertex> for VertexBuilder {
 pub struct Mesh {
     buffer: gfx::handle::Buffer<gfx_device_gl::Resources, Vertex>,
     slice: gfx::Slice<gfx_device_gl::Resources>,
+    /// Custom vertex attributes for shaders that use them,
+    /// and how many vertices they are for.
+    attributes: Option<(gfx::handle::RawBuffer<gfx_device_gl::Resources>, usize)>,
     blend_mode: Option<BlendMode>,
     image: Image,
     debug_id: DebugId,
@@ -590,6 +595,7 @@ impl Mesh {
         Ok(Mesh {
             buffer: vbuf,
             slice,
+            attributes: None,
             blend_mode: None,
             image: texture.unwrap_or_else(|| ctx.gfx_context.white_image.clone()),
             debug_id: DebugId::get(ctx),
@@ -602,6 +608,9 @@ impl Mesh {
     /// reusing memory instead of allocating and deallocating it, both on the CPU and
     /// GPU side.  There's too much variation in implementations and drivers to promise
     /// it will actually be faster though.  At worst, it will be the same speed.
+    ///
+    /// If the number of vertices changes, this also removes any custom
+    /// attributes set with [`set_vertex_attributes()`](#method.set_vertex_attributes).
     pub fn set_vertices(&mut self, ctx: &mut Context, verts: &[Vertex], indices: &[u32]) {
         // This is in principle faster than throwing away an existing mesh and
         // creating a new one with `Mesh::from_raw()`, but really only because it
@@ -623,6 +632,43 @@ impl Mesh {
             .create_vertex_buffer_with_slice(verts, indices);
         self.buffer = vbuf;
         self.slice = slice;
+        if let Some((_, count)) = self.attributes {
+            if count != verts.len() {
+                self.attributes = None;
+            }
+        }
+    }
+
+    /// Gives each vertex of the mesh custom attributes, for a `Shader`
+    /// set up to read them with [`Shader::set_vertex_attributes()`](type.Shader.html#method.set_vertex_attributes).
+    /// `V` should be the `vertex` struct its `AttributeFormat` was made
+    /// from, and there must be exactly one for each vertex, in the same
+    /// order as the vertices given to [`from_raw()`](#method.from_raw).
+    ///
+    /// Shaders that don't read custom attributes just ignore them.
+    pub fn set_vertex_attributes<V>(&mut self, ctx: &mut Context, attributes: &[V]) -> GameResult
+    where
+        V: Pod + Structure<gfx::format::Format>,
+    {
+        self.debug_id.assert(ctx);
+        let count = self.buffer.len();
+        if attributes.len() != count {
+            let msg = format!(
+                "Tried to give a mesh with {} vertices {} sets of vertex attributes",
+                count,
+                attributes.len()
+            );
+            return Err(GameError::RenderError(msg));
+        }
+        let buffer = ctx.gfx_context.factory.create_vertex_buffer(attributes);
+        self.attributes = Some((buffer.raw().clone(), count));
+        Ok(())
+    }
+
+    /// Removes the custom vertex attributes set with
+    /// [`set_vertex_attributes()`](#method.set_vertex_attributes).
+    pub fn clear_vertex_attributes(&mut self) {
+        self.attributes = None;
     }
 }
 
@@ -641,7 +687,11 @@ impl Drawable for Mesh {
         let typed_thingy = gfx.backend_spec.raw_to_typed_shader_resource(texture);
         gfx.data.tex = (typed_thingy, sampler);
 
-        gfx.draw(Some(&self.slice))?;
+        let attributes = AttributeBuffers {
+            vertices: self.attributes.as_ref().map(|(buffer, _)| buffer),
+            instances: None,
+        };
+        gfx.draw_with_attributes(Some(&self.slice), attributes)?;
 
         Ok(())
     }
@@ -38,9 +38,11 @@ use crate::context::DebugId;
 use crate::GameError;
 use crate::GameResult;
 
+pub(crate) mod camera;
 pub(crate) mod canvas;
 pub(crate) mod context;
 pub(crate) mod drawparam;
+pub(crate) mod headless;
 pub(crate) mod image;
 pub(crate) mod mesh;
 pub(crate) mod shader;
@@ -50,10 +52,16 @@ pub(crate) mod types;
 pub use mint;
 pub(crate) use nalgebra as na;
 
+pub mod animation;
+pub mod atlas;
+pub mod postprocess;
+pub mod snapshot;
 pub mod spritebatch;
 
+pub use crate::graphics::camera::*;
 pub use crate::graphics::canvas::*;
 pub use crate::graphics::drawparam::*;
+pub use crate::graphics::headless::HeadlessContext;
 pub use crate::graphics::image::*;
 pub use crate::graphics::mesh::*;
 pub use crate::graphics::shader::*;
@@ -160,6 +168,23 @@ pub trait BackendSpec: fmt::Debug {
         glutin::CreationError,
     >;
 
+    /// Creates a GL context with no window, which needs no display
+    /// server, and an offscreen color and depth target of the given
+    /// size to use in place of the window's framebuffer.
+    fn init_headless(
+        &self,
+        width: u16,
+        height: u16,
+        color_format: gfx::format::Format,
+        depth_format: gfx::format::Format,
+    ) -> GameResult<(
+        HeadlessContext,
+        Self::Device,
+        Self::Factory,
+        gfx::handle::RawRenderTargetView<Self::Resources>,
+        gfx::handle::RawDepthStencilView<Self::Resources>,
+    )>;
+
     /// Create an Encoder for the backend.
     fn encoder(factory: &mut Self::Factory) -> gfx::Encoder<Self::Resources, Self::CommandBuffer>;
 
@@ -268,6 +293,33 @@ impl BackendSpec for GlBackendSpec {
         )
     }
 
+    fn init_headless(
+        &self,
+        width: u16,
+        height: u16,
+        color_format: gfx::format::Format,
+        depth_format: gfx::format::Format,
+    ) -> GameResult<(
+        HeadlessContext,
+        Self::Device,
+        Self::Factory,
+        gfx::handle::RawRenderTargetView<Self::Resources>,
+        gfx::handle::RawDepthStencilView<Self::Resources>,
+    )> {
+        // Basically what gfx_window_glutin::init_raw() does,
+        // minus the window.
+        let context = HeadlessContext::new(self.api, self.version_tuple())?;
+        let (device, mut factory) = gfx_device_gl::create(|s| context.get_proc_address(s));
+        let (color_view, depth_view) = context::create_offscreen_target::<Self>(
+            &mut factory,
+            width,
+            height,
+            color_format,
+            depth_format,
+        )?;
+        Ok((context, device, factory, color_view, depth_view))
+    }
+
     fn info(&self, device: &Self::Device) -> String {
         let info = device.get_info();
         format!(
@@ -369,6 +421,9 @@ gfx_defines! {
         tex: gfx::TextureSampler<[f32; 4]> = "t_Texture",
         globals: gfx::ConstantBuffer<Globals> = "Globals",
         rect_instance_properties: gfx::InstanceBuffer<InstanceProperties> = (),
+        // The scissor test is always on; with no scissor rect set
+        // it just covers the whole render target.
+        scissor: gfx::Scissor = (),
         // The default values here are overwritten by the
         // pipeline init values in `shader::create_shader()`.
         out: gfx::RawRenderTarget =
@@ -376,6 +431,24 @@ gfx_defines! {
            gfx::format::Format(gfx::format::SurfaceType::R8_G8_B8_A8, gfx::format::ChannelType::Srgb),
            gfx::state::ColorMask::all(), Some(gfx::preset::blend::ALPHA)
           ),
+        // Extra color attachments of a `Canvas`.  When there aren't
+        // any these are just the same target as `out`, and shaders
+        // that don't write to them never touch them anyway.
+        out1: gfx::RawRenderTarget =
+          ("Target1",
+           gfx::format::Format(gfx::format::SurfaceType::R8_G8_B8_A8, gfx::format::ChannelType::Srgb),
+           gfx::state::ColorMask::all(), Some(gfx::preset::blend::ALPHA)
+          ),
+        out2: gfx::RawRenderTarget =
+          ("Target2",
+           gfx::format::Format(gfx::format::SurfaceType::R8_G8_B8_A8, gfx::format::ChannelType::Srgb),
+           gfx::state::ColorMask::all(), Some(gfx::preset::blend::ALPHA)
+          ),
+        out3: gfx::RawRenderTarget =
+          ("Target3",
+           gfx::format::Format(gfx::format::SurfaceType::R8_G8_B8_A8, gfx::format::ChannelType::Srgb),
+           gfx::state::ColorMask::all(), Some(gfx::preset::blend::ALPHA)
+          ),
     }
 }
 
@@ -464,12 +537,21 @@ impl From<gfx::buffer::CreationError> for GameError {
 // DRAWING
 // **********************************************************************
 
-/// Clear the screen to the background color.
+/// Clear the screen to the background color.  When drawing to a
+/// `Canvas` with several color attachments, clears all of them.
 pub fn clear(ctx: &mut Context, color: Color) {
     let gfx = &mut ctx.gfx_context;
     let linear_color: types::LinearColor = color.into();
     let c: [f32; 4] = linear_color.into();
-    gfx.encoder.clear_raw(&gfx.data.out, c.into());
+    let targets = [
+        &gfx.data.out,
+        &gfx.data.out1,
+        &gfx.data.out2,
+        &gfx.data.out3,
+    ];
+    for target in targets.iter().take(gfx.color_attachments) {
+        gfx.encoder.clear_raw(target, c.into());
+    }
 }
 
 /// Draws the given `Drawable` object to the screen by calling its
@@ -488,15 +570,21 @@ where
 /// [`draw()`](../event/trait.EventHandler.html#tymethod.draw) method.
 ///
 /// Unsets any active canvas.
+///
+/// For a headless `Context` there is nothing to show, but this
+/// still flushes all drawing to the offscreen target so that
+/// [`screenshot()`](fn.screenshot.html) can see it.
 pub fn present(ctx: &mut Context) -> GameResult<()> {
     let gfx = &mut ctx.gfx_context;
-    gfx.data.out = gfx.screen_render_target.clone();
+    gfx.set_screen_target();
     // We might want to give the user more control over when the
     // encoder gets flushed eventually, if we want them to be able
     // to do their own gfx drawing.  HOWEVER, the whole pipeline type
     // thing is a bigger hurdle, so this is fine for now.
     gfx.encoder.flush(&mut *gfx.device);
-    gfx.window.swap_buffers()?;
+    if let Some(window) = gfx.window.as_ref() {
+        window.swap_buffers()?;
+    }
     gfx.device.cleanup();
     Ok(())
 }
@@ -808,6 +896,131 @@ pub fn apply_transformations(context: &mut Context) -> GameResult {
     gfx.update_globals()
 }
 
+/// Sets the current scissor rect, replacing the one at the top of the
+/// scissor stack.  Anything drawn afterwards is clipped to it, whether
+/// it is a `Mesh`, `Image`, `SpriteBatch`, `Text` or anything else.
+/// `None` turns clipping off.
+///
+/// The rect is in screen coordinates, as set by
+/// [`set_screen_coordinates()`](fn.set_screen_coordinates.html), and is
+/// mapped onto whatever is being drawn to, the screen or a `Canvas`,
+/// at the time of drawing.  It is not affected by the transform stack
+/// or by a projection set with [`set_projection()`](fn.set_projection.html).
+pub fn set_scissor(context: &mut Context, rect: Option<Rect>) {
+    let gfx = &mut context.gfx_context;
+    gfx.set_scissor(rect);
+}
+
+/// Pushes a scissor rect onto the scissor stack, clipped to the current
+/// scissor rect so that nested UI panels and such can't draw outside
+/// their parents.  See [`set_scissor()`](fn.set_scissor.html).
+pub fn push_scissor(context: &mut Context, rect: Rect) {
+    let gfx = &mut context.gfx_context;
+    let clipped = match gfx.scissor() {
+        Some(current) => current
+            .intersection(rect)
+            .unwrap_or_else(|| Rect::new(rect.x, rect.y, 0.0, 0.0)),
+        None => rect,
+    };
+    gfx.push_scissor(Some(clipped));
+}
+
+/// Pops the scissor rect off the top of the scissor stack, going back
+/// to the one before it.  Will never pop the last one.
+pub fn pop_scissor(context: &mut Context) {
+    let gfx = &mut context.gfx_context;
+    gfx.pop_scissor();
+}
+
+/// Gets the current scissor rect, or `None` if clipping is off.
+pub fn scissor(context: &Context) -> Option<Rect> {
+    context.gfx_context.scissor()
+}
+
+/// Sets what the stencil test does with everything drawn from now on.
+/// This is how you draw masks, and then draw other things only inside or
+/// outside of them:
+///
+/// ```rust,no_run
+/// # use ggez::*;
+/// # use ggez::graphics::*;
+/// # fn t(ctx: &mut Context, mask: &Mesh, image: &Image) -> GameResult {
+/// graphics::clear_stencil(ctx)?;
+/// graphics::set_stencil_mode(ctx, StencilMode::Write(1));
+/// graphics::draw(ctx, mask, DrawParam::new())?;
+/// graphics::set_stencil_mode(ctx, StencilMode::Inside(1));
+/// graphics::draw(ctx, image, DrawParam::new())?;
+/// graphics::set_stencil_mode(ctx, StencilMode::Off);
+/// # Ok(())
+/// # }
+/// ```
+///
+/// The screen always has a stencil buffer, but a `Canvas` only has one
+/// if it was created with [`CanvasBuilder::stencil()`](struct.CanvasBuilder.html#method.stencil);
+/// drawing to a canvas without one using any mode besides
+/// `StencilMode::Off` returns an error.
+pub fn set_stencil_mode(context: &mut Context, mode: StencilMode) {
+    context.gfx_context.stencil_mode = mode;
+}
+
+/// Gets the current stencil mode.
+pub fn stencil_mode(context: &Context) -> StencilMode {
+    context.gfx_context.stencil_mode
+}
+
+/// Clears the stencil buffer of whatever is being drawn to, the screen
+/// or a `Canvas`, to zero, removing all masks.  Returns an error if
+/// it is a `Canvas` with no stencil buffer.
+pub fn clear_stencil(context: &mut Context) -> GameResult {
+    let gfx = &mut context.gfx_context;
+    match gfx.current_depth_stencil {
+        Some(ref view) => {
+            let view: gfx::handle::DepthStencilView<_, gfx::format::DepthStencil> =
+                gfx::memory::Typed::new(view.clone());
+            gfx.encoder.clear_stencil(&view, 0);
+            Ok(())
+        }
+        None => Err(GameError::RenderError(
+            "Tried to clear the stencil buffer, but the canvas has no stencil buffer".into(),
+        )),
+    }
+}
+
+/// Sets whether things drawn from now on are depth tested against
+/// what was already drawn, and whether they update the depth buffer.
+/// See [`DepthMode`](enum.DepthMode.html) for how to give things depth.
+///
+/// The screen always has a depth buffer, but a `Canvas` only has one
+/// if it was created with [`CanvasBuilder::depth()`](struct.CanvasBuilder.html#method.depth);
+/// drawing to a canvas without one using any mode besides
+/// `DepthMode::Off` returns an error.
+pub fn set_depth_mode(context: &mut Context, mode: DepthMode) {
+    context.gfx_context.depth_mode = mode;
+}
+
+/// Gets the current depth mode.
+pub fn depth_mode(context: &Context) -> DepthMode {
+    context.gfx_context.depth_mode
+}
+
+/// Clears the depth buffer of whatever is being drawn to, the screen
+/// or a `Canvas`, so that everything is as far away as it can be.
+/// Returns an error if it is a `Canvas` with no depth buffer.
+pub fn clear_depth(context: &mut Context) -> GameResult {
+    let gfx = &mut context.gfx_context;
+    match gfx.current_depth_stencil {
+        Some(ref view) => {
+            let view: gfx::handle::DepthStencilView<_, gfx::format::DepthStencil> =
+                gfx::memory::Typed::new(view.clone());
+            gfx.encoder.clear_depth(&view, 1.0);
+            Ok(())
+        }
+        None => Err(GameError::RenderError(
+            "Tried to clear the depth buffer, but the canvas has no depth buffer".into(),
+        )),
+    }
+}
+
 /// Sets the blend mode of the currently active shader program
 pub fn set_blend_mode(ctx: &mut Context, mode: BlendMode) -> GameResult {
     ctx.gfx_context.set_blend_mode(mode)
@@ -852,7 +1065,7 @@ pub fn set_resizable(context: &mut Context, resizable: bool) -> GameResult {
     set_mode(context, window_mode)
 }
 
-/// Sets the window icon.
+/// Sets the window icon.  Does nothing if the `Context` is headless.
 pub fn set_window_icon<P: AsRef<Path>>(context: &mut Context, path: Option<P>) -> GameResult<()> {
     let icon = match path {
         Some(p) => {
@@ -861,32 +1074,46 @@ pub fn set_window_icon<P: AsRef<Path>>(context: &mut Context, path: Option<P>) -
         }
         None => None,
     };
-    context.gfx_context.window.set_window_icon(icon);
+    if let Some(window) = context.gfx_context.window.as_ref() {
+        window.set_window_icon(icon);
+    }
     Ok(())
 }
 
-/// Sets the window title.
+/// Sets the window title.  Does nothing if the `Context` is headless.
 pub fn set_window_title(context: &Context, title: &str) {
-    context.gfx_context.window.set_title(title);
+    if let Some(window) = context.gfx_context.window.as_ref() {
+        window.set_title(title);
+    }
 }
 
 /// Returns a reference to the Glutin window.
 /// Ideally you should not need to use this because ggez
 /// would provide all the functions you need without having
 /// to dip into Glutin itself.  But life isn't always ideal.
+///
+/// Panics if the `Context` is headless, since then there is no window.
 pub fn window(context: &Context) -> &glutin::Window {
     let gfx = &context.gfx_context;
-    &gfx.window
+    gfx.window
+        .as_ref()
+        .expect("Tried to get the window of a headless Context")
 }
 
 /// Returns the size of the window in pixels as (width, height),
 /// including borders, titlebar, etc.
 /// Returns zeros if the window doesn't exist.
+///
+/// If the `Context` is headless, returns the size of the offscreen target.
 pub fn size(context: &Context) -> (f32, f32) {
     let gfx = &context.gfx_context;
-    let dpi = gfx.window.get_hidpi_factor();
+    if let Some(size) = gfx.offscreen_size() {
+        return size;
+    }
+    let window = window(context);
+    let dpi = window.get_hidpi_factor();
 
-    gfx.window
+    window
         .get_outer_size()
         .map(|logical_size| logical_size.to_physical(dpi))
         .map(|physical_size| (physical_size.width as f32, physical_size.height as f32))
@@ -895,10 +1122,16 @@ pub fn size(context: &Context) -> (f32, f32) {
 
 /// Returns the size of the window's underlying drawable in pixels as (width, height).
 /// Returns zeros if window doesn't exist.
+///
+/// If the `Context` is headless, returns the size of the offscreen target.
 pub fn drawable_size(context: &Context) -> (f32, f32) {
     let gfx = &context.gfx_context;
-    let dpi = gfx.window.get_hidpi_factor();
-    gfx.window
+    if let Some(size) = gfx.offscreen_size() {
+        return size;
+    }
+    let window = window(context);
+    let dpi = window.get_hidpi_factor();
+    window
         .get_inner_size()
         .map(|logical_size| logical_size.to_physical(dpi))
         .map(|physical_size| (physical_size.width as f32, physical_size.height as f32))
@@ -0,0 +1,364 @@
+//! Post-processing runs a chain of shaders over a whole frame, for
+//! effects like bloom, CRT screens and color grading.
+//!
+//! A [`PostProcess`](struct.PostProcess.html) owns the canvases the
+//! frame gets drawn to and passed between shaders, all sized to the
+//! window.  Draw your frame between [`begin()`](struct.PostProcess.html#method.begin)
+//! and [`finish()`](struct.PostProcess.html#method.finish), and `finish()`
+//! runs every pass in order, drawing the last one to the screen:
+//!
+//! ```rust,no_run
+//! # use ggez::*;
+//! # use ggez::graphics::postprocess::PostProcess;
+//! # use gfx::{self, *};
+//! # gfx_defines! { constant Dim { rate: f32 = "u_Rate", } }
+//! # fn t(ctx: &mut Context, dim: graphics::Shader<Dim>) -> GameResult {
+//! let mut post = PostProcess::new(ctx)?;
+//! let _ = post.add_pass(dim, Dim { rate: 0.5 });
+//!
+//! // Then each frame:
+//! post.begin(ctx);
+//! graphics::clear(ctx, graphics::BLACK);
+//! // ...draw things...
+//! post.finish(ctx)?;
+//! graphics::present(ctx)?;
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! Each pass draws its input with its shader, which samples it
+//! as `t_Texture`.  By default the input is the result of the pass
+//! before, but a pass can also read the original frame or the result
+//! of any earlier pass; see [`PassInput`](enum.PassInput.html).
+//! To sample more than one of those at once, bind the others to the
+//! shader with [`Shader::set_image()`](../type.Shader.html#method.set_image).
+//!
+//! Call [`PostProcess::resize()`](struct.PostProcess.html#method.resize)
+//! from your [`EventHandler::resize_event()`](../../event/trait.EventHandler.html#method.resize_event)
+//! to keep the canvases the same size as the window.
+
+use std::any::Any;
+use std::fmt;
+
+use gfx::pso::buffer::Structure;
+use gfx::shade::ConstFormat;
+use gfx::traits::Pod;
+
+use crate::conf;
+use crate::context::Context;
+use crate::error::{GameError, GameResult};
+use crate::graphics::{self, Canvas, DrawParam, Image, Rect, Shader, ShaderLock};
+
+/// Identifies a pass of a [`PostProcess`](struct.PostProcess.html),
+/// in the order the passes were added, starting at 0.
+pub type PassId = usize;
+
+/// What a post-processing pass draws with its shader.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, SmartDefault)]
+pub enum PassInput {
+    /// The result of the pass before this one, or the original
+    /// frame for the first pass.
+    #[default]
+    Previous,
+    /// The original frame, as drawn between `begin()` and `finish()`.
+    Scene,
+    /// The result of an earlier pass.
+    Pass(PassId),
+}
+
+/// A pass's shader and its constants, with the type of
+/// the constants erased so passes can go in one list.
+trait PassShader: fmt::Debug {
+    /// Sends the pass's constants and makes its shader current.
+    fn use_shader(&self, ctx: &mut Context) -> GameResult<ShaderLock>;
+    fn as_any_mut(&mut self) -> &mut dyn Any;
+}
+
+struct ShaderPass<C>
+where
+    C: 'static + Pod + Structure<ConstFormat> + Clone + Copy,
+{
+    shader: Shader<C>,
+    consts: C,
+}
+
+impl<C> fmt::Debug for ShaderPass<C>
+where
+    C: 'static + Pod + Structure<ConstFormat> + Clone + Copy,
+{
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        write!(formatter, "<ShaderPass: {:?}>", self.shader)
+    }
+}
+
+impl<C> PassShader for ShaderPass<C>
+where
+    C: 'static + Pod + Structure<ConstFormat> + Clone + Copy,
+{
+    fn use_shader(&self, ctx: &mut Context) -> GameResult<ShaderLock> {
+        self.shader.send(ctx, self.consts)?;
+        Ok(graphics::use_shader(ctx, &self.shader))
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
+
+#[derive(Debug)]
+struct Pass {
+    shader: Box<dyn PassShader>,
+    input: PassInput,
+    /// The pass's own canvas, if its result is kept around for
+    /// later passes to read.  Otherwise it draws to one of the
+    /// ping-pong canvases.
+    result: Option<Canvas>,
+}
+
+/// Where a pass reads from or draws to.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum Target {
+    Scene,
+    PingPong(usize),
+    Pass(PassId),
+}
+
+/// A chain of shaders run one after the other over a whole frame.
+/// See the [module docs](index.html) for how to use it.
+#[derive(Debug)]
+pub struct PostProcess {
+    scene: Canvas,
+    ping_pong: [Canvas; 2],
+    passes: Vec<Pass>,
+    width: u16,
+    height: u16,
+}
+
+impl PostProcess {
+    /// Creates a `PostProcess` with no passes, with canvases
+    /// the size of the window.
+    pub fn new(ctx: &mut Context) -> GameResult<Self> {
+        let (width, height) = window_size(ctx);
+        Ok(Self {
+            scene: new_canvas(ctx, width, height)?,
+            ping_pong: [
+                new_canvas(ctx, width, height)?,
+                new_canvas(ctx, width, height)?,
+            ],
+            passes: vec![],
+            width,
+            height,
+        })
+    }
+
+    /// Adds a pass to the end of the chain, drawing its input with
+    /// the given shader and constants.  Returns the new pass's id.
+    pub fn add_pass<C>(&mut self, shader: Shader<C>, consts: C) -> PassId
+    where
+        C: 'static + Pod + Structure<ConstFormat> + Clone + Copy,
+    {
+        self.passes.push(Pass {
+            shader: Box::new(ShaderPass { shader, consts }),
+            input: PassInput::default(),
+            result: None,
+        });
+        self.passes.len() - 1
+    }
+
+    /// Returns how many passes there are.
+    pub fn pass_count(&self) -> usize {
+        self.passes.len()
+    }
+
+    /// Replaces the constants a pass sends to its shader.  Returns
+    /// an error if there's no such pass, or its shader takes a
+    /// different type of constants.
+    pub fn set_consts<C>(&mut self, pass: PassId, consts: C) -> GameResult
+    where
+        C: 'static + Pod + Structure<ConstFormat> + Clone + Copy,
+    {
+        let shader_pass = self
+            .pass_mut(pass)?
+            .shader
+            .as_any_mut()
+            .downcast_mut::<ShaderPass<C>>()
+            .ok_or_else(|| {
+                GameError::RenderError(format!(
+                    "Post-processing pass {} takes a different type of constants",
+                    pass
+                ))
+            })?;
+        shader_pass.consts = consts;
+        Ok(())
+    }
+
+    /// Sets what a pass draws with its shader.  Reading the result
+    /// of an earlier pass keeps that result around, as with
+    /// [`keep_result()`](#method.keep_result).  Returns an error if
+    /// there's no such pass, or it would read a pass that isn't
+    /// before it.
+    pub fn set_input(&mut self, ctx: &mut Context, pass: PassId, input: PassInput) -> GameResult {
+        let _ = self.pass_mut(pass)?;
+        if let PassInput::Pass(earlier) = input {
+            if earlier >= pass {
+                let msg = format!(
+                    "Post-processing pass {} can only read passes before it, not {}",
+                    pass, earlier
+                );
+                return Err(GameError::RenderError(msg));
+            }
+            self.keep_result(ctx, earlier)?;
+        }
+        self.passes[pass].input = input;
+        Ok(())
+    }
+
+    /// Keeps the result of a pass around for the rest of the frame
+    /// instead of letting later passes draw over it, so it can be
+    /// read with [`result()`](#method.result).  This costs a canvas
+    /// the size of the window.
+    pub fn keep_result(&mut self, ctx: &mut Context, pass: PassId) -> GameResult {
+        let (width, height) = (self.width, self.height);
+        let pass = self.pass_mut(pass)?;
+        if pass.result.is_none() {
+            pass.result = Some(new_canvas(ctx, width, height)?);
+        }
+        Ok(())
+    }
+
+    /// Gets the result of a pass from the last time the chain was
+    /// run, if it was kept with [`keep_result()`](#method.keep_result)
+    /// or [`set_input()`](#method.set_input).  Bind it to a later pass's
+    /// shader with [`Shader::set_image()`](../type.Shader.html#method.set_image)
+    /// to sample it alongside that pass's input, and bind it again after
+    /// [`resize()`](#method.resize), which replaces it.
+    pub fn result(&self, pass: PassId) -> Option<&Image> {
+        self.passes
+            .get(pass)
+            .and_then(|pass| pass.result.as_ref())
+            .map(Canvas::image)
+    }
+
+    /// Gets the original frame from the last time the chain was run.
+    pub fn scene(&self) -> &Image {
+        self.scene.image()
+    }
+
+    /// Recreates the canvases if the window has changed size since
+    /// they were made.  Call this from your
+    /// [`EventHandler::resize_event()`](../../event/trait.EventHandler.html#method.resize_event).
+    pub fn resize(&mut self, ctx: &mut Context) -> GameResult {
+        let (width, height) = window_size(ctx);
+        if (width, height) == (self.width, self.height) {
+            return Ok(());
+        }
+        self.scene = new_canvas(ctx, width, height)?;
+        self.ping_pong = [
+            new_canvas(ctx, width, height)?,
+            new_canvas(ctx, width, height)?,
+        ];
+        for pass in &mut self.passes {
+            if pass.result.is_some() {
+                pass.result = Some(new_canvas(ctx, width, height)?);
+            }
+        }
+        self.width = width;
+        self.height = height;
+        Ok(())
+    }
+
+    /// Starts drawing the frame, by setting the canvas the
+    /// passes will read from.
+    pub fn begin(&self, ctx: &mut Context) {
+        graphics::set_canvas(ctx, Some(&self.scene));
+    }
+
+    /// Runs every pass over the frame drawn since
+    /// [`begin()`](#method.begin), drawing the last one to the screen.
+    /// With no passes, this just draws the frame to the screen.
+    ///
+    /// The screen coordinates, transform and scissor rect are reset
+    /// while the passes run, and put back afterwards.
+    pub fn finish(&self, ctx: &mut Context) -> GameResult {
+        let screen_rect = graphics::screen_coordinates(ctx);
+        let scissor = graphics::scissor(ctx);
+        let rect = Rect::new(0.0, 0.0, f32::from(self.width), f32::from(self.height));
+        graphics::set_screen_coordinates(ctx, rect)?;
+        graphics::set_scissor(ctx, None);
+        graphics::push_transform(ctx, None);
+        graphics::origin(ctx);
+        graphics::apply_transformations(ctx)?;
+
+        let result = self.run_passes(ctx);
+
+        graphics::pop_transform(ctx);
+        graphics::set_scissor(ctx, scissor);
+        graphics::set_screen_coordinates(ctx, screen_rect)?;
+        graphics::set_canvas(ctx, None);
+        result
+    }
+
+    fn run_passes(&self, ctx: &mut Context) -> GameResult {
+        let mut previous = Target::Scene;
+        for (id, pass) in self.passes.iter().enumerate() {
+            let input = match pass.input {
+                PassInput::Previous => previous,
+                PassInput::Scene => Target::Scene,
+                PassInput::Pass(earlier) => Target::Pass(earlier),
+            };
+            let output = if pass.result.is_some() {
+                Target::Pass(id)
+            } else if input == Target::PingPong(0) {
+                Target::PingPong(1)
+            } else {
+                Target::PingPong(0)
+            };
+            let last = id + 1 == self.passes.len();
+            if last && output != Target::Pass(id) {
+                graphics::set_canvas(ctx, None);
+            } else {
+                graphics::set_canvas(ctx, Some(self.canvas(output)));
+            }
+            graphics::clear(ctx, graphics::Color::new(0.0, 0.0, 0.0, 0.0));
+            let _lock = pass.shader.use_shader(ctx)?;
+            graphics::draw(ctx, self.canvas(input), DrawParam::new())?;
+            previous = output;
+        }
+        // Unless the last pass drew straight to the screen
+        // already, copy whatever it left to the screen.
+        let last = self.passes.last();
+        if last.map_or(true, |pass| pass.result.is_some()) {
+            graphics::set_canvas(ctx, None);
+            graphics::draw(ctx, self.canvas(previous), DrawParam::new())?;
+        }
+        Ok(())
+    }
+
+    fn canvas(&self, target: Target) -> &Canvas {
+        match target {
+            Target::Scene => &self.scene,
+            Target::PingPong(i) => &self.ping_pong[i],
+            Target::Pass(id) => self.passes[id]
+                .result
+                .as_ref()
+                .expect("Read a post-processing pass that wasn't kept, should never happen"),
+        }
+    }
+
+    fn pass_mut(&mut self, pass: PassId) -> GameResult<&mut Pass> {
+        self.passes.get_mut(pass).ok_or_else(|| {
+            GameError::RenderError(format!("No post-processing pass with id {}", pass))
+        })
+    }
+}
+
+/// The size of the window in pixels, which is what
+/// the canvases should be.
+fn window_size(ctx: &Context) -> (u16, u16) {
+    let (width, height) = graphics::drawable_size(ctx);
+    (width as u16, height as u16)
+}
+
+fn new_canvas(ctx: &mut Context, width: u16, height: u16) -> GameResult<Canvas> {
+    Canvas::new(ctx, width, height, conf::NumSamples::One)
+}
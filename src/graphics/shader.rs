@@ -6,11 +6,14 @@
 #![allow(unsafe_code)]
 use gfx::format;
 use gfx::handle::*;
+use gfx::memory::Typed;
 use gfx::preset::blend;
 use gfx::pso::buffer::*;
+use gfx::pso::target::DepthStencilTarget;
 use gfx::pso::*;
 use gfx::shade::*;
 use gfx::state::*;
+use gfx::state::{Comparison, Depth, Stencil, StencilOp};
 use gfx::traits::{FactoryExt, Pod};
 use gfx::*;
 use std::cell::RefCell;
@@ -18,11 +21,14 @@ use std::collections::HashMap;
 use std::fmt;
 use std::io::prelude::*;
 use std::marker::PhantomData;
-use std::path::Path;
+use std::mem;
+use std::path::{Path, PathBuf};
 use std::rc::Rc;
+use std::time::SystemTime;
 
 use crate::context::DebugId;
 use crate::error::*;
+use crate::filesystem::Filesystem;
 use crate::graphics;
 use crate::Context;
 
@@ -117,6 +123,207 @@ impl From<BlendMode> for Blend {
     }
 }
 
+/// What the stencil test does with whatever gets drawn, as set with
+/// [`graphics::set_stencil_mode()`](fn.set_stencil_mode.html).  This is
+/// how masking works: draw some shapes with `Write` to make a mask, then
+/// draw other things with `Inside` or `Outside` to only draw them where
+/// the mask is or isn't.
+///
+/// The number in each mode is the value written to or compared with
+/// the stencil buffer, so several masks can live in it at once.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, SmartDefault)]
+pub enum StencilMode {
+    /// No stencil test; everything gets drawn as usual.
+    #[default]
+    Off,
+    /// Draw to the stencil buffer only, setting it to the given value
+    /// everywhere a shape covers, including its transparent pixels.
+    /// Nothing gets drawn to the color buffer.
+    Write(u8),
+    /// Only draw where the stencil buffer has the given value.
+    Inside(u8),
+    /// Only draw where the stencil buffer doesn't have the given value.
+    Outside(u8),
+}
+
+impl StencilMode {
+    /// The value the stencil test compares against, if any.
+    fn reference(self) -> Option<u8> {
+        match self {
+            StencilMode::Off => None,
+            StencilMode::Write(r) | StencilMode::Inside(r) | StencilMode::Outside(r) => Some(r),
+        }
+    }
+
+    /// The mode with its reference value left out, since the value is
+    /// set at draw time but the rest gets baked into a PSO.
+    fn pso_key(self) -> StencilMode {
+        match self {
+            StencilMode::Off => StencilMode::Off,
+            StencilMode::Write(_) => StencilMode::Write(0),
+            StencilMode::Inside(_) => StencilMode::Inside(0),
+            StencilMode::Outside(_) => StencilMode::Outside(0),
+        }
+    }
+
+    /// The stencil state to bake into a PSO for this mode.
+    fn state(self) -> Option<Stencil> {
+        let keep = (StencilOp::Keep, StencilOp::Keep, StencilOp::Keep);
+        match self {
+            StencilMode::Off => None,
+            StencilMode::Write(_) => Some(Stencil::new(
+                Comparison::Always,
+                0xFF,
+                (StencilOp::Keep, StencilOp::Keep, StencilOp::Replace),
+            )),
+            StencilMode::Inside(_) => Some(Stencil::new(Comparison::Equal, 0xFF, keep)),
+            StencilMode::Outside(_) => Some(Stencil::new(Comparison::NotEqual, 0xFF, keep)),
+        }
+    }
+
+    /// Which color channels get written in this mode.
+    fn color_mask(self) -> ColorMask {
+        match self {
+            StencilMode::Write(_) => ColorMask::empty(),
+            _ => ColorMask::all(),
+        }
+    }
+}
+
+/// Whether drawing uses the depth buffer, as set with
+/// [`graphics::set_depth_mode()`](fn.set_depth_mode.html).
+///
+/// Everything gets drawn at a Z coordinate of 0 unless you move it,
+/// so to layer things with the depth test give them a Z coordinate
+/// with a transform, such as one pushed with
+/// [`graphics::push_transform()`](fn.push_transform.html), or in a
+/// custom vertex shader.  With the default projection Z goes from
+/// -1 to 1, and things with a lower Z end up in front.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, SmartDefault)]
+pub enum DepthMode {
+    /// No depth test; things drawn later end up in front.
+    #[default]
+    Off,
+    /// Only draw things in front of or level with what has already
+    /// been drawn, without updating the depth buffer.  Handy for
+    /// transparent things.
+    Test,
+    /// Only draw things in front of or level with what has already
+    /// been drawn, and update the depth buffer.
+    TestAndWrite,
+}
+
+impl DepthMode {
+    /// The depth state to bake into a PSO for this mode.
+    fn state(self) -> Depth {
+        match self {
+            DepthMode::Off => Depth {
+                fun: Comparison::Always,
+                write: false,
+            },
+            DepthMode::Test => Depth {
+                fun: Comparison::LessEqual,
+                write: false,
+            },
+            DepthMode::TestAndWrite => Depth {
+                fun: Comparison::LessEqual,
+                write: true,
+            },
+        }
+    }
+}
+
+/// The depth and stencil state to bake into a PSO, or `None` if
+/// it doesn't use the depth and stencil buffer at all.
+fn depth_stencil_state(stencil: StencilMode, depth: DepthMode) -> Option<(Depth, Stencil)> {
+    if stencil == StencilMode::Off && depth == DepthMode::Off {
+        return None;
+    }
+    let keep = (StencilOp::Keep, StencilOp::Keep, StencilOp::Keep);
+    let stencil = stencil
+        .state()
+        .unwrap_or_else(|| Stencil::new(Comparison::Always, 0, keep));
+    Some((depth.state(), stencil))
+}
+
+/// The state of the render target a [`ShaderHandle`](trait.ShaderHandle.html)
+/// draws to, apart from what is in the pipeline data.
+///
+/// As an end-user you shouldn't ever have to touch this.
+#[derive(Debug, Clone, Copy)]
+pub struct TargetState<'a, R: Resources> {
+    /// The current stencil mode.
+    pub stencil: StencilMode,
+    /// The current depth mode.
+    pub depth: DepthMode,
+    /// The target's depth and stencil buffer, if it has one.
+    pub depth_stencil: Option<&'a RawDepthStencilView<R>>,
+    /// How many color attachments the target has.
+    pub color_attachments: usize,
+}
+
+/// The layout of a buffer of custom vertex or instance attributes,
+/// for shaders that need more than ggez's own.  Describe the attributes
+/// with a `vertex` struct declared with `gfx_defines!`, just like a
+/// shader's constants:
+///
+/// ```rust,ignore
+/// gfx_defines! {
+///     vertex Lighting {
+///         normal: [f32; 3] = "a_Normal",
+///         wind: f32 = "a_Wind",
+///     }
+/// }
+///
+/// shader.set_vertex_attributes(ctx, Some(AttributeFormat::of::<Lighting>()))?;
+/// ```
+///
+/// See [`Shader::set_vertex_attributes()`](type.Shader.html#method.set_vertex_attributes)
+/// and [`Shader::set_instance_attributes()`](type.Shader.html#method.set_instance_attributes).
+#[derive(Clone, Copy)]
+pub struct AttributeFormat {
+    query: fn(&str) -> Option<Element<format::Format>>,
+    stride: ElemStride,
+}
+
+impl AttributeFormat {
+    /// The layout of the given vertex struct.
+    pub fn of<V>() -> Self
+    where
+        V: Pod + Structure<format::Format>,
+    {
+        Self {
+            query: V::query,
+            stride: mem::size_of::<V>() as ElemStride,
+        }
+    }
+}
+
+impl fmt::Debug for AttributeFormat {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        write!(formatter, "<AttributeFormat: {} bytes>", self.stride)
+    }
+}
+
+/// Buffers of custom vertex and instance attributes to draw with,
+/// for shaders that use them.
+///
+/// As an end-user you shouldn't ever have to touch this.
+#[derive(Debug, Clone, Copy)]
+pub struct AttributeBuffers<'a, R: Resources> {
+    /// Custom attributes for each vertex drawn, if any.
+    pub vertices: Option<&'a RawBuffer<R>>,
+    /// Custom attributes for each instance drawn, if any.
+    pub instances: Option<&'a RawBuffer<R>>,
+}
+
+/// The blend, stencil and depth modes baked into a PSO.
+type PsoKey = (BlendMode, StencilMode, DepthMode);
+
+fn pso_key(mode: BlendMode, stencil: StencilMode, depth: DepthMode) -> PsoKey {
+    (mode, stencil.pso_key(), depth)
+}
+
 /// A struct to easily store a set of pipeline state objects that are
 /// associated with a specific shader program.
 ///
@@ -129,12 +336,17 @@ impl From<BlendMode> for Blend {
 /// The `PsoSet` struct is basically just a hash map for easily
 /// storing each shader set's PSOs and then retrieving them based
 /// on a [`BlendMode`](enum.BlendMode.html).
+///
+/// The stencil and depth tests are baked into PSOs too, so each blend
+/// mode can also have a PSO for each [`StencilMode`](enum.StencilMode.html)
+/// and [`DepthMode`](enum.DepthMode.html).  Those get created the first
+/// time they're drawn with.
 struct PsoSet<Spec, C>
 where
     Spec: graphics::BackendSpec,
     C: Structure<ConstFormat>,
 {
-    psos: HashMap<BlendMode, PipelineState<Spec::Resources, ConstMeta<C>>>,
+    psos: HashMap<PsoKey, PipelineState<Spec::Resources, ConstMeta<C>>>,
 }
 
 impl<Spec, C> PsoSet<Spec, C>
@@ -148,19 +360,16 @@ where
         }
     }
 
-    pub fn insert_mode(
-        &mut self,
-        mode: BlendMode,
-        pso: PipelineState<Spec::Resources, ConstMeta<C>>,
-    ) {
-        let _ = self.psos.insert(mode, pso);
+    pub fn insert_mode(&mut self, key: PsoKey, pso: PipelineState<Spec::Resources, ConstMeta<C>>) {
+        let _ = self.psos.insert(key, pso);
     }
 
-    pub fn mode(
-        &self,
-        mode: BlendMode,
-    ) -> GameResult<&PipelineState<Spec::Resources, ConstMeta<C>>> {
-        match self.psos.get(&mode) {
+    pub fn has_mode(&self, key: PsoKey) -> bool {
+        self.psos.contains_key(&key)
+    }
+
+    pub fn mode(&self, key: PsoKey) -> GameResult<&PipelineState<Spec::Resources, ConstMeta<C>>> {
+        match self.psos.get(&key) {
             Some(pso) => Ok(pso),
             None => Err(GameError::RenderError(
                 "Could not find a pipeline for the specified shader and BlendMode".into(),
@@ -186,6 +395,11 @@ pub struct ShaderGeneric<Spec: graphics::BackendSpec, C: Structure<ConstFormat>>
 
 /// A `Shader` represents a handle to a user-defined shader that can be used
 /// with a ggez graphics context
+///
+/// A pixel shader writes to the screen or canvas through an output named
+/// `Target0`.  When drawing to a [`Canvas`](type.Canvas.html) with several
+/// color attachments it can also write to `Target1`, `Target2` and so on,
+/// one for each attachment.
 pub type Shader<C> = ShaderGeneric<graphics::GlBackendSpec, C>;
 
 pub(crate) fn create_shader<C, S, Spec>(
@@ -214,42 +428,27 @@ where
 
     let mut psos = PsoSet::new(blend_modes.len());
     let name: String = name.into();
+    let set = factory.create_shader_set(vertex_source, pixel_source)?;
+    let settings = PsoSettings {
+        name,
+        color_format,
+        multisample_samples,
+        vertex_attributes: None,
+        instance_attributes: None,
+    };
     for mode in blend_modes {
-        let init = ConstInit::<C>(
-            graphics::pipe::Init {
-                out: (
-                    "Target0",
-                    color_format,
-                    ColorMask::all(),
-                    Some((*mode).into()),
-                ),
-                ..graphics::pipe::new()
-            },
-            name.clone(),
-            PhantomData,
-        );
-        let set = factory.create_shader_set(vertex_source, pixel_source)?;
-        let sample = if multisample_samples > 1 {
-            Some(MultiSample)
-        } else {
-            None
-        };
-        let rasterizer = Rasterizer {
-            front_face: FrontFace::CounterClockwise,
-            cull_face: CullFace::Nothing,
-            method: RasterMethod::Fill,
-            offset: None,
-            samples: sample,
-        };
-
-        let pso = factory.create_pipeline_state(&set, Primitive::TriangleList, rasterizer, init)?;
-        psos.insert_mode(*mode, pso);
+        let key = pso_key(*mode, StencilMode::Off, DepthMode::Off);
+        let pso = create_pso::<Spec, C>(factory, &set, &settings, key)?;
+        psos.insert_mode(key, pso);
     }
 
     let program = ShaderProgram {
         buffer: buffer.clone(),
         psos,
         active_blend_mode: blend_modes[0],
+        set,
+        settings,
+        images: HashMap::new(),
     };
     let draw: Box<dyn ShaderHandle<Spec>> = Box::new(program);
 
@@ -263,6 +462,64 @@ where
     Ok((shader, draw))
 }
 
+/// Everything besides the blend, stencil and depth modes
+/// that goes into creating a PSO for a shader program.
+#[derive(Debug, Clone)]
+struct PsoSettings {
+    name: String,
+    color_format: format::Format,
+    multisample_samples: u8,
+    vertex_attributes: Option<AttributeFormat>,
+    instance_attributes: Option<AttributeFormat>,
+}
+
+/// Creates a PSO for the given shader program with the given
+/// blend, stencil and depth modes baked in.
+fn create_pso<Spec, C>(
+    factory: &mut Spec::Factory,
+    set: &ShaderSet<Spec::Resources>,
+    settings: &PsoSettings,
+    key: PsoKey,
+) -> GameResult<PipelineState<Spec::Resources, ConstMeta<C>>>
+where
+    C: Structure<ConstFormat>,
+    Spec: graphics::BackendSpec,
+{
+    let (mode, stencil, depth) = key;
+    let blend: Blend = mode.into();
+    let mask = stencil.color_mask();
+    let target = |name| (name, settings.color_format, mask, Some(blend));
+    let init = ConstInit::<C> {
+        pipe: graphics::pipe::Init {
+            out: target("Target0"),
+            out1: target("Target1"),
+            out2: target("Target2"),
+            out3: target("Target3"),
+            ..graphics::pipe::new()
+        },
+        consts_name: settings.name.clone(),
+        depth_stencil: depth_stencil_state(stencil, depth),
+        vertex_attributes: settings.vertex_attributes,
+        instance_attributes: settings.instance_attributes,
+        consts: PhantomData,
+    };
+    let sample = if settings.multisample_samples > 1 {
+        Some(MultiSample)
+    } else {
+        None
+    };
+    let rasterizer = Rasterizer {
+        front_face: FrontFace::CounterClockwise,
+        cull_face: CullFace::Nothing,
+        method: RasterMethod::Fill,
+        offset: None,
+        samples: sample,
+    };
+
+    let pso = factory.create_pipeline_state(set, Primitive::TriangleList, rasterizer, init)?;
+    Ok(pso)
+}
+
 impl<Spec, C> ShaderGeneric<Spec, C>
 where
     Spec: graphics::BackendSpec,
@@ -284,24 +541,32 @@ where
     ) -> GameResult<Shader<C>> {
         let vertex_source = {
             let mut buf = Vec::new();
-            let mut reader = ctx.filesystem.open(vertex_path)?;
+            let mut reader = ctx.filesystem.open(&vertex_path)?;
             let _ = reader.read_to_end(&mut buf)?;
             buf
         };
         let pixel_source = {
             let mut buf = Vec::new();
-            let mut reader = ctx.filesystem.open(pixel_path)?;
+            let mut reader = ctx.filesystem.open(&pixel_path)?;
             let _ = reader.read_to_end(&mut buf)?;
             buf
         };
-        Shader::from_u8(
+        let shader = Shader::from_u8(
             ctx,
             &vertex_source,
             &pixel_source,
             consts,
             name,
             blend_modes,
-        )
+        )?;
+        let sources = ShaderSources::new(
+            &ctx.filesystem,
+            shader.id,
+            vertex_path.as_ref(),
+            pixel_path.as_ref(),
+        );
+        ctx.gfx_context.shader_watcher.sources.push(sources);
+        Ok(shader)
     }
 
     /// Create a new `Shader` directly from GLSL source code.
@@ -351,6 +616,72 @@ where
         Ok(())
     }
 
+    /// Binds an `Image` to the sampler with the given name, such as
+    /// `t_Palette`, so the shader can sample it alongside the image of
+    /// whatever it's drawing, which is always `t_Texture`.  The image
+    /// is sampled with its own filter and wrap modes, as set with
+    /// [`Image::set_filter()`](type.Image.html#method.set_filter) and
+    /// [`Image::set_wrap()`](type.Image.html#method.set_wrap).
+    ///
+    /// Like constants sent with [`send()`](#method.send), the image stays
+    /// bound until it's replaced.  Samplers with nothing bound to them
+    /// sample the drawable's own image.  Returns an error if the shader
+    /// has no sampler by that name.
+    pub fn set_image(&self, ctx: &mut Context, name: &str, image: &Image) -> GameResult {
+        self.debug_id.assert(ctx);
+        image.debug_id.assert(ctx);
+        let gfx = &mut ctx.gfx_context;
+        let sampler = gfx
+            .samplers
+            .get_or_insert(image.sampler_info, gfx.factory.as_mut());
+        let view = gfx
+            .backend_spec
+            .raw_to_typed_shader_resource(image.texture.clone());
+        gfx.shaders[self.id].set_image(name, Some((view, sampler)))
+    }
+
+    /// Unbinds whatever `Image` is bound to the sampler with the given
+    /// name, as set with [`set_image()`](#method.set_image).  Returns an
+    /// error if the shader has no sampler by that name.
+    pub fn clear_image(&self, ctx: &mut Context, name: &str) -> GameResult {
+        self.debug_id.assert(ctx);
+        ctx.gfx_context.shaders[self.id].set_image(name, None)
+    }
+
+    /// Sets the layout of custom per-vertex attributes the shader reads,
+    /// besides ggez's own `a_Pos`, `a_Uv` and `a_VertColor`, or `None`
+    /// if it has none.  See [`AttributeFormat`](struct.AttributeFormat.html).
+    /// Anything drawn with the shader must then have them, which for a
+    /// `Mesh` are set with [`Mesh::set_vertex_attributes()`](struct.Mesh.html#method.set_vertex_attributes).
+    ///
+    /// Returns an error if the attributes don't match the types the
+    /// shader declares them as.
+    pub fn set_vertex_attributes(
+        &self,
+        ctx: &mut Context,
+        format: Option<AttributeFormat>,
+    ) -> GameResult {
+        self.debug_id.assert(ctx);
+        let gfx = &mut ctx.gfx_context;
+        gfx.shaders[self.id].set_vertex_attributes(&mut *gfx.factory, format)
+    }
+
+    /// Sets the layout of custom per-instance attributes the shader
+    /// reads, or `None` if it has none, like
+    /// [`set_vertex_attributes()`](#method.set_vertex_attributes) does
+    /// for vertices.  Only a `SpriteBatch` can have them, set with
+    /// [`SpriteBatch::set_instance_attributes()`](spritebatch/struct.SpriteBatch.html#method.set_instance_attributes),
+    /// with one for each sprite.
+    pub fn set_instance_attributes(
+        &self,
+        ctx: &mut Context,
+        format: Option<AttributeFormat>,
+    ) -> GameResult {
+        self.debug_id.assert(ctx);
+        let gfx = &mut ctx.gfx_context;
+        gfx.shaders[self.id].set_instance_attributes(&mut *gfx.factory, format)
+    }
+
     /// Gets the shader ID for the `Shader` which is used by the
     /// graphics context for identifying shaders in its cache
     pub fn shader_id(&self) -> ShaderId {
@@ -372,6 +703,10 @@ struct ShaderProgram<Spec: graphics::BackendSpec, C: Structure<ConstFormat>> {
     buffer: Buffer<Spec::Resources, C>,
     psos: PsoSet<Spec, C>,
     active_blend_mode: BlendMode,
+    set: ShaderSet<Spec::Resources>,
+    settings: PsoSettings,
+    /// Images bound to the program's extra samplers, by name.
+    images: HashMap<String, SamplerData<Spec::Resources>>,
 }
 
 impl<Spec, C> fmt::Debug for ShaderProgram<Spec, C>
@@ -389,10 +724,50 @@ where
 pub trait ShaderHandle<Spec: graphics::BackendSpec>: fmt::Debug {
     /// Draw with the current Shader
     fn draw(
-        &self,
+        &mut self,
         encoder: &mut Encoder<Spec::Resources, Spec::CommandBuffer>,
+        factory: &mut Spec::Factory,
         slice: &Slice<Spec::Resources>,
         data: &graphics::pipe::Data<Spec::Resources>,
+        target: &TargetState<Spec::Resources>,
+        attributes: &AttributeBuffers<Spec::Resources>,
+    ) -> GameResult;
+
+    /// Binds an image and sampler to one of the shader program's
+    /// samplers besides `t_Texture`, or unbinds it if given `None`
+    fn set_image(
+        &mut self,
+        name: &str,
+        image: Option<(
+            ShaderResourceView<Spec::Resources, [f32; 4]>,
+            gfx::handle::Sampler<Spec::Resources>,
+        )>,
+    ) -> GameResult;
+
+    /// Sets the layout of the shader program's custom vertex
+    /// attributes, if it has any, recreating its PSOs
+    fn set_vertex_attributes(
+        &mut self,
+        factory: &mut Spec::Factory,
+        format: Option<AttributeFormat>,
+    ) -> GameResult;
+
+    /// Sets the layout of the shader program's custom instance
+    /// attributes, if it has any, recreating its PSOs
+    fn set_instance_attributes(
+        &mut self,
+        factory: &mut Spec::Factory,
+        format: Option<AttributeFormat>,
+    ) -> GameResult;
+
+    /// Recompiles the shader program from new source code, keeping
+    /// its constants, images and PSO settings.  If that fails, the
+    /// old program is kept.
+    fn reload(
+        &mut self,
+        factory: &mut Spec::Factory,
+        vertex_source: &[u8],
+        pixel_source: &[u8],
     ) -> GameResult;
 
     /// Sets the shader program's blend mode
@@ -402,24 +777,170 @@ pub trait ShaderHandle<Spec: graphics::BackendSpec>: fmt::Debug {
     fn blend_mode(&self) -> BlendMode;
 }
 
+impl<Spec, C> ShaderProgram<Spec, C>
+where
+    Spec: graphics::BackendSpec,
+    C: Structure<ConstFormat>,
+{
+    /// Recreates all the PSOs the shader program has so far with
+    /// new settings, keeping the old ones if that fails.
+    fn rebuild_psos(&mut self, factory: &mut Spec::Factory, settings: PsoSettings) -> GameResult {
+        let mut psos = PsoSet::new(self.psos.psos.len());
+        for key in self.psos.psos.keys() {
+            let pso = create_pso::<Spec, C>(factory, &self.set, &settings, *key)?;
+            psos.insert_mode(*key, pso);
+        }
+        self.psos = psos;
+        self.settings = settings;
+        Ok(())
+    }
+}
+
 impl<Spec, C> ShaderHandle<Spec> for ShaderProgram<Spec, C>
 where
     Spec: graphics::BackendSpec,
     C: Structure<ConstFormat>,
 {
     fn draw(
-        &self,
+        &mut self,
         encoder: &mut Encoder<Spec::Resources, Spec::CommandBuffer>,
+        factory: &mut Spec::Factory,
         slice: &Slice<Spec::Resources>,
         data: &graphics::pipe::Data<Spec::Resources>,
+        target: &TargetState<Spec::Resources>,
+        attributes: &AttributeBuffers<Spec::Resources>,
+    ) -> GameResult {
+        let key = pso_key(self.active_blend_mode, target.stencil, target.depth);
+        let depth_stencil: Option<DepthStencilData<Spec::Resources>> =
+            if target.stencil == StencilMode::Off && target.depth == DepthMode::Off {
+                None
+            } else {
+                let view = target.depth_stencil.ok_or_else(|| {
+                    GameError::RenderError(
+                        "Tried to draw with a stencil or depth mode, \
+                         but the canvas has no depth and stencil buffer"
+                            .into(),
+                    )
+                })?;
+                let reference = target.stencil.reference().unwrap_or(0);
+                Some((Typed::new(view.clone()), (reference, reference)))
+            };
+        if !self.psos.has_mode(key) {
+            let pso = create_pso::<Spec, C>(factory, &self.set, &self.settings, key)?;
+            self.psos.insert_mode(key, pso);
+        }
+        let pso = self.psos.mode(key)?;
+
+        let meta = pso.get_meta();
+        if meta.vertex_attributes.is_active() && attributes.vertices.is_none() {
+            return Err(GameError::RenderError(
+                "Shader uses custom vertex attributes, but what is being drawn has none".into(),
+            ));
+        }
+        if meta.instance_attributes.is_active() && attributes.instances.is_none() {
+            return Err(GameError::RenderError(
+                "Shader uses custom instance attributes, but what is being drawn has none".into(),
+            ));
+        }
+
+        let outputs = [&meta.pipe.out1, &meta.pipe.out2, &meta.pipe.out3]
+            .iter()
+            .rposition(|out| out.is_active())
+            .map_or(1, |i| i + 2);
+        if outputs > target.color_attachments {
+            let msg = format!(
+                "Shader writes to {} outputs, but the canvas only has {} color attachments",
+                outputs, target.color_attachments
+            );
+            return Err(GameError::RenderError(msg));
+        }
+
+        // Samplers with no image bound just get the drawable's own.
+        let images: Vec<_> = meta
+            .samplers
+            .iter()
+            .map(|(name, _)| self.images.get(name).unwrap_or(&data.tex).clone())
+            .collect();
+
+        encoder.draw(
+            slice,
+            pso,
+            &ConstData {
+                pipe: data,
+                consts: &self.buffer,
+                depth_stencil: depth_stencil.as_ref(),
+                images: &images,
+                attributes: *attributes,
+            },
+        );
+        Ok(())
+    }
+
+    fn set_image(&mut self, name: &str, image: Option<SamplerData<Spec::Resources>>) -> GameResult {
+        let pso = self.psos.mode(pso_key(
+            self.active_blend_mode,
+            StencilMode::Off,
+            DepthMode::Off,
+        ))?;
+        let samplers = &pso.get_meta().samplers;
+        if !samplers.iter().any(|(sampler, _)| sampler == name) {
+            return Err(GameError::RenderError(format!(
+                "Shader has no sampler named {}",
+                name
+            )));
+        }
+        let _ = match image {
+            Some(image) => self.images.insert(name.to_owned(), image),
+            None => self.images.remove(name),
+        };
+        Ok(())
+    }
+
+    fn set_vertex_attributes(
+        &mut self,
+        factory: &mut Spec::Factory,
+        format: Option<AttributeFormat>,
     ) -> GameResult {
-        let pso = self.psos.mode(self.active_blend_mode)?;
-        encoder.draw(slice, pso, &ConstData(data, &self.buffer));
+        let settings = PsoSettings {
+            vertex_attributes: format,
+            ..self.settings.clone()
+        };
+        self.rebuild_psos(factory, settings)
+    }
+
+    fn set_instance_attributes(
+        &mut self,
+        factory: &mut Spec::Factory,
+        format: Option<AttributeFormat>,
+    ) -> GameResult {
+        let settings = PsoSettings {
+            instance_attributes: format,
+            ..self.settings.clone()
+        };
+        self.rebuild_psos(factory, settings)
+    }
+
+    fn reload(
+        &mut self,
+        factory: &mut Spec::Factory,
+        vertex_source: &[u8],
+        pixel_source: &[u8],
+    ) -> GameResult {
+        let set = factory.create_shader_set(vertex_source, pixel_source)?;
+        let mut psos = PsoSet::new(self.psos.psos.len());
+        for key in self.psos.psos.keys() {
+            let pso = create_pso::<Spec, C>(factory, &set, &self.settings, *key)?;
+            psos.insert_mode(*key, pso);
+        }
+        self.psos = psos;
+        self.set = set;
         Ok(())
     }
 
     fn set_blend_mode(&mut self, mode: BlendMode) -> GameResult {
-        let _ = self.psos.mode(mode)?;
+        let _ = self
+            .psos
+            .mode(pso_key(mode, StencilMode::Off, DepthMode::Off))?;
         self.active_blend_mode = mode;
         Ok(())
     }
@@ -429,6 +950,131 @@ where
     }
 }
 
+/// The source files of a shader created with
+/// [`Shader::new()`](type.Shader.html#method.new), and when they
+/// were last modified, so it can be reloaded when they change.
+#[derive(Debug, Clone)]
+pub(crate) struct ShaderSources {
+    id: ShaderId,
+    vertex_path: PathBuf,
+    pixel_path: PathBuf,
+    /// When each file was last modified and how long it was.
+    stamps: (Option<(SystemTime, u64)>, Option<(SystemTime, u64)>),
+}
+
+impl ShaderSources {
+    fn new(filesystem: &Filesystem, id: ShaderId, vertex_path: &Path, pixel_path: &Path) -> Self {
+        ShaderSources {
+            id,
+            vertex_path: vertex_path.to_path_buf(),
+            pixel_path: pixel_path.to_path_buf(),
+            stamps: (filesystem.stamp(vertex_path), filesystem.stamp(pixel_path)),
+        }
+    }
+}
+
+/// Keeps track of the source files of every shader created with
+/// [`Shader::new()`](type.Shader.html#method.new), and reloads them
+/// when they change once [`watch_shaders()`](fn.watch_shaders.html)
+/// turns it on.
+pub(crate) struct ShaderWatcher {
+    pub(crate) sources: Vec<ShaderSources>,
+    on_error: Option<Box<dyn FnMut(ShaderId, GameError)>>,
+}
+
+impl ShaderWatcher {
+    pub(crate) fn new() -> Self {
+        ShaderWatcher {
+            sources: vec![],
+            on_error: None,
+        }
+    }
+}
+
+impl fmt::Debug for ShaderWatcher {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            formatter,
+            "<ShaderWatcher: {} shaders, watching: {}>",
+            self.sources.len(),
+            self.on_error.is_some()
+        )
+    }
+}
+
+/// Starts watching the source files of every shader created with
+/// [`Shader::new()`](type.Shader.html#method.new) for changes, and
+/// recompiling the shader when they do, for iterating on shaders
+/// without restarting the game.  The shader keeps the same
+/// [`ShaderId`](type.ShaderId.html), constants and images, so
+/// existing `Shader` handles carry on working.
+///
+/// Only files on the physical filesystem, such as the `resources/`
+/// directory or paths added with
+/// [`ContextBuilder::add_resource_path()`](../struct.ContextBuilder.html#method.add_resource_path),
+/// can be watched; files in `resources.zip` never change.
+///
+/// The files are checked once a frame by [`event::run()`](../event/fn.run.html),
+/// or by [`reload_changed_shaders()`](fn.reload_changed_shaders.html)
+/// if you have your own main loop.  If a shader doesn't compile,
+/// usually a `GameError::ShaderProgramError`, `on_error` is called
+/// with its ID and the error, and the shader keeps its old program.
+pub fn watch_shaders<F>(ctx: &mut Context, on_error: F)
+where
+    F: FnMut(ShaderId, GameError) + 'static,
+{
+    ctx.gfx_context.shader_watcher.on_error = Some(Box::new(on_error));
+}
+
+/// Stops watching shader source files for changes, as started with
+/// [`watch_shaders()`](fn.watch_shaders.html).
+pub fn unwatch_shaders(ctx: &mut Context) {
+    ctx.gfx_context.shader_watcher.on_error = None;
+}
+
+/// Recompiles any shader whose source files have changed since it was
+/// last compiled, if [`watch_shaders()`](fn.watch_shaders.html) has
+/// been called; otherwise it does nothing.  [`event::run()`](../event/fn.run.html)
+/// calls this every frame, so you only need it if you have your own
+/// main loop.
+pub fn reload_changed_shaders(ctx: &mut Context) {
+    let filesystem = &mut ctx.filesystem;
+    let shaders = &mut ctx.gfx_context.shaders;
+    let factory = &mut ctx.gfx_context.factory;
+    let watcher = &mut ctx.gfx_context.shader_watcher;
+    let on_error = match watcher.on_error {
+        Some(ref mut on_error) => on_error,
+        None => return,
+    };
+    for sources in &mut watcher.sources {
+        let stamps = (
+            filesystem.stamp(&sources.vertex_path),
+            filesystem.stamp(&sources.pixel_path),
+        );
+        // Files that vanished, such as halfway through being saved,
+        // are left alone until they come back.
+        if stamps.0.is_none() || stamps.1.is_none() || stamps == sources.stamps {
+            continue;
+        }
+        sources.stamps = stamps;
+        let result = read_source(filesystem, &sources.vertex_path).and_then(|vertex| {
+            let pixel = read_source(filesystem, &sources.pixel_path)?;
+            shaders[sources.id].reload(&mut **factory, &vertex, &pixel)
+        });
+        match result {
+            Ok(()) => debug!("Reloaded shader {}", sources.id),
+            Err(e) => on_error(sources.id, e),
+        }
+    }
+}
+
+fn read_source(filesystem: &mut Filesystem, path: &Path) -> GameResult<Vec<u8>> {
+    let mut buf = Vec::new();
+    let mut reader = filesystem.open(path)?;
+    let _ = reader.read_to_end(&mut buf)?;
+    Ok(buf)
+}
+
 /// A lock for RAII shader regions. The shader automatically gets cleared once
 /// the lock goes out of scope, restoring the previous shader (if any).
 ///
@@ -479,11 +1125,40 @@ pub fn clear_shader(ctx: &mut Context) {
     *ctx.gfx_context.current_shader.borrow_mut() = None;
 }
 
+/// The depth and stencil buffer and stencil reference
+/// values to draw with, if any.
+type DepthStencilData<R> = (DepthStencilView<R, format::DepthStencil>, (u8, u8));
+
+/// An image and sampler for one of a shader's samplers.
+type SamplerData<R> = (ShaderResourceView<R, [f32; 4]>, gfx::handle::Sampler<R>);
+
+/// The name of the sampler every shader uses for the
+/// image being drawn, which is part of `pipe`.
+const DRAWABLE_SAMPLER: &str = "t_Texture";
+
+/// Everything a shader's PSO links to: `pipe`, plus the shader's
+/// constants, depth and stencil buffer, extra samplers and custom
+/// attribute buffers.
 #[derive(Debug)]
-struct ConstMeta<C: Structure<ConstFormat>>(graphics::pipe::Meta, ConstantBuffer<C>);
+struct ConstMeta<C: Structure<ConstFormat>> {
+    pipe: graphics::pipe::Meta,
+    consts: ConstantBuffer<C>,
+    depth_stencil: DepthStencilTarget<format::DepthStencil>,
+    samplers: Vec<(String, TextureSampler<[f32; 4]>)>,
+    vertex_attributes: RawVertexBuffer,
+    instance_attributes: RawVertexBuffer,
+}
 
+/// What gets bound to a `ConstMeta` for a draw call.
 #[derive(Debug)]
-struct ConstData<'a, R: Resources, C: 'a>(&'a graphics::pipe::Data<R>, &'a Buffer<R, C>);
+struct ConstData<'a, R: Resources, C: 'a> {
+    pipe: &'a graphics::pipe::Data<R>,
+    consts: &'a Buffer<R, C>,
+    depth_stencil: Option<&'a DepthStencilData<R>>,
+    /// One for each of `ConstMeta::samplers`, in the same order.
+    images: &'a [SamplerData<R>],
+    attributes: AttributeBuffers<'a, R>,
+}
 
 impl<'a, R, C> PipelineData<R> for ConstData<'a, R, C>
 where
@@ -499,13 +1174,35 @@ where
         man: &mut Manager<R>,
         access: &mut AccessInfo<R>,
     ) {
-        self.0.bake_to(out, &meta.0, man, access);
-        meta.1.bind_to(out, self.1, man, access);
+        self.pipe.bake_to(out, &meta.pipe, man, access);
+        meta.consts.bind_to(out, self.consts, man, access);
+        if let Some(depth_stencil) = self.depth_stencil {
+            meta.depth_stencil.bind_to(out, depth_stencil, man, access);
+        }
+        for ((_, sampler), image) in meta.samplers.iter().zip(self.images) {
+            sampler.bind_to(out, image, man, access);
+        }
+        if let Some(vertices) = self.attributes.vertices {
+            meta.vertex_attributes.bind_to(out, vertices, man, access);
+        }
+        if let Some(instances) = self.attributes.instances {
+            meta.instance_attributes
+                .bind_to(out, instances, man, access);
+        }
     }
 }
 
+/// How to link a shader's PSO into a `ConstMeta`.
 #[derive(Debug)]
-struct ConstInit<'a, C>(graphics::pipe::Init<'a>, String, PhantomData<C>);
+struct ConstInit<'a, C> {
+    pipe: graphics::pipe::Init<'a>,
+    /// The name of the shader's constant buffer.
+    consts_name: String,
+    depth_stencil: Option<(Depth, Stencil)>,
+    vertex_attributes: Option<AttributeFormat>,
+    instance_attributes: Option<AttributeFormat>,
+    consts: PhantomData<C>,
+}
 
 impl<'a, C> PipelineInit for ConstInit<'a, C>
 where
@@ -518,13 +1215,17 @@ where
         desc: &mut Descriptor,
         info: &'s ProgramInfo,
     ) -> Result<Self::Meta, InitError<&'s str>> {
-        let mut meta1 = ConstantBuffer::<C>::new();
+        let mut consts = ConstantBuffer::<C>::new();
+        let mut depth_stencil = DepthStencilTarget::<format::DepthStencil>::new();
+        if let Some(ref state) = self.depth_stencil {
+            desc.depth_stencil = depth_stencil.link_depth_stencil(state);
+        }
 
         let mut index = None;
         for (i, cb) in info.constant_buffers.iter().enumerate() {
-            match meta1.link_constant_buffer(cb, &self.1.as_str()) {
+            match consts.link_constant_buffer(cb, &self.consts_name.as_str()) {
                 Some(Ok(d)) => {
-                    assert!(meta1.is_active());
+                    assert!(consts.is_active());
                     desc.constant_buffers[cb.slot as usize] = Some(d);
                     index = Some(i);
                     break;
@@ -534,48 +1235,135 @@ where
             }
         }
 
+        // Any texture besides the drawable's own is an extra sampler
+        // that images can be bound to with `Shader::set_image()`.
+        let mut samplers = Vec::new();
+        for tex in info.textures.iter().filter(|t| t.name != DRAWABLE_SAMPLER) {
+            let mut sampler = TextureSampler::<[f32; 4]>::new();
+            let name = tex.name.as_str();
+            match sampler.link_resource_view(tex, &name) {
+                Some(Ok(d)) => desc.resource_views[tex.slot as usize] = Some(d),
+                Some(Err(_)) => return Err(InitError::ResourceView(&tex.name, Some(()))),
+                None => (),
+            }
+            for sm in &info.samplers {
+                if let Some(d) = sampler.link_sampler(sm, &name) {
+                    desc.samplers[sm.slot as usize] = Some(d);
+                }
+            }
+            samplers.push((tex.name.clone(), sampler));
+        }
+
+        // `pipe` has the first two vertex buffers, so custom
+        // attributes go in the ones after.
+        let mut vertex_buffer = RawVertexBuffer::new();
+        let mut instance_buffer = RawVertexBuffer::new();
+        let vertex_names =
+            link_attributes(&mut vertex_buffer, desc, info, self.vertex_attributes, 2, 0)?;
+        let instance_index = if vertex_buffer.is_active() { 3 } else { 2 };
+        let instance_names = link_attributes(
+            &mut instance_buffer,
+            desc,
+            info,
+            self.instance_attributes,
+            instance_index,
+            1,
+        )?;
+
+        // create a local clone of the program info so that we can remove
+        // the vars we found, which `pipe` doesn't know anything about
+        let mut program_info = info.clone();
         if let Some(index) = index {
-            // create a local clone of the program info so that we can remove
-            // the var we found from the `constant_buffer`
-            let mut program_info = info.clone();
             let _ = program_info.constant_buffers.remove(index);
+        }
+        program_info.textures.retain(|t| t.name == DRAWABLE_SAMPLER);
+        program_info.samplers.retain(|s| s.name == DRAWABLE_SAMPLER);
+        program_info.vertex_attributes.retain(|at| {
+            let name = at.name.as_str();
+            !vertex_names.contains(&name) && !instance_names.contains(&name)
+        });
 
-            let meta0 = match self.0.link_to(desc, &program_info) {
-                Ok(m) => m,
-                Err(e) => {
-                    // unfortunately... the error lifetime is bound to the
-                    // lifetime of our cloned program info which is bad since it
-                    // will go out of scope at the end of the function, so lets
-                    // convert the error to one that is bound to the lifetime of
-                    // the program info that was passed in!
-                    macro_rules! fixlifetimes {
-                        ($e:ident {
-                            $( $ty:path => $a:ident, )*
-                        }) => {{
-                            match $e {
-                                $( $ty(name, _) => {
-                                    let var = info.$a.iter().find(|v| v.name == name).unwrap();
-                                    // We can do better with the error data...
-                                    return Err($ty(&var.name, None));
-                                } )*
-                            }
-                        }}
-                    }
-                    fixlifetimes!(e {
-                        InitError::VertexImport => vertex_attributes,
-                        InitError::ConstantBuffer => constant_buffers,
-                        InitError::GlobalConstant => globals,
-                        InitError::ResourceView => textures,
-                        InitError::UnorderedView => unordereds,
-                        InitError::Sampler => samplers,
-                        InitError::PixelExport => outputs,
-                    })
+        let pipe = match self.pipe.link_to(desc, &program_info) {
+            Ok(m) => m,
+            Err(e) => {
+                // unfortunately... the error lifetime is bound to the
+                // lifetime of our cloned program info which is bad since it
+                // will go out of scope at the end of the function, so lets
+                // convert the error to one that is bound to the lifetime of
+                // the program info that was passed in!
+                macro_rules! fixlifetimes {
+                    ($e:ident {
+                        $( $ty:path => $a:ident, )*
+                    }) => {{
+                        match $e {
+                            $( $ty(name, _) => {
+                                let var = info.$a.iter().find(|v| v.name == name).unwrap();
+                                // We can do better with the error data...
+                                return Err($ty(&var.name, None));
+                            } )*
+                        }
+                    }}
                 }
-            };
+                fixlifetimes!(e {
+                    InitError::VertexImport => vertex_attributes,
+                    InitError::ConstantBuffer => constant_buffers,
+                    InitError::GlobalConstant => globals,
+                    InitError::ResourceView => textures,
+                    InitError::UnorderedView => unordereds,
+                    InitError::Sampler => samplers,
+                    InitError::PixelExport => outputs,
+                })
+            }
+        };
+
+        Ok(ConstMeta {
+            pipe,
+            consts,
+            depth_stencil,
+            samplers,
+            vertex_attributes: vertex_buffer,
+            instance_attributes: instance_buffer,
+        })
+    }
+}
+
+/// Links the shader's attributes that are in `attributes` but not in
+/// `pipe` to the custom attribute buffer with the given index, if there
+/// are any.  Returns the names of the linked attributes.
+fn link_attributes<'s>(
+    meta: &mut RawVertexBuffer,
+    desc: &mut Descriptor,
+    info: &'s ProgramInfo,
+    attributes: Option<AttributeFormat>,
+    index: BufferIndex,
+    rate: InstanceRate,
+) -> Result<Vec<&'s str>, InitError<&'s str>> {
+    let attributes = match attributes {
+        Some(attributes) => attributes,
+        None => return Ok(vec![]),
+    };
+    let is_pipe_attribute = |name: &str| {
+        <graphics::Vertex as Structure<format::Format>>::query(name).is_some()
+            || <graphics::InstanceProperties as Structure<format::Format>>::query(name).is_some()
+    };
+    let elements: Vec<(&str, Element<format::Format>)> = info
+        .vertex_attributes
+        .iter()
+        .filter(|at| !is_pipe_attribute(&at.name))
+        .filter_map(|at| (attributes.query)(&at.name).map(|e| (at.name.as_str(), e)))
+        .collect();
+    if elements.is_empty() {
+        return Ok(vec![]);
+    }
 
-            Ok(ConstMeta(meta0, meta1))
-        } else {
-            Ok(ConstMeta(self.0.link_to(desc, info)?, meta1))
+    let init = (&elements[..], attributes.stride, rate);
+    desc.vertex_buffers[index as usize] = meta.link_vertex_buffer(index, &init);
+    for at in &info.vertex_attributes {
+        match meta.link_input(at, &init) {
+            Some(Ok(d)) => desc.attributes[at.slot as usize] = Some(d),
+            Some(Err(fm)) => return Err(InitError::VertexImport(&at.name, Some(fm))),
+            None => (),
         }
     }
+    Ok(elements.into_iter().map(|(name, _)| name).collect())
 }
@@ -0,0 +1,212 @@
+//! Golden-image snapshot testing: compare a rendered frame against
+//! a reference image and find out how different they are.
+//!
+//! The usual workflow is to draw a frame (a
+//! [headless](../../struct.ContextBuilder.html#method.build_headless)
+//! `Context` is handy for this), then call
+//! [`compare_frame()`](fn.compare_frame.html) with the path to a
+//! reference PNG.  Reference images are loaded through the
+//! [`filesystem`](../../filesystem/index.html) module, so they usually
+//! live in your resources directory.  To make a new reference image,
+//! just save a frame you are happy with with
+//! [`save_frame()`](fn.save_frame.html) and copy it over.
+//!
+//! When a frame does not match, a diff image is written next to the
+//! reference path in the user directory, with the differing pixels
+//! painted solid red over a faded copy of the reference.
+//!
+//! All the images on disk are stored top row first, like any other
+//! PNG, so reference images can be made or touched up with other tools.
+
+use std::io::{self, Read};
+use std::path;
+
+use ::image;
+
+use crate::context::Context;
+use crate::error::{GameError, GameResult};
+use crate::filesystem;
+use crate::graphics;
+
+/// How different a frame is allowed to be from its reference image
+/// before it counts as a mismatch.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq, Hash)]
+pub struct Tolerance {
+    /// The largest difference allowed in any single color channel
+    /// (including alpha) before a pixel counts as differing.
+    pub per_channel: u8,
+    /// How many differing pixels are allowed before the whole frame
+    /// counts as a mismatch.
+    pub max_differing_pixels: usize,
+}
+
+impl Tolerance {
+    /// Creates a new `Tolerance`.
+    pub fn new(per_channel: u8, max_differing_pixels: usize) -> Self {
+        Self {
+            per_channel,
+            max_differing_pixels,
+        }
+    }
+
+    /// A `Tolerance` that requires every pixel to match exactly.
+    pub fn exact() -> Self {
+        Self::default()
+    }
+}
+
+/// The result of comparing a frame against a reference image.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Comparison {
+    /// The number of pixels that differed by more than
+    /// `Tolerance::per_channel` in at least one channel.
+    pub differing_pixels: usize,
+    /// The largest difference seen in any channel of any pixel.
+    pub max_channel_difference: u8,
+    /// Where the diff image was written, if the frame didn't match.
+    pub diff_path: Option<path::PathBuf>,
+    tolerance: Tolerance,
+}
+
+impl Comparison {
+    /// Whether the frame was close enough to the reference
+    /// to be considered a match.
+    pub fn matches(&self) -> bool {
+        self.differing_pixels <= self.tolerance.max_differing_pixels
+    }
+}
+
+/// Takes a screenshot of the current render target and compares it
+/// against the reference PNG at the given path, which is loaded through
+/// the [`filesystem`](../../filesystem/index.html) module.
+///
+/// If the frame does not match, writes a diff image to the user
+/// directory at the reference path with `.diff.png` replacing its
+/// extension, and returns its path in `Comparison::diff_path`.
+///
+/// Returns an error if the reference image can't be loaded or its
+/// size is different from the frame's.
+pub fn compare_frame<P: AsRef<path::Path>>(
+    ctx: &mut Context,
+    reference: P,
+    tolerance: Tolerance,
+) -> GameResult<Comparison> {
+    let reference = reference.as_ref();
+    let (frame_width, frame_height, actual) = read_frame(ctx)?;
+    let expected = {
+        let mut buf = Vec::new();
+        let mut reader = filesystem::open(ctx, reference)?;
+        let _ = reader.read_to_end(&mut buf)?;
+        image::load_from_memory(&buf)?.to_rgba()
+    };
+    let (width, height) = expected.dimensions();
+    if (width, height) != (u32::from(frame_width), u32::from(frame_height)) {
+        let msg = format!(
+            "Reference image {:?} is {}x{}, but the frame is {}x{}",
+            reference, width, height, frame_width, frame_height
+        );
+        return Err(GameError::RenderError(msg));
+    }
+
+    let (mut comparison, diff) = compare_rgba8(&expected, &actual, tolerance);
+    if !comparison.matches() {
+        let diff_path = reference.with_extension("diff.png");
+        match diff_path.parent() {
+            Some(parent) if parent != path::Path::new("/") => {
+                filesystem::create_dir(ctx, parent)?;
+            }
+            _ => (),
+        }
+        write_png(ctx, &diff_path, frame_width, frame_height, &diff)?;
+        comparison.diff_path = Some(diff_path);
+    }
+    Ok(comparison)
+}
+
+/// Takes a screenshot of the current render target and writes it to
+/// the given path in the user directory as a PNG, for use as a
+/// reference image with [`compare_frame()`](fn.compare_frame.html).
+pub fn save_frame<P: AsRef<path::Path>>(ctx: &mut Context, path: P) -> GameResult {
+    let (width, height, rgba) = read_frame(ctx)?;
+    write_png(ctx, path.as_ref(), width, height, &rgba)
+}
+
+/// Takes a screenshot and returns its size and RGBA pixels, top row
+/// first.  Render targets come back bottom row first, the way OpenGL
+/// stores them, so this is the one place that flips them.
+fn read_frame(ctx: &mut Context) -> GameResult<(u16, u16, Vec<u8>)> {
+    let frame = graphics::screenshot(ctx)?;
+    let rgba = frame.to_rgba8(ctx)?;
+    let stride = usize::from(frame.width()) * 4;
+    let flipped = rgba
+        .chunks(stride)
+        .rev()
+        .flat_map(|row| row.iter().cloned())
+        .collect();
+    Ok((frame.width(), frame.height(), flipped))
+}
+
+/// Writes RGBA pixels, top row first, to the given path in the user
+/// directory as a PNG.
+fn write_png(
+    ctx: &mut Context,
+    path: &path::Path,
+    width: u16,
+    height: u16,
+    rgba: &[u8],
+) -> GameResult {
+    let file = filesystem::create(ctx, path)?;
+    let writer = &mut io::BufWriter::new(file);
+    image::png::PNGEncoder::new(writer)
+        .encode(
+            rgba,
+            u32::from(width),
+            u32::from(height),
+            image::ColorType::RGBA(8),
+        )
+        .map_err(Into::into)
+}
+
+/// Compares two buffers of RGBA pixels of the same size, returning the
+/// `Comparison` and an RGBA diff image of the same size.
+///
+/// Panics if the buffers are different lengths.
+pub fn compare_rgba8(
+    expected: &[u8],
+    actual: &[u8],
+    tolerance: Tolerance,
+) -> (Comparison, Vec<u8>) {
+    assert_eq!(
+        expected.len(),
+        actual.len(),
+        "Tried to compare pixel buffers of different sizes"
+    );
+    let mut differing_pixels = 0;
+    let mut max_channel_difference = 0;
+    let mut diff = Vec::with_capacity(expected.len());
+    for (e, a) in expected.chunks(4).zip(actual.chunks(4)) {
+        let worst = e
+            .iter()
+            .zip(a)
+            .map(|(e, a)| if e > a { e - a } else { a - e })
+            .max()
+            .unwrap_or(0);
+        max_channel_difference = max_channel_difference.max(worst);
+        if worst > tolerance.per_channel {
+            differing_pixels += 1;
+            diff.extend_from_slice(&[255, 0, 0, 255]);
+        } else {
+            // Faded grayscale copy of the reference, so the red stands out.
+            let luma = (u16::from(e[0]) + u16::from(e[1]) + u16::from(e[2])) / 3;
+            let faded = (luma / 4 + 192) as u8;
+            diff.extend_from_slice(&[faded, faded, faded, 255]);
+        }
+    }
+    let comparison = Comparison {
+        differing_pixels,
+        max_channel_difference,
+        diff_path: None,
+        tolerance,
+    };
+    (comparison, diff)
+}
@@ -17,8 +17,13 @@ use crate::error;
 use crate::error::GameResult;
 use crate::graphics::shader::BlendMode;
 use crate::graphics::types::FilterMode;
-use crate::graphics::{self, transform_rect, BackendSpec, DrawParam, DrawTransform, Rect};
+use crate::graphics::{
+    self, transform_rect, AttributeBuffers, BackendSpec, DrawParam, DrawTransform, Rect,
+};
 use gfx;
+use gfx::memory::Typed;
+use gfx::pso::buffer::Structure;
+use gfx::traits::{FactoryExt, Pod};
 use gfx::Factory;
 
 /// A `SpriteBatch` draws a number of copies of the same image, using a single draw call.
@@ -34,6 +39,9 @@ pub struct SpriteBatch {
     image: graphics::Image,
     sprites: Vec<graphics::DrawParam>,
     blend_mode: Option<BlendMode>,
+    /// Custom instance attributes for shaders that use them,
+    /// and how many sprites they are for.
+    attributes: Option<(gfx::handle::RawBuffer<gfx_device_gl::Resources>, usize)>,
 }
 
 /// An index of a particular sprite in a `SpriteBatch`.
@@ -51,6 +59,7 @@ impl SpriteBatch {
             image,
             sprites: vec![],
             blend_mode: None,
+            attributes: None,
         }
     }
 
@@ -151,10 +160,44 @@ impl SpriteBatch {
     pub fn set_filter(&mut self, mode: FilterMode) {
         self.image.set_filter(mode);
     }
+
+    /// Gives each sprite in the batch custom attributes, such as a tint
+    /// or an animation frame, for a `Shader` set up to read them with
+    /// [`Shader::set_instance_attributes()`](../type.Shader.html#method.set_instance_attributes).
+    /// `I` should be the `vertex` struct its `AttributeFormat` was made
+    /// from, and there needs to be one for each sprite, in the order
+    /// they were added.  Drawing the batch with more sprites than that
+    /// returns an error.
+    ///
+    /// Shaders that don't read custom attributes just ignore them.
+    pub fn set_instance_attributes<I>(&mut self, ctx: &mut Context, attributes: &[I])
+    where
+        I: Pod + Structure<gfx::format::Format>,
+    {
+        self.image.debug_id.assert(ctx);
+        let buffer = ctx.gfx_context.factory.create_vertex_buffer(attributes);
+        self.attributes = Some((buffer.raw().clone(), attributes.len()));
+    }
+
+    /// Removes the custom instance attributes set with
+    /// [`set_instance_attributes()`](#method.set_instance_attributes).
+    pub fn clear_instance_attributes(&mut self) {
+        self.attributes = None;
+    }
 }
 
 impl graphics::Drawable for SpriteBatch {
     fn draw(&self, ctx: &mut Context, param: DrawParam) -> GameResult {
+        if let Some((_, count)) = self.attributes {
+            if count < self.sprites.len() {
+                let msg = format!(
+                    "Tried to draw {} sprites with only {} sets of instance attributes",
+                    self.sprites.len(),
+                    count
+                );
+                return Err(error::GameError::RenderError(msg));
+            }
+        }
         // Awkwardly we must update values on all sprites and such.
         // Also awkwardly we have this chain of colors with differing priorities.
         self.flush(ctx, &self.image)?;
@@ -186,7 +229,11 @@ impl graphics::Drawable for SpriteBatch {
         } else {
             None
         };
-        gfx.draw(Some(&slice))?;
+        let attributes = AttributeBuffers {
+            vertices: None,
+            instances: self.attributes.as_ref().map(|(buffer, _)| buffer),
+        };
+        gfx.draw_with_attributes(Some(&slice), attributes)?;
         if let Some(mode) = previous_mode {
             gfx.set_blend_mode(mode)?;
         }
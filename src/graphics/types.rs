@@ -180,6 +180,20 @@ impl Rect {
         let h = f32::max(self.bottom(), other.bottom()) - y;
         Rect { x, y, w, h }
     }
+
+    /// Returns the area covered by both of these `Rect`s, or `None`
+    /// if they don't overlap.
+    pub fn intersection(self, other: Rect) -> Option<Rect> {
+        let x = f32::max(self.x, other.x);
+        let y = f32::max(self.y, other.y);
+        let w = f32::min(self.right(), other.right()) - x;
+        let h = f32::min(self.bottom(), other.bottom()) - y;
+        if w < 0.0 || h < 0.0 {
+            None
+        } else {
+            Some(Rect { x, y, w, h })
+        }
+    }
 }
 
 impl approx::AbsDiffEq for Rect {
@@ -658,6 +672,18 @@ mod tests {
         }
     }
 
+    #[test]
+    fn headless_test_rect_intersection() {
+        let a = Rect::new(-1.0, 0.0, 2.0, 2.0);
+        let b = Rect::new(0.0, -1.0, 1.0, 1.5);
+        let expected = Rect::new(0.0, 0.0, 1.0, 0.5);
+        assert_relative_eq!(a.intersection(b).unwrap(), expected);
+        assert_relative_eq!(b.intersection(a).unwrap(), expected);
+
+        let c = Rect::new(5.0, 5.0, 1.0, 1.0);
+        assert_eq!(a.intersection(c), None);
+    }
+
     #[test]
     fn headless_test_rect_rotate() {
         {
@@ -0,0 +1,360 @@
+//! Maps named actions and axes to physical inputs, so your game can ask
+//! "is the player jumping?" instead of checking for the space bar,
+//! the A button and whatever else by hand, and so players can rebind
+//! their controls.
+//!
+//! An action is either pressed or not, and can be bound to any number
+//! of keys (optionally with modifiers, so `Ctrl+S` is different from `S`),
+//! mouse buttons or gamepad buttons.  An axis has a value from -1.0 to 1.0,
+//! and can be bound to gamepad axes or to a pair of keys.
+//!
+//! ```rust,no_run
+//! # use ggez::*;
+//! # use ggez::event::{Axis, Button, KeyCode, KeyMods};
+//! # use ggez::input::bindings::*;
+//! # fn t(ctx: &mut Context) -> GameResult {
+//! let bindings = InputBindings::new()
+//!     .bind_action("jump", Input::key(KeyCode::Space))
+//!     .bind_action("jump", Input::GamepadButton { button: Button::South })
+//!     .bind_action("save", Input::key_with_mods(KeyCode::S, KeyMods::CTRL))
+//!     .bind_axis("walk", AxisInput::Keys { negative: KeyCode::Left, positive: KeyCode::Right })
+//!     .bind_axis("walk", AxisInput::gamepad_axis(Axis::LeftStickX));
+//!
+//! if bindings.is_action_pressed(ctx, "jump") {
+//!     // ...
+//! }
+//! let walk_speed = bindings.axis_value(ctx, "walk") * 100.0;
+//!
+//! // Let the player change them and keep the changes around.
+//! bindings.save(ctx, "/controls.toml")?;
+//! let bindings = InputBindings::load(ctx, "/controls.toml")?;
+//! # Ok(())
+//! # }
+//! ```
+
+use std::collections::BTreeMap;
+use std::io::{Read, Write};
+use std::path;
+
+use crate::context::Context;
+use crate::error::GameResult;
+use crate::event::{Axis, Button};
+use crate::filesystem;
+use crate::input::gamepad::{self, GamepadId};
+use crate::input::keyboard::{self, KeyCode, KeyMods};
+use crate::input::mouse::{self, MouseButton};
+
+/// The deadzone used by [`AxisInput::gamepad_axis()`](enum.AxisInput.html#method.gamepad_axis).
+pub const DEFAULT_DEADZONE: f32 = 0.15;
+
+/// A physical input that can trigger an action.
+#[derive(Debug, Copy, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type")]
+pub enum Input {
+    /// A keyboard key, which only counts as pressed if at least
+    /// the given modifiers are held too.
+    Key {
+        /// The key.
+        keycode: KeyCode,
+        /// Modifiers that have to be held along with the key.
+        #[serde(default)]
+        mods: KeyMods,
+    },
+    /// A mouse button.
+    MouseButton {
+        /// The button.
+        button: MouseButton,
+    },
+    /// A button on the bindings' gamepad.
+    GamepadButton {
+        /// The button.
+        button: Button,
+    },
+}
+
+impl Input {
+    /// A keyboard key with no modifiers.
+    pub fn key(keycode: KeyCode) -> Self {
+        Input::Key {
+            keycode,
+            mods: KeyMods::NONE,
+        }
+    }
+
+    /// A keyboard key that only counts when the given
+    /// modifiers are also held, such as `Ctrl+S`.
+    pub fn key_with_mods(keycode: KeyCode, mods: KeyMods) -> Self {
+        Input::Key { keycode, mods }
+    }
+
+    fn is_pressed(&self, ctx: &Context, gamepad_id: Option<GamepadId>) -> bool {
+        match *self {
+            Input::Key { keycode, mods } => {
+                keyboard::is_key_pressed(ctx, keycode) && keyboard::is_mod_active(ctx, mods)
+            }
+            Input::MouseButton { button } => mouse::button_pressed(ctx, button),
+            Input::GamepadButton { button } => gamepad_id
+                .map(|id| gamepad::button_pressed(ctx, id, button))
+                .unwrap_or(false),
+        }
+    }
+}
+
+/// A physical input that can drive an axis.
+#[derive(Debug, Copy, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type")]
+pub enum AxisInput {
+    /// A pair of keys; holding `negative` gives -1.0, holding
+    /// `positive` gives 1.0, and holding both or neither gives 0.0.
+    Keys {
+        /// The key for the negative direction.
+        negative: KeyCode,
+        /// The key for the positive direction.
+        positive: KeyCode,
+    },
+    /// An axis on the bindings' gamepad.
+    GamepadAxis {
+        /// The axis.
+        axis: Axis,
+        /// Values closer to 0.0 than this are treated as 0.0, and the
+        /// rest of the range is stretched to still cover -1.0 to 1.0.
+        deadzone: f32,
+        /// Whether to flip the sign of the axis.
+        #[serde(default)]
+        inverted: bool,
+    },
+}
+
+impl AxisInput {
+    /// A gamepad axis with the
+    /// [default deadzone](constant.DEFAULT_DEADZONE.html).
+    pub fn gamepad_axis(axis: Axis) -> Self {
+        AxisInput::GamepadAxis {
+            axis,
+            deadzone: DEFAULT_DEADZONE,
+            inverted: false,
+        }
+    }
+
+    fn value(&self, ctx: &Context, gamepad_id: Option<GamepadId>) -> f32 {
+        match *self {
+            AxisInput::Keys { negative, positive } => {
+                let mut value = 0.0;
+                if keyboard::is_key_pressed(ctx, negative) {
+                    value -= 1.0;
+                }
+                if keyboard::is_key_pressed(ctx, positive) {
+                    value += 1.0;
+                }
+                value
+            }
+            AxisInput::GamepadAxis {
+                axis,
+                deadzone,
+                inverted,
+            } => {
+                let raw = gamepad_id
+                    .map(|id| gamepad::axis(ctx, id, axis))
+                    .unwrap_or(0.0);
+                let value = apply_deadzone(raw, deadzone);
+                if inverted {
+                    -value
+                } else {
+                    value
+                }
+            }
+        }
+    }
+}
+
+/// Zeroes out values inside the deadzone and rescales
+/// the rest so the output still covers -1.0 to 1.0.
+fn apply_deadzone(value: f32, deadzone: f32) -> f32 {
+    let deadzone = deadzone.max(0.0).min(0.999);
+    if value.abs() <= deadzone {
+        0.0
+    } else {
+        let scaled = ((value.abs() - deadzone) / (1.0 - deadzone)).min(1.0);
+        if value < 0.0 {
+            -scaled
+        } else {
+            scaled
+        }
+    }
+}
+
+/// A set of named actions and axes and the inputs bound to them.
+///
+/// Gamepad inputs are read from one gamepad, set with
+/// [`set_gamepad()`](#method.set_gamepad), so for local multiplayer
+/// you can give each player their own `InputBindings`.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct InputBindings {
+    #[serde(default)]
+    actions: BTreeMap<String, Vec<Input>>,
+    #[serde(default)]
+    axes: BTreeMap<String, Vec<AxisInput>>,
+    // Gamepad ids are only meaningful while the game is running.
+    #[serde(skip)]
+    gamepad: Option<GamepadId>,
+}
+
+impl InputBindings {
+    /// Creates a new, empty set of bindings.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Binds another input to the named action.
+    pub fn bind_action(mut self, action: &str, input: Input) -> Self {
+        self.actions
+            .entry(action.to_owned())
+            .or_insert_with(Vec::new)
+            .push(input);
+        self
+    }
+
+    /// Binds another input to the named axis.
+    pub fn bind_axis(mut self, axis: &str, input: AxisInput) -> Self {
+        self.axes
+            .entry(axis.to_owned())
+            .or_insert_with(Vec::new)
+            .push(input);
+        self
+    }
+
+    /// Removes all inputs bound to the named action,
+    /// returning what they were.
+    pub fn unbind_action(&mut self, action: &str) -> Vec<Input> {
+        self.actions.remove(action).unwrap_or_default()
+    }
+
+    /// Removes all inputs bound to the named axis,
+    /// returning what they were.
+    pub fn unbind_axis(&mut self, axis: &str) -> Vec<AxisInput> {
+        self.axes.remove(axis).unwrap_or_default()
+    }
+
+    /// Returns the inputs bound to the named action.
+    pub fn action_inputs(&self, action: &str) -> &[Input] {
+        self.actions.get(action).map(Vec::as_slice).unwrap_or(&[])
+    }
+
+    /// Returns the inputs bound to the named axis.
+    pub fn axis_inputs(&self, axis: &str) -> &[AxisInput] {
+        self.axes.get(axis).map(Vec::as_slice).unwrap_or(&[])
+    }
+
+    /// Sets which gamepad to read gamepad buttons and axes from.
+    /// With `None`, gamepad bindings are ignored.
+    pub fn set_gamepad(&mut self, id: Option<GamepadId>) {
+        self.gamepad = id;
+    }
+
+    /// Returns which gamepad gamepad buttons and axes are read from.
+    pub fn gamepad(&self) -> Option<GamepadId> {
+        self.gamepad
+    }
+
+    /// Checks whether any input bound to the named action is pressed.
+    /// Unknown actions are never pressed.
+    pub fn is_action_pressed(&self, ctx: &Context, action: &str) -> bool {
+        self.action_inputs(action)
+            .iter()
+            .any(|input| input.is_pressed(ctx, self.gamepad))
+    }
+
+    /// Returns the value of the named axis, from -1.0 to 1.0.  If
+    /// several inputs are bound to it, the one pushed furthest wins.
+    /// Unknown axes are always 0.0.
+    pub fn axis_value(&self, ctx: &Context, axis: &str) -> f32 {
+        self.axis_inputs(axis)
+            .iter()
+            .map(|input| input.value(ctx, self.gamepad))
+            .fold(0.0, |best, value| {
+                if value.abs() > best.abs() {
+                    value
+                } else {
+                    best
+                }
+            })
+    }
+
+    /// Returns the names of the actions bound to the given key with
+    /// the given modifiers held, for use in
+    /// [`EventHandler::key_down_event()`](../../event/trait.EventHandler.html#method.key_down_event)
+    /// and friends.
+    pub fn key_actions(&self, keycode: KeyCode, keymods: KeyMods) -> Vec<&str> {
+        self.actions
+            .iter()
+            .filter(|(_, inputs)| {
+                inputs.iter().any(|input| match *input {
+                    Input::Key { keycode: k, mods } => k == keycode && keymods.contains(mods),
+                    _ => false,
+                })
+            })
+            .map(|(action, _)| action.as_str())
+            .collect()
+    }
+
+    /// Loads bindings from a TOML file at the given path.  The documentation
+    /// for the [`filesystem`](../../filesystem/index.html) module explains
+    /// how the path must be specified.
+    pub fn load<P: AsRef<path::Path>>(ctx: &mut Context, path: P) -> GameResult<Self> {
+        let mut s = String::new();
+        let mut file = filesystem::open(ctx, path)?;
+        let _ = file.read_to_string(&mut s)?;
+        Ok(toml::from_str(&s)?)
+    }
+
+    /// Saves the bindings as a TOML file at the given
+    /// path in the user directory.
+    pub fn save<P: AsRef<path::Path>>(&self, ctx: &mut Context, path: P) -> GameResult {
+        let s = toml::to_vec(self)?;
+        let mut file = filesystem::create(ctx, path)?;
+        file.write_all(&s)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use approx::assert_relative_eq;
+
+    #[test]
+    fn deadzone() {
+        assert_relative_eq!(apply_deadzone(0.1, 0.2), 0.0);
+        assert_relative_eq!(apply_deadzone(-0.2, 0.2), 0.0);
+        assert_relative_eq!(apply_deadzone(1.0, 0.2), 1.0);
+        assert_relative_eq!(apply_deadzone(-1.0, 0.2), -1.0);
+        assert_relative_eq!(apply_deadzone(0.6, 0.2), 0.5);
+        assert_relative_eq!(apply_deadzone(-0.6, 0.2), -0.5);
+    }
+
+    #[test]
+    fn bindings_encode_round_trip() {
+        let b1 = InputBindings::new()
+            .bind_action("jump", Input::key(KeyCode::Space))
+            .bind_action(
+                "jump",
+                Input::GamepadButton {
+                    button: Button::South,
+                },
+            )
+            .bind_action("save", Input::key_with_mods(KeyCode::S, KeyMods::CTRL))
+            .bind_axis(
+                "walk",
+                AxisInput::Keys {
+                    negative: KeyCode::A,
+                    positive: KeyCode::D,
+                },
+            )
+            .bind_axis("walk", AxisInput::gamepad_axis(Axis::LeftStickX));
+        let s = toml::to_string(&b1).unwrap();
+        let b2: InputBindings = toml::from_str(&s).unwrap();
+        assert_eq!(b1, b2);
+        assert_eq!(b2.key_actions(KeyCode::S, KeyMods::CTRL), vec!["save"]);
+        assert!(b2.key_actions(KeyCode::S, KeyMods::NONE).is_empty());
+    }
+}
@@ -3,24 +3,84 @@
 //! This is going to be a bit of a work-in-progress as gamepad input
 //! gets fleshed out.  The `gilrs` crate needs help to add better
 //! cross-platform support.  Why not give it a hand?
+//!
+//! To test gamepad handling without a real controller plugged in,
+//! replace the `Context`'s gamepad context with a
+//! [`ScriptedGamepadContext`](struct.ScriptedGamepadContext.html):
+//!
+//! ```rust,no_run
+//! # use ggez::*;
+//! # use ggez::event::Button;
+//! # use ggez::input::gamepad::{self, ScriptedGamepadContext};
+//! # fn t(ctx: &mut Context) {
+//! let script = ScriptedGamepadContext::new();
+//! ctx.gamepad_context = Box::new(script.clone());
+//! let id = script.connect("Test Pad");
+//! script.press_button(id, Button::South);
+//! assert!(gamepad::button_pressed(ctx, id, Button::South));
+//! # }
+//! ```
+use std::collections::{HashMap, HashSet, VecDeque};
 use std::fmt;
+use std::sync::{Arc, Mutex};
 
 pub use gilrs::{self, Event, Gamepad, Gilrs};
 
+use crate::event::{Axis, Button};
+
 /// A unique identifier for a particular GamePad
-#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
-pub struct GamepadId(pub(crate) gilrs::GamepadId);
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct GamepadId(pub(crate) usize);
+
+impl From<gilrs::GamepadId> for GamepadId {
+    fn from(id: gilrs::GamepadId) -> Self {
+        GamepadId(id.into())
+    }
+}
 
 use crate::context::Context;
 use crate::error::GameResult;
 
+/// Something that happened to a gamepad.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum GamepadEvent {
+    /// A gamepad was plugged in.
+    Connected(GamepadId),
+    /// A gamepad was unplugged.
+    Disconnected(GamepadId),
+    /// A button was pressed.
+    ButtonPressed(GamepadId, Button),
+    /// A button was released.
+    ButtonReleased(GamepadId, Button),
+    /// An axis changed to the given value.
+    AxisChanged(GamepadId, Axis, f32),
+}
+
+/// Basic information about a connected gamepad.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct GamepadInfo {
+    /// The gamepad's id.
+    pub id: GamepadId,
+    /// The gamepad's name, as reported by the OS.
+    pub name: String,
+}
+
 /// Trait object defining a gamepad/joystick context.
 pub trait GamepadContext {
     /// Returns a gamepad event.
-    fn next_event(&mut self) -> Option<Event>;
+    fn next_event(&mut self) -> Option<GamepadEvent>;
 
     /// returns the `Gamepad` associated with an id.
     fn gamepad(&self, id: GamepadId) -> Gamepad;
+
+    /// Returns all the currently connected gamepads.
+    fn gamepads(&self) -> Vec<GamepadInfo>;
+
+    /// Returns whether the given button is held down.
+    fn is_button_pressed(&self, id: GamepadId, button: Button) -> bool;
+
+    /// Returns the value of the given axis, from -1.0 to 1.0.
+    fn axis(&self, id: GamepadId, axis: Axis) -> f32;
 }
 
 /// A structure that contains gamepad state using `gilrs`.
@@ -39,15 +99,64 @@ impl GilrsGamepadContext {
         let gilrs = Gilrs::new()?;
         Ok(GilrsGamepadContext { gilrs })
     }
+
+    /// `gilrs` doesn't let us make its ids from numbers, so we
+    /// have to go looking for the gamepad instead.
+    fn find(&self, id: GamepadId) -> Option<Gamepad> {
+        self.gilrs
+            .gamepads()
+            .find(|&(gilrs_id, _)| GamepadId::from(gilrs_id) == id)
+            .map(|(_, gamepad)| gamepad)
+    }
 }
 
 impl GamepadContext for GilrsGamepadContext {
-    fn next_event(&mut self) -> Option<Event> {
-        self.gilrs.next_event()
+    fn next_event(&mut self) -> Option<GamepadEvent> {
+        while let Some(Event { id, event, .. }) = self.gilrs.next_event() {
+            let id = GamepadId::from(id);
+            let event = match event {
+                gilrs::EventType::Connected => GamepadEvent::Connected(id),
+                gilrs::EventType::Disconnected => GamepadEvent::Disconnected(id),
+                gilrs::EventType::ButtonPressed(button, _) => {
+                    GamepadEvent::ButtonPressed(id, button)
+                }
+                gilrs::EventType::ButtonReleased(button, _) => {
+                    GamepadEvent::ButtonReleased(id, button)
+                }
+                gilrs::EventType::AxisChanged(axis, value, _) => {
+                    GamepadEvent::AxisChanged(id, axis, value)
+                }
+                _ => continue,
+            };
+            return Some(event);
+        }
+        None
     }
 
     fn gamepad(&self, id: GamepadId) -> Gamepad {
-        self.gilrs.gamepad(id.0)
+        self.find(id).expect("No gamepad with the given id")
+    }
+
+    fn gamepads(&self) -> Vec<GamepadInfo> {
+        self.gilrs
+            .gamepads()
+            .map(|(id, gamepad)| GamepadInfo {
+                id: id.into(),
+                name: gamepad.name().to_owned(),
+            })
+            .collect()
+    }
+
+    fn is_button_pressed(&self, id: GamepadId, button: Button) -> bool {
+        self.find(id)
+            .map(|gamepad| gamepad.is_pressed(button))
+            .unwrap_or(false)
+    }
+
+    fn axis(&self, id: GamepadId, axis: Axis) -> f32 {
+        self.find(id)
+            .map(|gamepad| gamepad.value(axis))
+            .unwrap_or(0.0)
     }
 }
 
@@ -58,43 +167,283 @@ impl GamepadContext for GilrsGamepadContext {
 pub(crate) struct NullGamepadContext {}
 
 impl GamepadContext for NullGamepadContext {
-    fn next_event(&mut self) -> Option<Event> {
-        panic!("Gamepad module disabled")
+    fn next_event(&mut self) -> Option<GamepadEvent> {
+        None
     }
 
     fn gamepad(&self, _id: GamepadId) -> Gamepad {
         panic!("Gamepad module disabled")
     }
+
+    fn gamepads(&self) -> Vec<GamepadInfo> {
+        vec![]
+    }
+
+    fn is_button_pressed(&self, _id: GamepadId, _button: Button) -> bool {
+        false
+    }
+
+    fn axis(&self, _id: GamepadId, _axis: Axis) -> f32 {
+        0.0
+    }
+}
+
+#[derive(Debug, Default)]
+struct ScriptedGamepad {
+    name: String,
+    buttons: HashSet<Button>,
+    axes: HashMap<Axis, f32>,
+}
+
+#[derive(Debug, Default)]
+struct ScriptedState {
+    next_id: usize,
+    gamepads: HashMap<GamepadId, ScriptedGamepad>,
+    events: VecDeque<GamepadEvent>,
+}
+
+/// A [`GamepadContext`](trait.GamepadContext.html) where gamepads
+/// are connected, pressed and moved by calling its methods, for
+/// testing gamepad handling without any real hardware.
+///
+/// Each change is also queued up as an event, so
+/// [`event::run()`](../../event/fn.run.html) will pass it on to your
+/// [`EventHandler`](../../event/trait.EventHandler.html).
+/// Clones share the same gamepads, so keep a clone around to
+/// script it after giving it to the `Context`.
+#[derive(Debug, Clone, Default)]
+pub struct ScriptedGamepadContext {
+    state: Arc<Mutex<ScriptedState>>,
+}
+
+impl ScriptedGamepadContext {
+    /// Creates a new `ScriptedGamepadContext` with no gamepads.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn with_state<T>(&self, f: impl FnOnce(&mut ScriptedState) -> T) -> T {
+        let mut state = self
+            .state
+            .lock()
+            .expect("ScriptedGamepadContext lock poisoned");
+        f(&mut state)
+    }
+
+    /// Connects a new gamepad with the given name, returning its id.
+    pub fn connect(&self, name: &str) -> GamepadId {
+        self.with_state(|state| {
+            let id = GamepadId(state.next_id);
+            state.next_id += 1;
+            let gamepad = ScriptedGamepad {
+                name: name.to_owned(),
+                ..ScriptedGamepad::default()
+            };
+            let _ = state.gamepads.insert(id, gamepad);
+            state.events.push_back(GamepadEvent::Connected(id));
+            id
+        })
+    }
+
+    /// Disconnects the given gamepad.
+    pub fn disconnect(&self, id: GamepadId) {
+        self.with_state(|state| {
+            if state.gamepads.remove(&id).is_some() {
+                state.events.push_back(GamepadEvent::Disconnected(id));
+            }
+        })
+    }
+
+    /// Presses a button on the given gamepad.
+    /// Does nothing if it isn't connected.
+    pub fn press_button(&self, id: GamepadId, button: Button) {
+        self.with_state(|state| {
+            if let Some(gamepad) = state.gamepads.get_mut(&id) {
+                let _ = gamepad.buttons.insert(button);
+                state
+                    .events
+                    .push_back(GamepadEvent::ButtonPressed(id, button));
+            }
+        })
+    }
+
+    /// Releases a button on the given gamepad.
+    /// Does nothing if it isn't connected.
+    pub fn release_button(&self, id: GamepadId, button: Button) {
+        self.with_state(|state| {
+            if let Some(gamepad) = state.gamepads.get_mut(&id) {
+                let _ = gamepad.buttons.remove(&button);
+                state
+                    .events
+                    .push_back(GamepadEvent::ButtonReleased(id, button));
+            }
+        })
+    }
+
+    /// Moves an axis on the given gamepad to the given value,
+    /// clamped to -1.0 to 1.0.  Does nothing if it isn't connected.
+    pub fn move_axis(&self, id: GamepadId, axis: Axis, value: f32) {
+        let value = value.max(-1.0).min(1.0);
+        self.with_state(|state| {
+            if let Some(gamepad) = state.gamepads.get_mut(&id) {
+                let _ = gamepad.axes.insert(axis, value);
+                state
+                    .events
+                    .push_back(GamepadEvent::AxisChanged(id, axis, value));
+            }
+        })
+    }
+}
+
+impl ScriptedGamepadContext {
+    /// Changes the gamepads the way the given event says, without
+    /// queueing it up, for replaying recorded input.  Unlike
+    /// `connect()`, this keeps the id the event has.
+    pub(crate) fn replay(&self, event: GamepadEvent) {
+        self.with_state(|state| match event {
+            GamepadEvent::Connected(id) => {
+                state.next_id = state.next_id.max(id.0 + 1);
+                let _ = state.gamepads.entry(id).or_insert_with(|| ScriptedGamepad {
+                    name: "Replayed gamepad".to_owned(),
+                    ..ScriptedGamepad::default()
+                });
+            }
+            GamepadEvent::Disconnected(id) => {
+                let _ = state.gamepads.remove(&id);
+            }
+            GamepadEvent::ButtonPressed(id, button) => {
+                if let Some(gamepad) = state.gamepads.get_mut(&id) {
+                    let _ = gamepad.buttons.insert(button);
+                }
+            }
+            GamepadEvent::ButtonReleased(id, button) => {
+                if let Some(gamepad) = state.gamepads.get_mut(&id) {
+                    let _ = gamepad.buttons.remove(&button);
+                }
+            }
+            GamepadEvent::AxisChanged(id, axis, value) => {
+                if let Some(gamepad) = state.gamepads.get_mut(&id) {
+                    let _ = gamepad.axes.insert(axis, value);
+                }
+            }
+        })
+    }
+}
+
+impl GamepadContext for ScriptedGamepadContext {
+    fn next_event(&mut self) -> Option<GamepadEvent> {
+        self.with_state(|state| state.events.pop_front())
+    }
+
+    fn gamepad(&self, _id: GamepadId) -> Gamepad {
+        panic!("ScriptedGamepadContext has no gilrs gamepads, use the functions in ggez::input::gamepad instead")
+    }
+
+    fn gamepads(&self) -> Vec<GamepadInfo> {
+        self.with_state(|state| {
+            let mut gamepads: Vec<_> = state
+                .gamepads
+                .iter()
+                .map(|(&id, gamepad)| GamepadInfo {
+                    id,
+                    name: gamepad.name.clone(),
+                })
+                .collect();
+            gamepads.sort_by_key(|info| info.id.0);
+            gamepads
+        })
+    }
+
+    fn is_button_pressed(&self, id: GamepadId, button: Button) -> bool {
+        self.with_state(|state| {
+            state
+                .gamepads
+                .get(&id)
+                .map(|gamepad| gamepad.buttons.contains(&button))
+                .unwrap_or(false)
+        })
+    }
+
+    fn axis(&self, id: GamepadId, axis: Axis) -> f32 {
+        self.with_state(|state| {
+            state
+                .gamepads
+                .get(&id)
+                .and_then(|gamepad| gamepad.axes.get(&axis).cloned())
+                .unwrap_or(0.0)
+        })
+    }
+}
+
+/// Keeps track of which gamepad buttons went down or up
+/// during the current frame.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct GamepadTransitions {
+    just_pressed: HashSet<(GamepadId, Button)>,
+    just_released: HashSet<(GamepadId, Button)>,
+}
+
+impl GamepadTransitions {
+    pub(crate) fn set_button(&mut self, id: GamepadId, button: Button, pressed: bool) {
+        if pressed {
+            let _ = self.just_pressed.insert((id, button));
+        } else {
+            let _ = self.just_released.insert((id, button));
+        }
+    }
+
+    /// Forgets everything, for the start of a new frame.
+    pub(crate) fn reset(&mut self) {
+        self.just_pressed.clear();
+        self.just_released.clear();
+    }
 }
 
 /// Returns the `Gamepad` associated with an `id`.
+///
+/// This is the raw `gilrs` gamepad, so it's only available when
+/// using the default `gilrs`-based gamepad context.
 pub fn gamepad(ctx: &Context, id: GamepadId) -> Gamepad {
     ctx.gamepad_context.gamepad(id)
 }
 
-// Properties gamepads might want:
-// Number of buttons
-// Number of axes
-// Name/ID
-// Is it connected?  (For consoles?)
-// Whether or not they support vibration
+/// Lists all connected gamepads.
+pub fn list_gamepads(ctx: &Context) -> Vec<GamepadInfo> {
+    ctx.gamepad_context.gamepads()
+}
 
-/*
-/// Lists all gamepads.  With metainfo, maybe?
-pub fn list_gamepads() {
-    unimplemented!()
+/// Returns the state of the given axis on a gamepad, from -1.0 to 1.0.
+/// Returns 0.0 if the gamepad isn't connected.
+pub fn axis(ctx: &Context, id: GamepadId, axis: Axis) -> f32 {
+    ctx.gamepad_context.axis(id, axis)
 }
 
-/// Returns the state of the given axis on a gamepad.
-pub fn axis() {
-    unimplemented!()
+/// Returns whether the given button on a gamepad is held down.
+pub fn button_pressed(ctx: &Context, id: GamepadId, button: Button) -> bool {
+    ctx.gamepad_context.is_button_pressed(id, button)
 }
 
-/// Returns the state of the given button on a gamepad.
-pub fn button_pressed() {
-    unimplemented!()
+/// Returns whether the given button on a gamepad was
+/// pressed during the current frame.
+///
+/// [`event::run()`](../../event/fn.run.html) resets this at the
+/// start of every frame, see
+/// [`input::reset_transitions()`](../fn.reset_transitions.html).
+pub fn button_just_pressed(ctx: &Context, id: GamepadId, button: Button) -> bool {
+    ctx.gamepad_transitions.just_pressed.contains(&(id, button))
+}
+
+/// Returns whether the given button on a gamepad was
+/// released during the current frame.
+///
+/// [`event::run()`](../../event/fn.run.html) resets this at the
+/// start of every frame, see
+/// [`input::reset_transitions()`](../fn.reset_transitions.html).
+pub fn button_just_released(ctx: &Context, id: GamepadId, button: Button) -> bool {
+    ctx.gamepad_transitions
+        .just_released
+        .contains(&(id, button))
 }
-*/
 
 #[cfg(test)]
 mod tests {
@@ -104,4 +453,40 @@ mod tests {
     fn gilrs_init() {
         assert!(GilrsGamepadContext::new().is_ok());
     }
+
+    #[test]
+    fn scripted_gamepad() {
+        let script = ScriptedGamepadContext::new();
+        let mut ctx: Box<dyn GamepadContext> = Box::new(script.clone());
+        let id = script.connect("Test Pad");
+        script.press_button(id, Button::South);
+        script.move_axis(id, Axis::LeftStickX, 2.0);
+
+        assert_eq!(
+            ctx.gamepads(),
+            vec![GamepadInfo {
+                id,
+                name: "Test Pad".to_owned()
+            }]
+        );
+        assert!(ctx.is_button_pressed(id, Button::South));
+        assert!(!ctx.is_button_pressed(id, Button::North));
+        assert_eq!(ctx.axis(id, Axis::LeftStickX) as i32, 1);
+
+        assert_eq!(ctx.next_event(), Some(GamepadEvent::Connected(id)));
+        assert_eq!(
+            ctx.next_event(),
+            Some(GamepadEvent::ButtonPressed(id, Button::South))
+        );
+        assert_eq!(
+            ctx.next_event(),
+            Some(GamepadEvent::AxisChanged(id, Axis::LeftStickX, 1.0))
+        );
+        assert_eq!(ctx.next_event(), None);
+
+        script.disconnect(id);
+        assert!(ctx.gamepads().is_empty());
+        assert!(!ctx.is_button_pressed(id, Button::South));
+        assert_eq!(ctx.next_event(), Some(GamepadEvent::Disconnected(id)));
+    }
 }
@@ -109,6 +109,26 @@ impl From<ModifiersState> for KeyMods {
     }
 }
 
+// `bitflags` doesn't do serde for us, so we just store the bits.
+impl serde::Serialize for KeyMods {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        serializer.serialize_u8(self.bits())
+    }
+}
+
+impl<'de> serde::Deserialize<'de> for KeyMods {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        let bits = u8::deserialize(deserializer)?;
+        Ok(KeyMods::from_bits_truncate(bits))
+    }
+}
+
 /// Tracks held down keyboard keys, active keyboard modifiers,
 /// and figures out if the system is sending repeat keystrokes.
 #[derive(Clone, Debug)]
@@ -118,6 +138,9 @@ pub struct KeyboardContext {
     /// We COULD use a `Vec<bool>` but turning Rust enums to and from
     /// integers is unsafe and a set really is what we want anyway.
     pressed_keys_set: HashSet<KeyCode>,
+    /// Keys that went down or up since the start of the frame.
+    just_pressed_keys: HashSet<KeyCode>,
+    just_released_keys: HashSet<KeyCode>,
 
     // These two are necessary for tracking key-repeat.
     last_pressed: Option<KeyCode>,
@@ -130,6 +153,8 @@ impl KeyboardContext {
             active_modifiers: KeyMods::empty(),
             // We just use 256 as a number Big Enough For Keyboard Keys to try to avoid resizing.
             pressed_keys_set: HashSet::with_capacity(256),
+            just_pressed_keys: HashSet::new(),
+            just_released_keys: HashSet::new(),
             last_pressed: None,
             current_pressed: None,
         }
@@ -137,11 +162,16 @@ impl KeyboardContext {
 
     pub(crate) fn set_key(&mut self, key: KeyCode, pressed: bool) {
         if pressed {
-            let _ = self.pressed_keys_set.insert(key);
+            // Repeats don't count as being just pressed.
+            if self.pressed_keys_set.insert(key) {
+                let _ = self.just_pressed_keys.insert(key);
+            }
             self.last_pressed = self.current_pressed;
             self.current_pressed = Some(key);
         } else {
-            let _ = self.pressed_keys_set.remove(&key);
+            if self.pressed_keys_set.remove(&key) {
+                let _ = self.just_released_keys.insert(key);
+            }
             self.current_pressed = None;
         }
 
@@ -185,6 +215,21 @@ impl KeyboardContext {
         self.pressed_keys_set.contains(&key)
     }
 
+    pub(crate) fn is_key_just_pressed(&self, key: KeyCode) -> bool {
+        self.just_pressed_keys.contains(&key)
+    }
+
+    pub(crate) fn is_key_just_released(&self, key: KeyCode) -> bool {
+        self.just_released_keys.contains(&key)
+    }
+
+    /// Forgets which keys were just pressed or released,
+    /// for the start of a new frame.
+    pub(crate) fn reset_transitions(&mut self) {
+        self.just_pressed_keys.clear();
+        self.just_released_keys.clear();
+    }
+
     pub(crate) fn is_key_repeated(&self) -> bool {
         if self.last_pressed.is_some() {
             self.last_pressed == self.current_pressed
@@ -213,6 +258,25 @@ pub fn is_key_pressed(ctx: &Context, key: KeyCode) -> bool {
     ctx.keyboard_context.is_key_pressed(key)
 }
 
+/// Checks if a key was pressed during the current frame.
+/// Key repeats don't count.
+///
+/// [`event::run()`](../../event/fn.run.html) resets this at the
+/// start of every frame, see
+/// [`input::reset_transitions()`](../fn.reset_transitions.html).
+pub fn is_key_just_pressed(ctx: &Context, key: KeyCode) -> bool {
+    ctx.keyboard_context.is_key_just_pressed(key)
+}
+
+/// Checks if a key was released during the current frame.
+///
+/// [`event::run()`](../../event/fn.run.html) resets this at the
+/// start of every frame, see
+/// [`input::reset_transitions()`](../fn.reset_transitions.html).
+pub fn is_key_just_released(ctx: &Context, key: KeyCode) -> bool {
+    ctx.keyboard_context.is_key_just_released(key)
+}
+
 /// Checks if the last keystroke sent by the system is repeated,
 /// like when a key is held down for a period of time.
 pub fn is_key_repeated(ctx: &Context) -> bool {
@@ -238,6 +302,22 @@ pub fn active_mods(ctx: &Context) -> KeyMods {
 mod tests {
     use super::*;
 
+    #[test]
+    fn key_transitions() {
+        let mut keyboard = KeyboardContext::new();
+        keyboard.set_key(KeyCode::A, true);
+        assert!(keyboard.is_key_just_pressed(KeyCode::A));
+        keyboard.reset_transitions();
+        // A repeat isn't a new press.
+        keyboard.set_key(KeyCode::A, true);
+        assert!(!keyboard.is_key_just_pressed(KeyCode::A));
+        keyboard.set_key(KeyCode::A, false);
+        assert!(keyboard.is_key_just_released(KeyCode::A));
+        assert!(!keyboard.is_key_pressed(KeyCode::A));
+        keyboard.reset_transitions();
+        assert!(!keyboard.is_key_just_released(KeyCode::A));
+    }
+
     #[test]
     fn key_mod_conversions() {
         assert_eq!(
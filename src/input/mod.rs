@@ -1,4 +1,22 @@
 //! Input handling modules for keyboard, mouse and gamepad.
+pub mod bindings;
 pub mod gamepad;
 pub mod keyboard;
 pub mod mouse;
+pub mod replay;
+
+use crate::context::Context;
+
+/// Forgets which keys and buttons were just pressed or released, for
+/// the start of a new frame.  This is what makes functions like
+/// [`keyboard::is_key_just_pressed()`](keyboard/fn.is_key_just_pressed.html)
+/// only report a key press during the frame it happened on.
+///
+/// [`event::run()`](../event/fn.run.html) calls this for you at the
+/// start of every frame.  If you write your own event loop, call it
+/// right after `timer_context.tick()`, before handling any events.
+pub fn reset_transitions(ctx: &mut Context) {
+    ctx.keyboard_context.reset_transitions();
+    ctx.mouse_context.reset_transitions();
+    ctx.gamepad_transitions.reset();
+}
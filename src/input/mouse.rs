@@ -3,9 +3,8 @@
 use crate::context::Context;
 use crate::error::GameError;
 use crate::error::GameResult;
-use crate::graphics;
 use crate::graphics::Point2;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use winit::dpi;
 pub use winit::{MouseButton, MouseCursor};
 
@@ -15,6 +14,9 @@ pub struct MouseContext {
     last_position: Point2,
     last_delta: Point2,
     buttons_pressed: HashMap<MouseButton, bool>,
+    /// Buttons that went down or up since the start of the frame.
+    buttons_just_pressed: HashSet<MouseButton>,
+    buttons_just_released: HashSet<MouseButton>,
     cursor_type: MouseCursor,
     cursor_grabbed: bool,
     cursor_hidden: bool,
@@ -27,6 +29,8 @@ impl MouseContext {
             last_delta: Point2::origin(),
             cursor_type: MouseCursor::Default,
             buttons_pressed: HashMap::new(),
+            buttons_just_pressed: HashSet::new(),
+            buttons_just_released: HashSet::new(),
             cursor_grabbed: false,
             cursor_hidden: false,
         }
@@ -41,7 +45,22 @@ impl MouseContext {
     }
 
     pub(crate) fn set_button(&mut self, button: MouseButton, pressed: bool) {
-        let _ = self.buttons_pressed.insert(button, pressed);
+        let was_pressed = self
+            .buttons_pressed
+            .insert(button, pressed)
+            .unwrap_or(false);
+        if pressed && !was_pressed {
+            let _ = self.buttons_just_pressed.insert(button);
+        } else if !pressed && was_pressed {
+            let _ = self.buttons_just_released.insert(button);
+        }
+    }
+
+    /// Forgets which buttons were just pressed or released,
+    /// for the start of a new frame.
+    pub(crate) fn reset_transitions(&mut self) {
+        self.buttons_just_pressed.clear();
+        self.buttons_just_released.clear();
     }
 
     fn button_pressed(&self, button: MouseButton) -> bool {
@@ -63,7 +82,9 @@ pub fn cursor_type(ctx: &Context) -> MouseCursor {
 /// Modifies the mouse cursor type of the window.
 pub fn set_cursor_type(ctx: &mut Context, cursor_type: MouseCursor) {
     ctx.mouse_context.cursor_type = cursor_type;
-    graphics::window(ctx).set_cursor(cursor_type);
+    if let Some(window) = ctx.gfx_context.window.as_ref() {
+        window.set_cursor(cursor_type);
+    }
 }
 
 /// Get whether or not the mouse is grabbed (confined to the window)
@@ -74,9 +95,12 @@ pub fn cursor_grabbed(ctx: &Context) -> bool {
 /// Set whether or not the mouse is grabbed (confined to the window)
 pub fn set_cursor_grabbed(ctx: &mut Context, grabbed: bool) -> GameResult<()> {
     ctx.mouse_context.cursor_grabbed = grabbed;
-    graphics::window(ctx)
-        .grab_cursor(grabbed)
-        .map_err(|e| GameError::WindowError(e.to_string()))
+    match ctx.gfx_context.window.as_ref() {
+        Some(window) => window
+            .grab_cursor(grabbed)
+            .map_err(|e| GameError::WindowError(e.to_string())),
+        None => Ok(()),
+    }
 }
 
 /// Set whether or not the mouse is hidden (invisible)
@@ -87,7 +111,9 @@ pub fn cursor_hidden(ctx: &Context) -> bool {
 /// Set whether or not the mouse is hidden (invisible).
 pub fn set_cursor_hidden(ctx: &mut Context, hidden: bool) {
     ctx.mouse_context.cursor_hidden = hidden;
-    graphics::window(ctx).hide_cursor(hidden)
+    if let Some(window) = ctx.gfx_context.window.as_ref() {
+        window.hide_cursor(hidden);
+    }
 }
 
 /// Get the current position of the mouse cursor, in pixels.
@@ -106,10 +132,14 @@ where
     let mintpoint = point.into();
     ctx.mouse_context.last_position = Point2::from(mintpoint);
 
-    let window = &ctx.gfx_context.window;
+    let window = match ctx.gfx_context.window.as_ref() {
+        Some(window) => window,
+        // No real cursor to move when headless.
+        None => return Ok(()),
+    };
     let dpi = window.get_hidpi_factor();
 
-    graphics::window(ctx)
+    window
         .set_cursor_position(dpi::LogicalPosition {
             x: f64::from(mintpoint.x) / dpi,
             y: f64::from(mintpoint.y) / dpi,
@@ -126,3 +156,21 @@ pub fn delta(ctx: &Context) -> mint::Point2<f32> {
 pub fn button_pressed(ctx: &Context, button: MouseButton) -> bool {
     ctx.mouse_context.button_pressed(button)
 }
+
+/// Returns whether the given mouse button was pressed during the current frame.
+///
+/// [`event::run()`](../../event/fn.run.html) resets this at the
+/// start of every frame, see
+/// [`input::reset_transitions()`](../fn.reset_transitions.html).
+pub fn button_just_pressed(ctx: &Context, button: MouseButton) -> bool {
+    ctx.mouse_context.buttons_just_pressed.contains(&button)
+}
+
+/// Returns whether the given mouse button was released during the current frame.
+///
+/// [`event::run()`](../../event/fn.run.html) resets this at the
+/// start of every frame, see
+/// [`input::reset_transitions()`](../fn.reset_transitions.html).
+pub fn button_just_released(ctx: &Context, button: MouseButton) -> bool {
+    ctx.mouse_context.buttons_just_released.contains(&button)
+}
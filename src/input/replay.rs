@@ -0,0 +1,396 @@
+//! Recording and replaying input, so a play session (or a bug report)
+//! can be reproduced exactly.
+//!
+//! While recording, every event that [`event::run()`](../../event/fn.run.html)
+//! hands to your [`EventHandler`](../../event/trait.EventHandler.html) is
+//! saved along with the frame it happened on, as counted by
+//! [`timer::ticks()`](../../timer/fn.ticks.html).  A
+//! [`Recording`](struct.Recording.html) can be saved to and loaded from
+//! the [`filesystem`](../../filesystem/index.html), and
+//! [`replay::run()`](fn.run.html) plays it back into an `EventHandler` in
+//! place of a real `EventsLoop`.
+//!
+//! Replays are only as deterministic as your game is.  In particular
+//! you probably want to use the same
+//! [`timer::FixedClock`](../../timer/struct.FixedClock.html) when both
+//! recording and replaying, so that every frame takes the same amount
+//! of time, and seed any random number generators the same way.
+//!
+//! ```rust,no_run
+//! # use ggez::*;
+//! # use ggez::input::replay;
+//! # fn t<S: event::EventHandler>(ctx: &mut Context, events_loop: &mut event::EventsLoop, state: &mut S) -> GameResult {
+//! replay::start_recording(ctx);
+//! event::run(ctx, events_loop, state)?;
+//! if let Some(recording) = replay::stop_recording(ctx) {
+//!     recording.save(ctx, "/session.toml")?;
+//! }
+//!
+//! // ...later...
+//! let recording = replay::Recording::load(ctx, "/session.toml")?;
+//! replay::run(ctx, &recording, state)?;
+//! # Ok(())
+//! # }
+//! ```
+
+use std::io::{Read, Write};
+use std::mem;
+use std::path;
+
+use crate::context::Context;
+use crate::error::{GameError, GameResult};
+use crate::event::{self, Axis, Button, EventHandler, GamepadId, KeyCode, KeyMods, MouseButton};
+use crate::filesystem;
+use crate::graphics::Point2;
+use crate::input::gamepad::{GamepadEvent, ScriptedGamepadContext};
+use crate::timer;
+
+/// The version of the recording file format.  Recordings with
+/// a different version can't be loaded.
+pub const RECORDING_VERSION: u32 = 1;
+
+/// An event as dispatched to an
+/// [`EventHandler`](../../event/trait.EventHandler.html), with
+/// everything needed to dispatch it again.  Each variant matches
+/// one of the `EventHandler` callbacks.
+#[derive(Debug, Copy, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type")]
+pub enum InputEvent {
+    /// See `EventHandler::key_down_event()`.
+    KeyDown {
+        /// The key that was pressed.
+        keycode: KeyCode,
+        /// The modifiers held at the time.
+        keymods: KeyMods,
+        /// Whether this was a key repeat.
+        repeat: bool,
+    },
+    /// See `EventHandler::key_up_event()`.
+    KeyUp {
+        /// The key that was released.
+        keycode: KeyCode,
+        /// The modifiers held at the time.
+        keymods: KeyMods,
+    },
+    /// See `EventHandler::text_input_event()`.
+    TextInput {
+        /// The character received.
+        character: char,
+    },
+    /// See `EventHandler::mouse_button_down_event()`.
+    MouseButtonDown {
+        /// The button that was pressed.
+        button: MouseButton,
+        /// Mouse X position.
+        x: f32,
+        /// Mouse Y position.
+        y: f32,
+    },
+    /// See `EventHandler::mouse_button_up_event()`.
+    MouseButtonUp {
+        /// The button that was released.
+        button: MouseButton,
+        /// Mouse X position.
+        x: f32,
+        /// Mouse Y position.
+        y: f32,
+    },
+    /// See `EventHandler::mouse_motion_event()`.
+    MouseMotion {
+        /// Mouse X position.
+        x: f32,
+        /// Mouse Y position.
+        y: f32,
+        /// Relative X motion.
+        dx: f32,
+        /// Relative Y motion.
+        dy: f32,
+    },
+    /// See `EventHandler::mouse_wheel_event()`.
+    MouseWheel {
+        /// Horizontal scroll.
+        x: f32,
+        /// Vertical scroll.
+        y: f32,
+    },
+    /// See `EventHandler::gamepad_button_down_event()`.
+    GamepadButtonDown {
+        /// The button that was pressed.
+        button: Button,
+        /// Which gamepad it was.
+        id: GamepadId,
+    },
+    /// See `EventHandler::gamepad_button_up_event()`.
+    GamepadButtonUp {
+        /// The button that was released.
+        button: Button,
+        /// Which gamepad it was.
+        id: GamepadId,
+    },
+    /// See `EventHandler::gamepad_axis_event()`.
+    GamepadAxis {
+        /// The axis that moved.
+        axis: Axis,
+        /// Its new value.
+        value: f32,
+        /// Which gamepad it was.
+        id: GamepadId,
+    },
+    /// See `EventHandler::gamepad_connected_event()`.
+    GamepadConnected {
+        /// Which gamepad it was.
+        id: GamepadId,
+    },
+    /// See `EventHandler::gamepad_disconnected_event()`.
+    GamepadDisconnected {
+        /// Which gamepad it was.
+        id: GamepadId,
+    },
+    /// See `EventHandler::resize_event()`.
+    Resize {
+        /// The new width.
+        width: f32,
+        /// The new height.
+        height: f32,
+    },
+    /// See `EventHandler::focus_event()`.
+    Focus {
+        /// Whether focus was gained or lost.
+        gained: bool,
+    },
+    /// See `EventHandler::quit_event()`.
+    Quit,
+}
+
+impl InputEvent {
+    /// Updates the `Context`'s input state the way
+    /// [`Context::process_event()`](../../struct.Context.html#method.process_event)
+    /// would have for the original event, so functions like
+    /// `keyboard::is_key_pressed()` give the same answers during a replay.
+    /// Gamepad events go to the given gamepads, which stand in for the
+    /// real ones while replaying.
+    ///
+    /// Resize events don't resize the window, they only get passed
+    /// on to the `EventHandler`.
+    fn apply(&self, ctx: &mut Context, gamepads: &ScriptedGamepadContext) {
+        match *self {
+            InputEvent::KeyDown {
+                keycode, keymods, ..
+            } => {
+                ctx.keyboard_context.set_modifiers(keymods);
+                ctx.keyboard_context.set_key(keycode, true);
+            }
+            InputEvent::KeyUp { keycode, keymods } => {
+                ctx.keyboard_context.set_modifiers(keymods);
+                ctx.keyboard_context.set_key(keycode, false);
+            }
+            InputEvent::MouseButtonDown { button, x, y } => {
+                ctx.mouse_context.set_last_position(Point2::new(x, y));
+                ctx.mouse_context.set_button(button, true);
+            }
+            InputEvent::MouseButtonUp { button, x, y } => {
+                ctx.mouse_context.set_last_position(Point2::new(x, y));
+                ctx.mouse_context.set_button(button, false);
+            }
+            InputEvent::MouseMotion { x, y, dx, dy } => {
+                ctx.mouse_context.set_last_position(Point2::new(x, y));
+                ctx.mouse_context.set_last_delta(Point2::new(dx, dy));
+            }
+            InputEvent::GamepadButtonDown { button, id } => {
+                gamepads.replay(GamepadEvent::ButtonPressed(id, button));
+                ctx.gamepad_transitions.set_button(id, button, true);
+            }
+            InputEvent::GamepadButtonUp { button, id } => {
+                gamepads.replay(GamepadEvent::ButtonReleased(id, button));
+                ctx.gamepad_transitions.set_button(id, button, false);
+            }
+            InputEvent::GamepadAxis { axis, value, id } => {
+                gamepads.replay(GamepadEvent::AxisChanged(id, axis, value));
+            }
+            InputEvent::GamepadConnected { id } => {
+                gamepads.replay(GamepadEvent::Connected(id));
+            }
+            InputEvent::GamepadDisconnected { id } => {
+                gamepads.replay(GamepadEvent::Disconnected(id));
+            }
+            _ => (),
+        }
+    }
+}
+
+/// An `InputEvent` and the frame it happened on.
+#[derive(Debug, Copy, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RecordedEvent {
+    /// The frame the event was dispatched on, counted in
+    /// [`timer::ticks()`](../../timer/fn.ticks.html) since the
+    /// recording started.
+    pub tick: usize,
+    /// The event itself.
+    pub event: InputEvent,
+}
+
+/// A list of recorded events, in the order they happened.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Recording {
+    version: u32,
+    /// The recorded events.
+    pub events: Vec<RecordedEvent>,
+}
+
+impl Recording {
+    /// Creates a new, empty `Recording`.
+    pub fn new() -> Self {
+        Self {
+            version: RECORDING_VERSION,
+            events: Vec::new(),
+        }
+    }
+
+    /// Adds an event to the end of the recording.
+    pub fn push(&mut self, tick: usize, event: InputEvent) {
+        self.events.push(RecordedEvent { tick, event });
+    }
+
+    /// Loads a `Recording` from the given path.  The documentation
+    /// for the [`filesystem`](../../filesystem/index.html) module
+    /// explains how the path must be specified.
+    pub fn load<P: AsRef<path::Path>>(ctx: &mut Context, path: P) -> GameResult<Self> {
+        let mut s = String::new();
+        let mut file = filesystem::open(ctx, path)?;
+        let _ = file.read_to_string(&mut s)?;
+        let recording: Recording = toml::from_str(&s)?;
+        if recording.version != RECORDING_VERSION {
+            let msg = format!(
+                "Recording has version {}, expected version {}",
+                recording.version, RECORDING_VERSION
+            );
+            return Err(GameError::ResourceLoadError(msg));
+        }
+        Ok(recording)
+    }
+
+    /// Saves the `Recording` to the given path in the user directory.
+    pub fn save<P: AsRef<path::Path>>(&self, ctx: &mut Context, path: P) -> GameResult {
+        let s = toml::to_vec(self)?;
+        let mut file = filesystem::create(ctx, path)?;
+        file.write_all(&s)?;
+        Ok(())
+    }
+
+    /// The tick of the last event in the recording, if any.
+    pub fn last_tick(&self) -> Option<usize> {
+        self.events.last().map(|e| e.tick)
+    }
+}
+
+impl Default for Recording {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// A `Recording` in progress.  Ticks are counted from when
+/// the recording started, so a replay can start at any time.
+#[derive(Debug, Clone)]
+pub(crate) struct Recorder {
+    start_tick: usize,
+    /// Ticks recorded before the frame count last started over.
+    offset: usize,
+    recording: Recording,
+}
+
+impl Recorder {
+    pub(crate) fn record(&mut self, tick: usize, event: InputEvent) {
+        self.recording
+            .push(self.offset + tick - self.start_tick, event);
+    }
+
+    /// Called by `timer::set_clock()` before it starts the frame count
+    /// over from zero, with the count it had reached, so the recorded
+    /// ticks carry on going up from there.
+    pub(crate) fn clock_reset(&mut self, ticks: usize) {
+        self.offset += ticks - self.start_tick;
+        self.start_tick = 0;
+    }
+}
+
+/// Starts recording events dispatched by
+/// [`event::run()`](../../event/fn.run.html), throwing away any
+/// recording already in progress.
+pub fn start_recording(ctx: &mut Context) {
+    ctx.recorder = Some(Recorder {
+        start_tick: timer::ticks(ctx),
+        offset: 0,
+        recording: Recording::new(),
+    });
+}
+
+/// Stops recording and returns what was recorded, or `None`
+/// if we weren't recording.
+pub fn stop_recording(ctx: &mut Context) -> Option<Recording> {
+    ctx.recorder.take().map(|recorder| recorder.recording)
+}
+
+/// Returns whether events are currently being recorded.
+pub fn is_recording(ctx: &Context) -> bool {
+    ctx.recorder.is_some()
+}
+
+/// Plays a `Recording` back into the given `EventHandler`, running
+/// the same main loop as [`event::run()`](../../event/fn.run.html) but
+/// taking events from the recording instead of an `EventsLoop`.
+///
+/// Each frame, the events recorded on that tick are dispatched, then
+/// `update()` and `draw()` get called as usual.  Returns after the
+/// frame of the last recorded event, or sooner if something calls
+/// [`event::quit()`](../../event/fn.quit.html).
+///
+/// While replaying, the real gamepads are swapped out for the recorded
+/// ones, so the functions in [`gamepad`](../gamepad/index.html) answer
+/// as they did when recording.
+pub fn run<S>(ctx: &mut Context, recording: &Recording, state: &mut S) -> GameResult
+where
+    S: EventHandler,
+{
+    let gamepads = ScriptedGamepadContext::new();
+    let gamepad_context = mem::replace(&mut ctx.gamepad_context, Box::new(gamepads.clone()));
+    let result = replay(ctx, recording, state, &gamepads);
+    ctx.gamepad_context = gamepad_context;
+    result
+}
+
+fn replay<S>(
+    ctx: &mut Context,
+    recording: &Recording,
+    state: &mut S,
+    gamepads: &ScriptedGamepadContext,
+) -> GameResult
+where
+    S: EventHandler,
+{
+    let last_tick = match recording.last_tick() {
+        Some(tick) => tick,
+        None => return Ok(()),
+    };
+    // Frames are counted here rather than with `timer::ticks()`, which
+    // starts over if the game calls `timer::set_clock()`.
+    let mut tick = 0;
+    let mut events = recording.events.iter().peekable();
+    while ctx.continuing {
+        event::begin_frame(ctx, state);
+        tick += 1;
+        while let Some(recorded) = events.peek().cloned() {
+            if recorded.tick > tick {
+                break;
+            }
+            let _ = events.next();
+            recorded.event.apply(ctx, gamepads);
+            event::dispatch_event(ctx, state, recorded.event);
+        }
+        event::run_frame(ctx, state)?;
+        if tick >= last_tick {
+            break;
+        }
+    }
+    Ok(())
+}
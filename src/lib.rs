@@ -102,6 +102,7 @@ extern crate smart_default;
 pub extern crate mint;
 pub extern crate nalgebra;
 
+pub mod assets;
 pub mod audio;
 pub mod conf;
 mod context;
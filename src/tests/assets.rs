@@ -0,0 +1,103 @@
+use crate::assets::{self, Handle};
+use crate::graphics::{Color, Image, ImageFormat};
+use crate::tests;
+use crate::*;
+
+use std::io::Write;
+use std::path::PathBuf;
+
+#[test]
+fn handles_reload_changed_files() {
+    let c = &mut tests::make_headless_context(32.0, 32.0);
+    let path = "/asset_reload_test.png";
+    // Each version is a different size, so it counts as changed even
+    // where modification times are too coarse to tell them apart.
+    let save = |c: &mut Context, size: u16, color: Color| {
+        Image::solid(c, size, color)
+            .unwrap()
+            .encode(c, ImageFormat::Png, path)
+            .unwrap();
+    };
+    let pixel =
+        |c: &mut Context, image: &Handle<Image>| image.get().to_rgba8(c).unwrap()[..4].to_vec();
+
+    save(c, 4, Color::new(1.0, 0.0, 0.0, 1.0));
+    let image: Handle<Image> = Handle::new(c, path).unwrap();
+    let copy = image.clone();
+    assert_eq!(pixel(c, &image), [255, 0, 0, 255]);
+    assert!(assets::reload_changed_assets(c).is_empty());
+
+    save(c, 8, Color::new(0.0, 0.0, 1.0, 1.0));
+    assert_eq!(pixel(c, &image), [255, 0, 0, 255]);
+    assert_eq!(assets::reload_changed_assets(c), vec![PathBuf::from(path)]);
+    assert_eq!(pixel(c, &image), [0, 0, 255, 255]);
+    assert_eq!(pixel(c, &copy), [0, 0, 255, 255]);
+    assert!(assets::reload_changed_assets(c).is_empty());
+
+    // A broken file keeps the old asset.
+    {
+        let mut file = filesystem::create(c, path).unwrap();
+        file.write_all(b"not a png").unwrap();
+    }
+    assert!(assets::reload_changed_assets(c).is_empty());
+    assert_eq!(pixel(c, &image), [0, 0, 255, 255]);
+
+    // Nothing is reloaded once all the handles are gone.
+    drop(image);
+    drop(copy);
+    save(c, 16, Color::new(0.0, 1.0, 0.0, 1.0));
+    assert!(assets::reload_changed_assets(c).is_empty());
+
+    filesystem::delete(c, path).unwrap();
+}
+
+#[test]
+fn load_batch_in_background() {
+    use std::thread;
+    use std::time::Duration;
+
+    let c = &mut tests::make_headless_context(32.0, 32.0);
+    let batch = assets::LoadBatch::new()
+        .image("/player.png")
+        .font("/DejaVuSerif.ttf")
+        .sound("/pew.ogg")
+        .image("/no_such_image.png")
+        .image("/SIL Open Font License.txt");
+    let id = assets::load_batch(c, batch);
+    let mut progress = assets::batch_progress(c, id).unwrap();
+    assert_eq!(progress.total, 5);
+    for _ in 0..500 {
+        assets::update_loading(c);
+        progress = assets::batch_progress(c, id).unwrap();
+        if progress.is_done() {
+            break;
+        }
+        thread::sleep(Duration::from_millis(10));
+    }
+    assert_eq!(progress.loaded, 3);
+    assert_eq!(progress.failed, 2);
+    assert!(progress.is_done());
+
+    let loaded = assets::take_batch(c, id).unwrap();
+    assert!(assets::batch_progress(c, id).is_none());
+    assert!(assets::take_batch(c, id).is_none());
+    let image = loaded.image("/player.png").unwrap();
+    let expected = Image::new(c, "/player.png").unwrap();
+    assert_eq!(image.dimensions(), expected.dimensions());
+    assert_eq!(image.to_rgba8(c).unwrap(), expected.to_rgba8(c).unwrap());
+    assert!(loaded.font("/DejaVuSerif.ttf").is_some());
+    assert!(loaded.sound("/pew.ogg").unwrap().can_play());
+    let mut failed: Vec<_> = loaded
+        .errors()
+        .iter()
+        .map(|(path, _)| path.clone())
+        .collect();
+    failed.sort();
+    assert_eq!(
+        failed,
+        vec![
+            PathBuf::from("/SIL Open Font License.txt"),
+            PathBuf::from("/no_such_image.png")
+        ]
+    );
+}
@@ -126,3 +126,373 @@ fn volume_persists_after_play() {
         assert_eq!(sound.volume(), volume);
     }
 }
+
+#[test]
+fn streaming_source_plays() {
+    let (c, _e) = &mut tests::make_context();
+
+    for filename in &["/pew.ogg", "/pew.wav", "/pew.flac"] {
+        let mut sound = audio::StreamingSource::new(c, filename).unwrap();
+        assert!(!sound.playing());
+        sound.set_volume(0.5);
+        sound.play().unwrap();
+        assert!(sound.playing());
+        sound.stop();
+        assert!(!sound.playing());
+        assert_eq!(sound.volume(), 0.5);
+    }
+
+    assert!(audio::StreamingSource::new(c, "/does-not-exist.ogg").is_err());
+    assert!(audio::StreamingSource::new(c, "/player.png").is_err());
+}
+
+#[test]
+fn repeating_streaming_source_keeps_playing() {
+    use std::time::Duration;
+
+    let c = &mut tests::make_headless_context(32.0, 32.0);
+    c.audio_context = Box::new(audio::OfflineAudioContext::new(2, 44100));
+
+    let mut sound = audio::StreamingSource::new(c, "/pew.ogg").unwrap();
+    sound.set_repeat(true);
+    sound.play().unwrap();
+    // Longer than the sound itself.
+    c.audio_context.advance(Duration::from_millis(500));
+    assert!(sound.playing());
+}
+
+#[test]
+fn mixer_buses_fade() {
+    use approx::assert_relative_eq;
+    use std::time::Duration;
+
+    let mixer = audio::Mixer::new();
+    let music = mixer.bus(audio::MUSIC_BUS).unwrap();
+    assert_eq!(music.volume(), 1.0);
+
+    music.fade_to(0.0, Duration::from_secs(2));
+    assert!(music.fading());
+    mixer.update(Duration::from_millis(500));
+    assert_relative_eq!(music.volume(), 0.75);
+    mixer.update(Duration::from_secs(2));
+    assert_eq!(music.volume(), 0.0);
+    assert!(!music.fading());
+
+    // Setting the volume cancels a fade.
+    music.fade_to(1.0, Duration::from_secs(1));
+    music.set_volume(0.5);
+    assert!(!music.fading());
+    mixer.update(Duration::from_secs(1));
+    assert_eq!(music.volume(), 0.5);
+}
+
+#[test]
+fn mixer_adds_buses() {
+    let mut mixer = audio::Mixer::new();
+    assert!(mixer.bus("ambience").is_none());
+    mixer.add_bus("ambience").set_volume(0.5);
+    assert_eq!(mixer.bus("ambience").unwrap().volume(), 0.5);
+    // Adding it again gives back the same bus.
+    assert_eq!(mixer.add_bus("ambience").volume(), 0.5);
+
+    mixer.master().set_muted(true);
+    assert!(mixer.master().muted());
+    assert!(!mixer.bus("ambience").unwrap().muted());
+}
+
+#[test]
+fn paused_bus_pauses_its_sources() {
+    use std::time::Duration;
+
+    let c = &mut tests::make_headless_context(32.0, 32.0);
+    c.audio_context = Box::new(audio::OfflineAudioContext::new(2, 44100));
+    let sfx = audio::mixer(c).bus(audio::SFX_BUS).unwrap().clone();
+
+    let mut sound = audio::Source::new(c, "/pew.ogg").unwrap();
+    sound.set_bus(&sfx);
+    sound.set_query_interval(Duration::from_millis(10));
+    sfx.pause();
+    sound.play().unwrap();
+    // Longer than the sound itself.
+    c.audio_context.advance(Duration::from_millis(500));
+    assert!(!sound.stopped());
+    assert_eq!(sound.elapsed(), Duration::from_secs(0));
+
+    sfx.resume();
+    c.audio_context.advance(Duration::from_millis(500));
+    assert!(sound.stopped());
+}
+
+#[test]
+fn offline_audio_context_records_playback() {
+    use std::time::Duration;
+
+    let c = &mut tests::make_headless_context(32.0, 32.0);
+    let offline = audio::OfflineAudioContext::new(2, 44100);
+    let recording = offline.recording();
+    c.audio_context = Box::new(offline);
+
+    let mut sound = audio::Source::new(c, "/pew.wav").unwrap();
+    sound.set_query_interval(Duration::from_millis(10));
+    sound.play().unwrap();
+    c.audio_context.advance(Duration::from_millis(50));
+    assert!(sound.playing());
+    assert!(sound.elapsed() > Duration::from_millis(30));
+    assert!(sound.elapsed() <= Duration::from_millis(60));
+
+    // Nothing moves on while paused, once the sink has noticed.
+    sound.pause();
+    c.audio_context.advance(Duration::from_millis(10));
+    let elapsed = sound.elapsed();
+    c.audio_context.advance(Duration::from_millis(100));
+    assert_eq!(sound.elapsed(), elapsed);
+    sound.resume();
+
+    c.audio_context.advance(Duration::from_millis(200));
+    assert!(sound.stopped());
+
+    let samples = recording.samples();
+    assert_eq!(samples.len(), 360 * 44100 / 1000 * 2);
+    assert_eq!(recording.duration(), Duration::from_millis(360));
+    assert!(samples[..50 * 441 * 2 / 10].iter().any(|s| s.abs() > 0.01));
+    assert!(samples[samples.len() - 1000..].iter().all(|&s| s == 0.0));
+
+    let mut wav = Vec::new();
+    recording.write_wav(&mut wav).unwrap();
+    assert_eq!(&wav[..4], b"RIFF");
+    assert_eq!(wav.len(), 44 + samples.len() * 2);
+
+    recording.clear();
+    assert!(recording.samples().is_empty());
+}
+
+#[test]
+fn headless_context_plays_sound_without_a_device() {
+    use std::time::Duration;
+
+    let c = &mut tests::make_headless_context(32.0, 32.0);
+    let mut sound = audio::StreamingSource::new(c, "/pew.ogg").unwrap();
+    sound.play().unwrap();
+    assert!(sound.playing());
+    c.audio_context.advance(Duration::from_secs(1));
+    assert!(sound.stopped());
+}
+
+#[test]
+fn sources_know_their_duration() {
+    use std::time::Duration;
+
+    let c = &mut tests::make_headless_context(32.0, 32.0);
+    // pew.wav is 5899 frames at 44100 Hz.
+    let wav = audio::Source::new(c, "/pew.wav").unwrap();
+    let duration = wav.duration().unwrap();
+    assert!(duration >= Duration::from_millis(133));
+    assert!(duration <= Duration::from_millis(134));
+
+    // Ogg files don't say, so they get decoded to find out.
+    let ogg = audio::StreamingSource::new(c, "/pew.ogg").unwrap();
+    let duration = ogg.duration().unwrap();
+    assert!(duration > Duration::from_millis(100));
+    assert!(duration < Duration::from_millis(200));
+}
+
+#[test]
+fn seek_moves_playback() {
+    use std::time::Duration;
+
+    let c = &mut tests::make_headless_context(32.0, 32.0);
+    let offline = audio::OfflineAudioContext::new(2, 44100);
+    c.audio_context = Box::new(offline);
+
+    // Seeking a stopped source starts it from there when played.
+    let mut sound = audio::Source::new(c, "/pew.wav").unwrap();
+    sound.set_query_interval(Duration::from_millis(10));
+    sound.seek(Duration::from_millis(100)).unwrap();
+    sound.play().unwrap();
+    assert_eq!(sound.elapsed(), Duration::from_millis(100));
+    c.audio_context.advance(Duration::from_millis(50));
+    assert!(sound.stopped());
+
+    // Seeking a playing source carries on from there.
+    sound.play().unwrap();
+    assert_eq!(sound.elapsed(), Duration::from_millis(0));
+    c.audio_context.advance(Duration::from_millis(20));
+    sound.seek(Duration::from_millis(120)).unwrap();
+    assert!(sound.playing());
+    assert_eq!(sound.elapsed(), Duration::from_millis(120));
+    c.audio_context.advance(Duration::from_millis(30));
+    assert!(sound.stopped());
+}
+
+#[test]
+fn loop_region_repeats_part_of_the_sound() {
+    use std::time::Duration;
+
+    let c = &mut tests::make_headless_context(32.0, 32.0);
+    let offline = audio::OfflineAudioContext::new(1, 44100);
+    let recording = offline.recording();
+    c.audio_context = Box::new(offline);
+
+    let mut sound = audio::StreamingSource::new(c, "/pew.wav").unwrap();
+    assert!(sound
+        .set_loop_region(Duration::from_millis(50), Some(Duration::from_millis(20)))
+        .is_err());
+    sound
+        .set_loop_region(Duration::from_millis(50), Some(Duration::from_millis(100)))
+        .unwrap();
+    assert_eq!(
+        sound.loop_region(),
+        (Duration::from_millis(50), Some(Duration::from_millis(100)))
+    );
+    sound.set_repeat(true);
+    sound.play().unwrap();
+    c.audio_context.advance(Duration::from_millis(500));
+    assert!(sound.playing());
+
+    // After the first 100ms every 50ms is the same as the last.
+    let samples = recording.samples();
+    let period = 2205;
+    let loop_start = 4410;
+    assert!(samples[loop_start..loop_start + period]
+        .iter()
+        .any(|s| s.abs() > 0.01));
+    for i in loop_start..samples.len() - period {
+        assert_eq!(samples[i], samples[i + period]);
+    }
+}
+
+#[test]
+fn sources_fade() {
+    use approx::assert_relative_eq;
+    use std::time::Duration;
+
+    let c = &mut tests::make_headless_context(32.0, 32.0);
+    let offline = audio::OfflineAudioContext::new(1, 44100);
+    let recording = offline.recording();
+    c.audio_context = Box::new(offline);
+
+    let mut sound = audio::Source::new(c, "/pew.wav").unwrap();
+    sound.set_repeat(true);
+    sound.play().unwrap();
+    sound.fade_to(0.5, Duration::from_millis(100));
+    assert!(sound.fading());
+    c.audio_context.advance(Duration::from_millis(50));
+    assert_relative_eq!(sound.volume(), 0.75, epsilon = 0.01);
+    c.audio_context.advance(Duration::from_millis(100));
+    assert!(!sound.fading());
+    assert_eq!(sound.volume(), 0.5);
+
+    // Fading out stops the sound and puts the volume back.
+    sound.fade_out(Duration::from_millis(100));
+    c.audio_context.advance(Duration::from_millis(50));
+    assert!(sound.playing());
+    assert_relative_eq!(sound.volume(), 0.25, epsilon = 0.01);
+    c.audio_context.advance(Duration::from_millis(100));
+    assert!(sound.stopped());
+    assert!(!sound.fading());
+    assert_eq!(sound.volume(), 0.5);
+    let samples = recording.samples();
+    assert!(samples[samples.len() - 2000..].iter().all(|&s| s == 0.0));
+
+    // It plays at that volume the next time.
+    sound.play().unwrap();
+    c.audio_context.advance(Duration::from_millis(50));
+    assert!(sound.playing());
+    assert_eq!(sound.volume(), 0.5);
+}
+
+#[test]
+fn stopping_fades_out() {
+    use std::time::Duration;
+
+    let c = &mut tests::make_headless_context(32.0, 32.0);
+    let offline = audio::OfflineAudioContext::new(1, 44100);
+    let recording = offline.recording();
+    c.audio_context = Box::new(offline);
+    let loud = |samples: &[f32]| samples.iter().any(|s| s.abs() > 0.01);
+
+    let mut sound = audio::StreamingSource::new(c, "/pew.wav").unwrap();
+    sound.set_repeat(true);
+    sound.set_fade_out(Duration::from_millis(100));
+    sound.set_query_interval(Duration::from_millis(10));
+    sound.play().unwrap();
+    c.audio_context.advance(Duration::from_millis(50));
+    sound.stop();
+    assert!(sound.stopped());
+    assert_eq!(sound.volume(), 1.0);
+    c.audio_context.advance(Duration::from_millis(200));
+    // The sound fading out doesn't count as the source playing.
+    assert_eq!(sound.elapsed(), Duration::from_millis(0));
+    let samples = recording.samples();
+    // 50ms to 100ms is still fading out, 150ms on is silent.
+    assert!(loud(&samples[2205..4410]));
+    assert!(!loud(&samples[6615..]));
+
+    // So does dropping it.
+    recording.clear();
+    sound.play().unwrap();
+    c.audio_context.advance(Duration::from_millis(50));
+    drop(sound);
+    c.audio_context.advance(Duration::from_millis(200));
+    let samples = recording.samples();
+    assert!(loud(&samples[2205..4410]));
+    assert!(!loud(&samples[6615..]));
+}
+
+#[test]
+fn seeking_cuts_without_fading_out() {
+    use std::time::Duration;
+
+    let c = &mut tests::make_headless_context(32.0, 32.0);
+    let offline = audio::OfflineAudioContext::new(1, 44100);
+    let recording = offline.recording();
+    c.audio_context = Box::new(offline);
+
+    let mut sound = audio::StreamingSource::new(c, "/pew.wav").unwrap();
+    sound.set_repeat(true);
+    sound.set_fade_out(Duration::from_millis(100));
+    sound.set_query_interval(Duration::from_millis(10));
+    sound.play().unwrap();
+    c.audio_context.advance(Duration::from_millis(50));
+    sound.seek(Duration::from_millis(0)).unwrap();
+    assert_eq!(sound.elapsed(), Duration::from_millis(0));
+    c.audio_context.advance(Duration::from_millis(20));
+    // Only the new position counts towards the play time...
+    let elapsed = sound.elapsed();
+    assert!(elapsed >= Duration::from_millis(10) && elapsed <= Duration::from_millis(20));
+    // ...and it's the only thing heard, the old one isn't fading out under it.
+    let samples = recording.samples();
+    for (after, before) in samples[2205..2205 + 882].iter().zip(&samples[..882]) {
+        assert!((after - before).abs() < 0.0001);
+    }
+}
+
+#[test]
+fn crossfade_between_sources() {
+    use approx::assert_relative_eq;
+    use std::time::Duration;
+
+    let c = &mut tests::make_headless_context(32.0, 32.0);
+    c.audio_context = Box::new(audio::OfflineAudioContext::new(2, 44100));
+
+    let mut town = audio::Source::new(c, "/pew.wav").unwrap();
+    let mut battle = audio::StreamingSource::new(c, "/pew.ogg").unwrap();
+    town.set_repeat(true);
+    battle.set_repeat(true);
+    battle.set_volume(0.8);
+    town.play().unwrap();
+    c.audio_context.advance(Duration::from_millis(20));
+
+    audio::crossfade(&mut town, &mut battle, Duration::from_millis(100)).unwrap();
+    assert!(battle.playing());
+    c.audio_context.advance(Duration::from_millis(50));
+    assert!(town.playing());
+    assert_relative_eq!(town.volume(), 0.5, epsilon = 0.01);
+    assert_relative_eq!(battle.volume(), 0.4, epsilon = 0.01);
+
+    c.audio_context.advance(Duration::from_millis(100));
+    assert!(town.stopped());
+    assert_eq!(town.volume(), 1.0);
+    assert!(battle.playing());
+    assert_eq!(battle.volume(), 0.8);
+}
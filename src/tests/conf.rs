@@ -30,3 +30,32 @@ pub fn context_build_tests() {
         // Can't really test whether or not the window is resizable?
     }
 }
+
+#[test]
+fn headless_conf_needs_build_headless() {
+    let conf = conf::Conf::default().headless(true);
+    let cb = ContextBuilder::new("ggez_unit_tests", "ggez")
+        .conf(conf)
+        .with_conf_file(false);
+    match cb.build() {
+        Err(GameError::ConfigError(_)) => (),
+        other => panic!("Expected a ConfigError, got {:?}", other.map(|_| ())),
+    }
+}
+
+#[test]
+fn zero_update_rate_is_rejected() {
+    let bad_modes = vec![
+        conf::LoopMode::fixed(0),
+        conf::LoopMode::interpolated(0),
+        conf::LoopMode::fixed(60).max_updates_per_frame(0),
+    ];
+    for mode in bad_modes {
+        let conf = conf::Conf::default().loop_mode(mode);
+        let cb = ContextBuilder::new("ggez_unit_tests", "ggez").conf(conf);
+        match cb.build_headless() {
+            Err(GameError::ConfigError(_)) => (),
+            other => panic!("Expected a ConfigError, got {:?}", other.map(|_| ())),
+        }
+    }
+}
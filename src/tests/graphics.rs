@@ -1,5 +1,6 @@
 use crate::tests;
 use crate::*;
+use std::time::Duration;
 
 // use std::path;
 
@@ -79,6 +80,752 @@ fn sanity_check_window_sizes() {
     assert_eq!(h, size.1);
 }
 
+/// Make sure a headless context can draw and read back what it drew.
+#[test]
+fn headless_context_screenshot() {
+    let c = &mut tests::make_headless_context(64.0, 32.0);
+    assert_eq!(graphics::drawable_size(c), (64.0, 32.0));
+
+    graphics::clear(c, graphics::Color::new(1.0, 0.0, 0.0, 1.0));
+    graphics::present(c).unwrap();
+    let screenshot = graphics::screenshot(c).unwrap();
+    assert_eq!((screenshot.width(), screenshot.height()), (64, 32));
+    let pixels = screenshot.to_rgba8(c).unwrap();
+    assert_eq!(pixels.len(), 64 * 32 * 4);
+    for pixel in pixels.chunks(4) {
+        assert_eq!(pixel, &[255, 0, 0, 255]);
+    }
+
+    // Resizing a headless context resizes its offscreen target, and
+    // drawing carries on into the new one straight away.
+    graphics::set_drawable_size(c, 16.0, 16.0).unwrap();
+    assert_eq!(graphics::drawable_size(c), (16.0, 16.0));
+    graphics::clear(c, graphics::Color::new(0.0, 0.0, 1.0, 1.0));
+    graphics::present(c).unwrap();
+    let pixels = graphics::screenshot(c).unwrap().to_rgba8(c).unwrap();
+    assert_eq!(pixels.len(), 16 * 16 * 4);
+    for pixel in pixels.chunks(4) {
+        assert_eq!(pixel, &[0, 0, 255, 255]);
+    }
+}
+
+#[test]
+fn snapshot_compare_frame() {
+    use crate::graphics::snapshot::{self, Tolerance};
+    let c = &mut tests::make_headless_context(32.0, 32.0);
+    let reference = "/snapshot_tests/red.png";
+
+    graphics::clear(c, graphics::Color::new(1.0, 0.0, 0.0, 1.0));
+    graphics::present(c).unwrap();
+    snapshot::save_frame(c, reference).unwrap();
+    let comparison = snapshot::compare_frame(c, reference, Tolerance::exact()).unwrap();
+    assert!(comparison.matches());
+    assert_eq!(comparison.diff_path, None);
+
+    graphics::clear(c, graphics::Color::new(0.0, 0.0, 1.0, 1.0));
+    graphics::present(c).unwrap();
+    let comparison = snapshot::compare_frame(c, reference, Tolerance::new(8, 16)).unwrap();
+    assert!(!comparison.matches());
+    assert_eq!(comparison.differing_pixels, 32 * 32);
+    assert_eq!(comparison.max_channel_difference, 255);
+    let diff_path = comparison.diff_path.unwrap();
+    assert!(filesystem::is_file(c, &diff_path));
+
+    filesystem::delete(c, &diff_path).unwrap();
+    filesystem::delete(c, reference).unwrap();
+}
+
+#[test]
+fn snapshot_reference_from_elsewhere() {
+    use crate::graphics::snapshot::{self, Tolerance};
+    use crate::graphics::{DrawParam, Rect};
+    let c = &mut tests::make_target_context();
+    let reference = "/snapshot_tests/red_over_blue.png";
+
+    // Made without `save_frame()`, top row first like any other PNG.
+    let red_over_blue: Vec<u8> = (0..32 * 32)
+        .flat_map(|i| {
+            if i < 16 * 32 {
+                vec![255, 0, 0, 255]
+            } else {
+                vec![0, 0, 255, 255]
+            }
+        })
+        .collect();
+    filesystem::create_dir(c, "/snapshot_tests").unwrap();
+    {
+        let file = filesystem::create(c, reference).unwrap();
+        ::image::png::PNGEncoder::new(file)
+            .encode(&red_over_blue, 32, 32, ::image::ColorType::RGBA(8))
+            .unwrap();
+    }
+
+    let red = tests::rectangle(
+        c,
+        Rect::new(0.0, 0.0, 32.0, 16.0),
+        graphics::Color::new(1.0, 0.0, 0.0, 1.0),
+    );
+    graphics::clear(c, graphics::Color::new(0.0, 0.0, 1.0, 1.0));
+    graphics::draw(c, &red, DrawParam::new()).unwrap();
+    graphics::present(c).unwrap();
+    let comparison = snapshot::compare_frame(c, reference, Tolerance::exact()).unwrap();
+    assert!(comparison.matches());
+
+    filesystem::delete(c, reference).unwrap();
+}
+
+#[test]
+fn atlas_save_and_load() {
+    use crate::graphics::atlas::{Atlas, AtlasBuilder};
+    let c = &mut tests::make_headless_context(32.0, 32.0);
+    let packed = AtlasBuilder::new()
+        .padding(1)
+        .extrude(1)
+        .add_file(c, "player", "/player.png")
+        .unwrap()
+        .add_file(c, "tile", "/tile.png")
+        .unwrap()
+        .pack()
+        .unwrap();
+    packed
+        .save(c, "/atlas_test.png", "/atlas_test.toml")
+        .unwrap();
+
+    let atlas = Atlas::load(c, "/atlas_test.png", "/atlas_test.toml").unwrap();
+    assert_eq!(atlas.layout(), &packed.layout);
+    let tile = atlas.region("tile").unwrap();
+    let mut batch = atlas.sprite_batch();
+    let _ = batch.add(graphics::DrawParam::new().src(tile));
+    graphics::draw(c, &batch, graphics::DrawParam::new()).unwrap();
+    graphics::present(c).unwrap();
+
+    filesystem::delete(c, "/atlas_test.png").unwrap();
+    filesystem::delete(c, "/atlas_test.toml").unwrap();
+}
+
+fn animation_frames(n: usize) -> graphics::animation::Animation {
+    let rects = (0..n).map(|i| graphics::Rect::new(i as f32 / n as f32, 0.0, 1.0 / n as f32, 1.0));
+    graphics::animation::Animation::from_rects(rects, Duration::from_millis(100))
+}
+
+fn play_animation(sprite: &mut graphics::animation::AnimatedSprite, steps: usize) -> Vec<usize> {
+    (0..steps)
+        .map(|_| {
+            let _ = sprite.update(Duration::from_millis(100));
+            sprite.current_frame()
+        })
+        .collect()
+}
+
+#[test]
+fn animation_modes() {
+    use crate::graphics::animation::{AnimatedSprite, AnimationMode};
+    let c = &mut tests::make_headless_context(32.0, 32.0);
+    let image = graphics::Image::solid(c, 4, graphics::WHITE).unwrap();
+    let mut sprite = AnimatedSprite::new(image, animation_frames(3));
+    assert_eq!(play_animation(&mut sprite, 5), vec![1, 2, 0, 1, 2]);
+
+    sprite.set_animation(animation_frames(3).mode(AnimationMode::PingPong));
+    assert_eq!(play_animation(&mut sprite, 6), vec![1, 2, 1, 0, 1, 2]);
+
+    sprite.set_animation(animation_frames(3).mode(AnimationMode::Once));
+    assert_eq!(play_animation(&mut sprite, 4), vec![1, 2, 2, 2]);
+    assert!(sprite.is_finished());
+}
+
+#[test]
+fn animation_frames_out_of_range() {
+    let animation = animation_frames(3).frames(1..10);
+    assert_eq!(animation.total_duration(), Duration::from_millis(200));
+    let animation = animation_frames(3).frames(5..10);
+    assert_eq!(animation.total_duration(), Duration::from_millis(0));
+}
+
+#[test]
+fn animation_events_and_drawing() {
+    use crate::graphics::animation::{AnimatedSprite, AnimationEvent, AnimationMode};
+    let c = &mut tests::make_headless_context(32.0, 32.0);
+    let image = graphics::Image::new(c, "/player.png").unwrap();
+    let animation = animation_frames(3)
+        .mode(AnimationMode::Once)
+        .frame_duration(1, Duration::from_millis(50));
+    let mut sprite = AnimatedSprite::new(image, animation);
+    assert_eq!(sprite.update(Duration::from_millis(99)), vec![]);
+    assert_eq!(
+        sprite.update(Duration::from_millis(51)),
+        vec![AnimationEvent::Frame(1), AnimationEvent::Frame(2)]
+    );
+    assert_eq!(
+        sprite.update(Duration::from_millis(100)),
+        vec![AnimationEvent::Finished]
+    );
+    assert_eq!(
+        sprite.src(),
+        graphics::Rect::new(2.0 / 3.0, 0.0, 1.0 / 3.0, 1.0)
+    );
+
+    let mut batch = graphics::spritebatch::SpriteBatch::new(sprite.image().clone());
+    let _ = sprite.add_to(&mut batch, graphics::DrawParam::new());
+    graphics::draw(c, &batch, graphics::DrawParam::new()).unwrap();
+    graphics::draw(c, &sprite, graphics::DrawParam::new()).unwrap();
+    graphics::present(c).unwrap();
+}
+
+#[test]
+fn scissor_clips_drawing() {
+    use crate::graphics::{DrawParam, Rect};
+    let c = &mut tests::make_target_context();
+    // Scissor rects are in screen coordinates, so this makes sure
+    // they get mapped onto pixels.
+    graphics::set_screen_coordinates(c, Rect::new(0.0, 0.0, 2.0, 2.0)).unwrap();
+    let square = tests::rectangle(c, Rect::new(0.0, 0.0, 2.0, 2.0), graphics::WHITE);
+    let canvas = graphics::Canvas::new(c, 32, 32, conf::NumSamples::One).unwrap();
+
+    let drawn = tests::draw_to_each_target(c, &canvas, |c| {
+        graphics::clear(c, graphics::BLACK);
+        graphics::push_scissor(c, Rect::new(0.0, 0.0, 1.0, 2.0));
+        graphics::push_scissor(c, Rect::new(0.0, 0.0, 2.0, 1.0));
+        assert_eq!(graphics::scissor(c), Some(Rect::new(0.0, 0.0, 1.0, 1.0)));
+        graphics::draw(c, &square, DrawParam::new()).unwrap();
+        graphics::pop_scissor(c);
+        graphics::pop_scissor(c);
+        assert_eq!(graphics::scissor(c), None);
+    });
+    for rgba in drawn {
+        assert_eq!(tests::target_pixel(&rgba, 8, 8), [255, 255, 255, 255]);
+        assert_eq!(tests::target_pixel(&rgba, 24, 8), [0, 0, 0, 255]);
+        assert_eq!(tests::target_pixel(&rgba, 8, 24), [0, 0, 0, 255]);
+    }
+}
+
+#[test]
+fn stencil_masking() {
+    use crate::graphics::{CanvasBuilder, DrawParam, Rect, StencilMode};
+    let c = &mut tests::make_target_context();
+    let left_half = tests::rectangle(c, Rect::new(0.0, 0.0, 16.0, 32.0), graphics::WHITE);
+    let everything = Rect::new(0.0, 0.0, 32.0, 32.0);
+    let red = tests::rectangle(c, everything, graphics::Color::new(1.0, 0.0, 0.0, 1.0));
+    let blue = tests::rectangle(c, everything, graphics::Color::new(0.0, 0.0, 1.0, 1.0));
+    let canvas = CanvasBuilder::new(32, 32).stencil(true).build(c).unwrap();
+
+    let drawn = tests::draw_to_each_target(c, &canvas, |c| {
+        graphics::clear(c, graphics::BLACK);
+        graphics::clear_stencil(c).unwrap();
+        graphics::set_stencil_mode(c, StencilMode::Write(1));
+        graphics::draw(c, &left_half, DrawParam::new()).unwrap();
+        graphics::set_stencil_mode(c, StencilMode::Inside(1));
+        graphics::draw(c, &red, DrawParam::new()).unwrap();
+        graphics::set_stencil_mode(c, StencilMode::Outside(1));
+        graphics::draw(c, &blue, DrawParam::new()).unwrap();
+        graphics::set_stencil_mode(c, StencilMode::Off);
+    });
+    for rgba in drawn {
+        // The mask itself never shows up, only what was drawn through it.
+        assert_eq!(tests::target_pixel(&rgba, 8, 8), [255, 0, 0, 255]);
+        assert_eq!(tests::target_pixel(&rgba, 24, 8), [0, 0, 255, 255]);
+    }
+
+    let plain = graphics::Canvas::new(c, 32, 32, conf::NumSamples::One).unwrap();
+    graphics::set_canvas(c, Some(&plain));
+    assert!(graphics::clear_stencil(c).is_err());
+    graphics::set_stencil_mode(c, StencilMode::Inside(1));
+    assert!(graphics::draw(c, &red, DrawParam::new()).is_err());
+    graphics::set_stencil_mode(c, StencilMode::Off);
+    graphics::draw(c, &red, DrawParam::new()).unwrap();
+    graphics::set_canvas(c, None);
+}
+
+#[test]
+fn canvas_color_attachments() {
+    use crate::graphics::{CanvasBuilder, DrawParam, Rect};
+    let pixel_source = b"#version 150 core
+in vec4 v_Color;
+out vec4 Target0;
+out vec4 Target1;
+
+void main() {
+    Target0 = v_Color;
+    Target1 = vec4(1.0 - v_Color.rgb, 1.0);
+}
+";
+    let c = &mut tests::make_target_context();
+    let shader = tests::unused_shader(c, tests::BASIC_VERTEX_SHADER, pixel_source);
+    let red = tests::rectangle(
+        c,
+        Rect::new(0.0, 0.0, 32.0, 32.0),
+        graphics::Color::new(1.0, 0.0, 0.0, 1.0),
+    );
+    let canvas = CanvasBuilder::new(32, 32)
+        .color_attachments(2)
+        .build(c)
+        .unwrap();
+    assert_eq!(canvas.attachment_count(), 2);
+    assert!(canvas.attachment(2).is_none());
+    assert!(canvas.depth_image().is_none());
+
+    graphics::set_canvas(c, Some(&canvas));
+    graphics::clear(c, graphics::BLACK);
+    {
+        let _lock = graphics::use_shader(c, &shader);
+        graphics::draw(c, &red, DrawParam::new()).unwrap();
+        // The screen only has one color attachment to write to.
+        graphics::set_canvas(c, None);
+        assert!(graphics::draw(c, &red, DrawParam::new()).is_err());
+    }
+    graphics::present(c).unwrap();
+
+    let first = canvas.attachment(0).unwrap().to_rgba8(c).unwrap();
+    let second = canvas.attachment(1).unwrap().to_rgba8(c).unwrap();
+    assert_eq!(tests::target_pixel(&first, 8, 8), [255, 0, 0, 255]);
+    assert_eq!(tests::target_pixel(&second, 8, 8), [0, 255, 255, 255]);
+
+    assert!(CanvasBuilder::new(32, 32)
+        .color_attachments(graphics::MAX_COLOR_ATTACHMENTS + 1)
+        .build(c)
+        .is_err());
+}
+
+#[test]
+fn depth_testing() {
+    use crate::graphics::{CanvasBuilder, DepthMode, DrawParam, Rect};
+    use crate::nalgebra::{Matrix4, Vector3};
+    let c = &mut tests::make_target_context();
+    let red = tests::rectangle(
+        c,
+        Rect::new(0.0, 0.0, 16.0, 32.0),
+        graphics::Color::new(1.0, 0.0, 0.0, 1.0),
+    );
+    let blue = tests::rectangle(
+        c,
+        Rect::new(0.0, 0.0, 32.0, 32.0),
+        graphics::Color::new(0.0, 0.0, 1.0, 1.0),
+    );
+    let canvas = CanvasBuilder::new(32, 32).depth(true).build(c).unwrap();
+    assert!(canvas.depth_image().is_some());
+
+    let drawn = tests::draw_to_each_target(c, &canvas, |c| {
+        graphics::clear(c, graphics::BLACK);
+        graphics::clear_depth(c).unwrap();
+        graphics::set_depth_mode(c, DepthMode::TestAndWrite);
+        // Red gets drawn first, but closer, so blue goes behind it.
+        let closer = Matrix4::new_translation(&Vector3::new(0.0, 0.0, -0.5));
+        graphics::push_transform(c, Some(closer));
+        graphics::apply_transformations(c).unwrap();
+        graphics::draw(c, &red, DrawParam::new()).unwrap();
+        graphics::pop_transform(c);
+        graphics::apply_transformations(c).unwrap();
+        graphics::draw(c, &blue, DrawParam::new()).unwrap();
+        graphics::set_depth_mode(c, DepthMode::Off);
+    });
+    for rgba in drawn {
+        assert_eq!(tests::target_pixel(&rgba, 8, 8), [255, 0, 0, 255]);
+        assert_eq!(tests::target_pixel(&rgba, 24, 8), [0, 0, 255, 255]);
+    }
+
+    let plain = graphics::Canvas::new(c, 32, 32, conf::NumSamples::One).unwrap();
+    graphics::set_canvas(c, Some(&plain));
+    assert!(graphics::clear_depth(c).is_err());
+    graphics::set_depth_mode(c, DepthMode::Test);
+    assert!(graphics::draw(c, &red, DrawParam::new()).is_err());
+    graphics::set_depth_mode(c, DepthMode::Off);
+    graphics::set_canvas(c, None);
+}
+
+#[test]
+fn post_process_chain() {
+    use crate::graphics::postprocess::{PassInput, PostProcess};
+    use crate::graphics::{DrawParam, Rect, Shader};
+    gfx_defines! {
+        constant Strength {
+            strength: f32 = "u_Strength",
+        }
+    }
+    let invert_source = b"#version 150 core
+uniform sampler2D t_Texture;
+in vec2 v_Uv;
+in vec4 v_Color;
+out vec4 Target0;
+
+layout (std140) uniform Strength {
+    float u_Strength;
+};
+
+void main() {
+    vec4 color = texture(t_Texture, v_Uv) * v_Color;
+    Target0 = vec4(mix(color.rgb, 1.0 - color.rgb, u_Strength), color.a);
+}
+";
+    let c = &mut tests::make_target_context();
+    let invert = Shader::from_u8(
+        c,
+        tests::BASIC_VERTEX_SHADER,
+        invert_source,
+        Strength { strength: 1.0 },
+        "Strength",
+        None,
+    )
+    .unwrap();
+    let red = tests::rectangle(
+        c,
+        Rect::new(0.0, 0.0, 16.0, 32.0),
+        graphics::Color::new(1.0, 0.0, 0.0, 1.0),
+    );
+    let mut post = PostProcess::new(c).unwrap();
+    let first = post.add_pass(invert.clone(), Strength { strength: 1.0 });
+    let second = post.add_pass(invert, Strength { strength: 1.0 });
+    assert_eq!(post.pass_count(), 2);
+
+    let run = |c: &mut Context, post: &PostProcess| {
+        post.begin(c);
+        graphics::clear(c, graphics::BLACK);
+        graphics::draw(c, &red, DrawParam::new()).unwrap();
+        post.finish(c).unwrap();
+        graphics::present(c).unwrap();
+        let rgba = tests::read_target(c, None);
+        (
+            tests::target_pixel(&rgba, 8, 8),
+            tests::target_pixel(&rgba, 24, 8),
+        )
+    };
+
+    // Inverting twice gets the scene back.
+    let (left, right) = run(c, &post);
+    assert_eq!(left, [255, 0, 0, 255]);
+    assert_eq!(right, [0, 0, 0, 255]);
+
+    // Inverting the scene again instead of the first pass's result.
+    post.set_input(c, second, PassInput::Scene).unwrap();
+    let (left, right) = run(c, &post);
+    assert_eq!(left, [0, 255, 255, 255]);
+    assert_eq!(right, [255, 255, 255, 255]);
+
+    post.set_consts(second, Strength { strength: 0.0 }).unwrap();
+    post.set_input(c, second, PassInput::Pass(first)).unwrap();
+    let (left, right) = run(c, &post);
+    assert_eq!(left, [0, 255, 255, 255]);
+    assert_eq!(right, [255, 255, 255, 255]);
+    let kept = post.result(first).unwrap().to_rgba8(c).unwrap();
+    assert_eq!(tests::target_pixel(&kept, 8, 8), [0, 255, 255, 255]);
+    assert!(post.result(second).is_none());
+
+    assert!(post.set_input(c, first, PassInput::Pass(second)).is_err());
+    assert!(post.set_consts(2, Strength { strength: 0.0 }).is_err());
+}
+
+#[test]
+fn shader_extra_samplers() {
+    use crate::graphics::{DrawParam, Image, Rect};
+    let pixel_source = b"#version 150 core
+uniform sampler2D t_Texture;
+uniform sampler2D t_Extra;
+in vec2 v_Uv;
+in vec4 v_Color;
+out vec4 Target0;
+
+void main() {
+    Target0 = texture(t_Texture, v_Uv) * texture(t_Extra, v_Uv) * v_Color;
+}
+";
+    let c = &mut tests::make_target_context();
+    let shader = tests::unused_shader(c, tests::BASIC_VERTEX_SHADER, pixel_source);
+    let white = tests::rectangle(c, Rect::new(0.0, 0.0, 32.0, 32.0), graphics::WHITE);
+    let blue = Image::solid(c, 4, graphics::Color::new(0.0, 0.0, 1.0, 1.0)).unwrap();
+    let draw = |c: &mut Context| {
+        graphics::clear(c, graphics::BLACK);
+        {
+            let _lock = graphics::use_shader(c, &shader);
+            graphics::draw(c, &white, DrawParam::new()).unwrap();
+        }
+        graphics::present(c).unwrap();
+        let rgba = tests::read_target(c, None);
+        tests::target_pixel(&rgba, 16, 16)
+    };
+
+    // With nothing bound, `t_Extra` samples the mesh's own white image.
+    assert_eq!(draw(c), [255, 255, 255, 255]);
+    shader.set_image(c, "t_Extra", &blue).unwrap();
+    assert_eq!(draw(c), [0, 0, 255, 255]);
+    // The binding sticks around for later draws.
+    assert_eq!(draw(c), [0, 0, 255, 255]);
+    shader.clear_image(c, "t_Extra").unwrap();
+    assert_eq!(draw(c), [255, 255, 255, 255]);
+
+    assert!(shader.set_image(c, "t_Missing", &blue).is_err());
+    assert!(shader.set_image(c, "t_Texture", &blue).is_err());
+}
+
+#[test]
+fn custom_vertex_attributes() {
+    use crate::graphics::spritebatch::SpriteBatch;
+    use crate::graphics::{AttributeFormat, DrawParam, Image, Mesh, Shader, Vertex};
+    gfx_defines! {
+        vertex Tint {
+            tint: [f32; 4] = "a_Tint",
+        }
+    }
+    let vertex_source = b"#version 150 core
+
+in vec2 a_Pos;
+in vec2 a_Uv;
+in vec4 a_VertColor;
+in vec4 a_Tint;
+
+in vec4 a_Src;
+in vec4 a_TCol1;
+in vec4 a_TCol2;
+in vec4 a_TCol3;
+in vec4 a_TCol4;
+in vec4 a_Color;
+
+layout (std140) uniform Globals {
+    mat4 u_MVP;
+};
+
+out vec2 v_Uv;
+out vec4 v_Color;
+
+void main() {
+    v_Uv = a_Uv * a_Src.zw + a_Src.xy;
+    v_Color = a_Color * a_VertColor * a_Tint;
+    mat4 instance_transform = mat4(a_TCol1, a_TCol2, a_TCol3, a_TCol4);
+    vec4 position = instance_transform * vec4(a_Pos, 0.0, 1.0);
+
+    gl_Position = u_MVP * position;
+}
+";
+    let c = &mut tests::make_target_context();
+    let new_shader = |c: &mut Context| {
+        tests::unused_shader(
+            c,
+            vertex_source,
+            include_bytes!("../graphics/shader/basic_150.glslf"),
+        )
+    };
+    fn draw<D: graphics::Drawable>(
+        c: &mut Context,
+        shader: &Shader<tests::Unused>,
+        drawable: &D,
+    ) -> GameResult<Vec<u8>> {
+        graphics::clear(c, graphics::BLACK);
+        let result = {
+            let _lock = graphics::use_shader(c, shader);
+            graphics::draw(c, drawable, DrawParam::new())
+        };
+        graphics::present(c).unwrap();
+        let rgba = tests::read_target(c, None);
+        result.map(|()| tests::target_pixel(&rgba, 16, 16))
+    }
+    let red = Tint {
+        tint: [1.0, 0.0, 0.0, 1.0],
+    };
+    let green = Tint {
+        tint: [0.0, 1.0, 0.0, 1.0],
+    };
+
+    // Per-vertex attributes on a mesh.
+    let vertex_shader = new_shader(c);
+    vertex_shader
+        .set_vertex_attributes(c, Some(AttributeFormat::of::<Tint>()))
+        .unwrap();
+    let vertex = |x, y| Vertex {
+        pos: [x, y],
+        uv: [0.0, 0.0],
+        color: [1.0, 1.0, 1.0, 1.0],
+    };
+    let verts = [
+        vertex(0.0, 0.0),
+        vertex(32.0, 0.0),
+        vertex(32.0, 32.0),
+        vertex(0.0, 32.0),
+    ];
+    let mut mesh = Mesh::from_raw(c, &verts, &[0, 1, 2, 0, 2, 3], None).unwrap();
+    // The shader reads attributes the mesh doesn't have yet.
+    assert!(draw(c, &vertex_shader, &mesh).is_err());
+    assert!(mesh.set_vertex_attributes(c, &[red; 3]).is_err());
+    mesh.set_vertex_attributes(c, &[red; 4]).unwrap();
+    assert_eq!(draw(c, &vertex_shader, &mesh).unwrap(), [255, 0, 0, 255]);
+    mesh.set_vertex_attributes(c, &[green; 4]).unwrap();
+    assert_eq!(draw(c, &vertex_shader, &mesh).unwrap(), [0, 255, 0, 255]);
+    mesh.clear_vertex_attributes();
+    assert!(draw(c, &vertex_shader, &mesh).is_err());
+
+    // Per-instance attributes on a sprite batch.
+    let instance_shader = new_shader(c);
+    instance_shader
+        .set_instance_attributes(c, Some(AttributeFormat::of::<Tint>()))
+        .unwrap();
+    let image = Image::solid(c, 32, graphics::WHITE).unwrap();
+    let mut batch = SpriteBatch::new(image);
+    let _ = batch.add(DrawParam::new());
+    assert!(draw(c, &instance_shader, &batch).is_err());
+    batch.set_instance_attributes(c, &[green]);
+    assert_eq!(draw(c, &instance_shader, &batch).unwrap(), [0, 255, 0, 255]);
+    // More sprites than attributes is an error.
+    let _ = batch.add(DrawParam::new());
+    assert!(draw(c, &instance_shader, &batch).is_err());
+    batch.set_instance_attributes(c, &[green, red]);
+    assert_eq!(draw(c, &instance_shader, &batch).unwrap(), [255, 0, 0, 255]);
+
+    // Shaders that don't read the attributes don't mind them.
+    let plain_shader = new_shader(c);
+    assert_eq!(draw(c, &plain_shader, &mesh).unwrap(), [255, 255, 255, 255]);
+}
+
+#[test]
+fn shader_hot_reload() {
+    use crate::graphics::{DrawParam, Rect, Shader};
+    use std::cell::RefCell;
+    use std::io::Write;
+    use std::rc::Rc;
+    let pixel_source = |color: &str| {
+        format!(
+            "#version 150 core
+in vec2 v_Uv;
+in vec4 v_Color;
+out vec4 Target0;
+
+void main() {{
+    Target0 = {};
+}}
+",
+            color
+        )
+    };
+    let write = |c: &mut Context, path: &str, source: &[u8]| {
+        let mut file = filesystem::create(c, path).unwrap();
+        file.write_all(source).unwrap();
+    };
+    let vertex_path = "/shader_hot_reload.glslv";
+    let pixel_path = "/shader_hot_reload.glslf";
+    // Each version is a different length, so it counts as changed even
+    // where modification times are too coarse to tell them apart.
+    let write_pixel = |c: &mut Context, color: &str| {
+        write(c, pixel_path, pixel_source(color).as_bytes());
+    };
+    let c = &mut tests::make_target_context();
+    write(c, vertex_path, tests::BASIC_VERTEX_SHADER);
+    write_pixel(c, "vec4(1.0, 0.0, 0.0, 1.0)");
+    let shader = Shader::new(
+        c,
+        vertex_path,
+        pixel_path,
+        tests::Unused { unused: 0.0 },
+        "Unused",
+        None,
+    )
+    .unwrap();
+    let white = tests::rectangle(c, Rect::new(0.0, 0.0, 32.0, 32.0), graphics::WHITE);
+    let draw = |c: &mut Context| {
+        graphics::reload_changed_shaders(c);
+        graphics::clear(c, graphics::BLACK);
+        {
+            let _lock = graphics::use_shader(c, &shader);
+            graphics::draw(c, &white, DrawParam::new()).unwrap();
+        }
+        graphics::present(c).unwrap();
+        let rgba = tests::read_target(c, None);
+        tests::target_pixel(&rgba, 16, 16)
+    };
+    let errors = Rc::new(RefCell::new(vec![]));
+    assert_eq!(draw(c), [255, 0, 0, 255]);
+
+    // Nothing happens until we ask for it.
+    write_pixel(c, "vec4(0.0, 1.00, 0.0, 1.0)");
+    assert_eq!(draw(c), [255, 0, 0, 255]);
+    let errors_handle = errors.clone();
+    graphics::watch_shaders(c, move |id, e| errors_handle.borrow_mut().push((id, e)));
+    assert_eq!(draw(c), [0, 255, 0, 255]);
+
+    // A broken shader keeps the last one that compiled.
+    write_pixel(c, "not_a_color");
+    assert_eq!(draw(c), [0, 255, 0, 255]);
+    {
+        let errors = errors.borrow();
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].0, shader.shader_id());
+        match errors[0].1 {
+            GameError::ShaderProgramError(_) => (),
+            ref e => panic!("Unexpected error: {:?}", e),
+        }
+    }
+    write_pixel(c, "vec4(0.0, 0.0, 1.000, 1.0)");
+    assert_eq!(draw(c), [0, 0, 255, 255]);
+    assert_eq!(errors.borrow().len(), 1);
+
+    graphics::unwatch_shaders(c);
+    filesystem::delete(c, vertex_path).unwrap();
+    filesystem::delete(c, pixel_path).unwrap();
+}
+
+#[test]
+fn camera_2d() {
+    use crate::graphics::{Camera2D, DrawParam, Rect};
+    use approx::assert_relative_eq;
+    use std::f32::consts::PI;
+
+    let c = &mut tests::make_target_context();
+    let camera = Camera2D::new(c).position([0.0, 0.0]).zoom(2.0);
+    assert_eq!(camera.viewport, Rect::new(0.0, 0.0, 32.0, 32.0));
+    let screen = camera.world_to_screen([4.0, -2.0]);
+    assert_relative_eq!(screen.x, 24.0);
+    assert_relative_eq!(screen.y, 12.0);
+    let world = camera.screen_to_world(screen);
+    assert_relative_eq!(world.x, 4.0);
+    assert_relative_eq!(world.y, -2.0);
+
+    // The matrix agrees with the conversions, rotation included.
+    let rotated = camera.rotation(PI / 2.0);
+    let screen = rotated.world_to_screen([1.0, 0.0]);
+    assert_relative_eq!(screen.x, 16.0, epsilon = 1e-4);
+    assert_relative_eq!(screen.y, 14.0, epsilon = 1e-4);
+    let matrix = crate::nalgebra::Matrix4::<f32>::from(rotated.to_matrix());
+    let transformed = matrix.transform_point(&crate::nalgebra::Point3::new(1.0, 0.0, 0.0));
+    assert_relative_eq!(transformed.x, screen.x, epsilon = 1e-4);
+    assert_relative_eq!(transformed.y, screen.y, epsilon = 1e-4);
+    let world = rotated.screen_to_world(screen);
+    assert_relative_eq!(world.x, 1.0, epsilon = 1e-4);
+    assert_relative_eq!(world.y, 0.0, epsilon = 1e-4);
+
+    let square = tests::rectangle(c, Rect::new(0.0, 0.0, 4.0, 4.0), graphics::WHITE);
+    let draw = |c: &mut Context, camera: &Camera2D| {
+        graphics::clear(c, graphics::BLACK);
+        camera.push(c).unwrap();
+        graphics::draw(c, &square, DrawParam::new()).unwrap();
+        Camera2D::pop(c).unwrap();
+        graphics::present(c).unwrap();
+        tests::read_target(c, None)
+    };
+
+    // The square covers world (0, 0) to (4, 4), so pixels 16 to 24.
+    let rgba = draw(c, &camera);
+    assert_eq!(tests::target_pixel(&rgba, 20, 20), [255, 255, 255, 255]);
+    assert_eq!(tests::target_pixel(&rgba, 12, 12), [0, 0, 0, 255]);
+    assert_eq!(tests::target_pixel(&rgba, 26, 20), [0, 0, 0, 255]);
+
+    // A viewport on the left half of the window centers the world
+    // there and clips to it.
+    // Unclipped, the square would cover pixels -8 to 24.
+    let left = Camera2D::with_viewport(Rect::new(0.0, 0.0, 16.0, 32.0))
+        .position([2.0, 2.0])
+        .zoom(8.0);
+    let rgba = draw(c, &left);
+    assert_eq!(tests::target_pixel(&rgba, 8, 16), [255, 255, 255, 255]);
+    assert_eq!(tests::target_pixel(&rgba, 14, 16), [255, 255, 255, 255]);
+    assert_eq!(tests::target_pixel(&rgba, 20, 16), [0, 0, 0, 255]);
+
+    // Popping the camera puts everything back.
+    assert_eq!(
+        graphics::screen_coordinates(c),
+        Rect::new(0.0, 0.0, 32.0, 32.0)
+    );
+    assert_eq!(graphics::scissor(c), None);
+    let identity = crate::nalgebra::Matrix4::<f32>::identity();
+    assert_eq!(
+        crate::nalgebra::Matrix4::from(graphics::transform(c)),
+        identity
+    );
+}
+
 /// Ensure that the transform stack applies operations in the correct order.
 #[test]
 fn test_transform_stack_order() {
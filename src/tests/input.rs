@@ -0,0 +1,312 @@
+use crate::event::{Button, EventHandler, GamepadId, KeyCode, KeyMods};
+use crate::input::keyboard;
+use crate::input::replay::{self, InputEvent, Recording};
+use crate::tests;
+use crate::*;
+
+/// Logs what it sees so we can check a replay did the right thing.
+#[derive(Default)]
+struct Logger {
+    keys_down: Vec<(usize, KeyCode)>,
+    a_held_on_ticks: Vec<usize>,
+    a_just_pressed_on_ticks: Vec<usize>,
+    updates: usize,
+}
+
+impl EventHandler for Logger {
+    fn update(&mut self, ctx: &mut Context) -> GameResult {
+        self.updates += 1;
+        if keyboard::is_key_pressed(ctx, KeyCode::A) {
+            self.a_held_on_ticks.push(timer::ticks(ctx));
+        }
+        if keyboard::is_key_just_pressed(ctx, KeyCode::A) {
+            self.a_just_pressed_on_ticks.push(timer::ticks(ctx));
+        }
+        Ok(())
+    }
+
+    fn draw(&mut self, _ctx: &mut Context) -> GameResult {
+        Ok(())
+    }
+
+    fn key_down_event(&mut self, ctx: &mut Context, keycode: KeyCode, _: KeyMods, _: bool) {
+        self.keys_down.push((timer::ticks(ctx), keycode));
+    }
+}
+
+#[test]
+fn replay_round_trip() {
+    let c = &mut tests::make_headless_context(32.0, 32.0);
+    let mut recording = Recording::new();
+    let key = |keycode, down| {
+        if down {
+            InputEvent::KeyDown {
+                keycode,
+                keymods: KeyMods::NONE,
+                repeat: false,
+            }
+        } else {
+            InputEvent::KeyUp {
+                keycode,
+                keymods: KeyMods::NONE,
+            }
+        }
+    };
+    recording.push(2, key(KeyCode::A, true));
+    recording.push(4, key(KeyCode::A, false));
+    recording.push(5, key(KeyCode::B, true));
+
+    let path = "/replay_round_trip.toml";
+    recording.save(c, path).unwrap();
+    let loaded = Recording::load(c, path).unwrap();
+    filesystem::delete(c, path).unwrap();
+    assert_eq!(recording, loaded);
+
+    // Replaying records the same events again.
+    replay::start_recording(c);
+    let mut logger = Logger::default();
+    replay::run(c, &loaded, &mut logger).unwrap();
+    let rerecorded = replay::stop_recording(c).unwrap();
+    assert_eq!(rerecorded, recording);
+
+    assert_eq!(logger.updates, 5);
+    assert_eq!(logger.keys_down, vec![(2, KeyCode::A), (5, KeyCode::B)]);
+    assert_eq!(logger.a_held_on_ticks, vec![2, 3]);
+    assert_eq!(logger.a_just_pressed_on_ticks, vec![2]);
+}
+
+#[test]
+fn bindings_read_keyboard_state() {
+    use crate::input::bindings::{AxisInput, Input, InputBindings};
+
+    let c = &mut tests::make_headless_context(32.0, 32.0);
+    let bindings = InputBindings::new()
+        .bind_action("save", Input::key_with_mods(KeyCode::S, KeyMods::CTRL))
+        .bind_axis(
+            "walk",
+            AxisInput::Keys {
+                negative: KeyCode::Left,
+                positive: KeyCode::Right,
+            },
+        );
+
+    c.keyboard_context.set_key(KeyCode::S, true);
+    assert!(!bindings.is_action_pressed(c, "save"));
+    c.keyboard_context.set_key(KeyCode::LControl, true);
+    assert!(bindings.is_action_pressed(c, "save"));
+    assert!(!bindings.is_action_pressed(c, "no such action"));
+
+    assert_eq!(bindings.axis_value(c, "walk") as i32, 0);
+    c.keyboard_context.set_key(KeyCode::Left, true);
+    assert_eq!(bindings.axis_value(c, "walk") as i32, -1);
+    c.keyboard_context.set_key(KeyCode::Right, true);
+    assert_eq!(bindings.axis_value(c, "walk") as i32, 0);
+}
+
+/// Presses a button on the scripted gamepad and checks what
+/// the `EventHandler` sees on each frame.
+struct GamepadChecker {
+    script: input::gamepad::ScriptedGamepadContext,
+    id: GamepadId,
+    connected: Vec<GamepadId>,
+    just_pressed_on_ticks: Vec<usize>,
+    pressed_on_ticks: Vec<usize>,
+}
+
+impl EventHandler for GamepadChecker {
+    fn update(&mut self, ctx: &mut Context) -> GameResult {
+        use crate::input::gamepad;
+
+        let tick = timer::ticks(ctx);
+        if gamepad::button_just_pressed(ctx, self.id, Button::South) {
+            self.just_pressed_on_ticks.push(tick);
+        }
+        if gamepad::button_pressed(ctx, self.id, Button::South) {
+            self.pressed_on_ticks.push(tick);
+        }
+        match tick {
+            2 => self.script.press_button(self.id, Button::South),
+            3 => self.script.release_button(self.id, Button::South),
+            4 => event::quit(ctx),
+            _ => (),
+        }
+        Ok(())
+    }
+
+    fn draw(&mut self, _ctx: &mut Context) -> GameResult {
+        Ok(())
+    }
+
+    fn gamepad_connected_event(&mut self, _ctx: &mut Context, id: GamepadId) {
+        self.connected.push(id);
+    }
+}
+
+#[test]
+fn scripted_gamepad_events() {
+    use crate::input::gamepad::{self, ScriptedGamepadContext};
+
+    let c = &mut tests::make_headless_context(32.0, 32.0);
+    let script = ScriptedGamepadContext::new();
+    c.gamepad_context = Box::new(script.clone());
+    let id = script.connect("Test Pad");
+    assert_eq!(gamepad::list_gamepads(c)[0].name, "Test Pad");
+
+    let mut checker = GamepadChecker {
+        script,
+        id,
+        connected: vec![],
+        just_pressed_on_ticks: vec![],
+        pressed_on_ticks: vec![],
+    };
+    event::run_headless(c, &mut checker).unwrap();
+    assert_eq!(checker.connected, vec![id]);
+    // Pressed during frame 2's update, so the event arrives on frame 3.
+    assert_eq!(checker.just_pressed_on_ticks, vec![3]);
+    assert_eq!(checker.pressed_on_ticks, vec![3]);
+}
+
+/// Logs the gamepad state on each frame of a replay.
+#[derive(Default)]
+struct GamepadLogger {
+    gamepads: Vec<usize>,
+    axis: Vec<f32>,
+    pressed_on_ticks: Vec<usize>,
+}
+
+impl EventHandler for GamepadLogger {
+    fn update(&mut self, ctx: &mut Context) -> GameResult {
+        use crate::event::Axis;
+        use crate::input::gamepad;
+
+        let id = GamepadId(0);
+        self.gamepads.push(gamepad::list_gamepads(ctx).len());
+        self.axis.push(gamepad::axis(ctx, id, Axis::LeftStickX));
+        if gamepad::button_pressed(ctx, id, Button::South) {
+            self.pressed_on_ticks.push(timer::ticks(ctx));
+        }
+        Ok(())
+    }
+
+    fn draw(&mut self, _ctx: &mut Context) -> GameResult {
+        Ok(())
+    }
+}
+
+#[test]
+fn replay_restores_gamepad_state() {
+    use crate::event::Axis;
+    use crate::input::gamepad::{self, ScriptedGamepadContext};
+
+    let c = &mut tests::make_headless_context(32.0, 32.0);
+    let script = ScriptedGamepadContext::new();
+    c.gamepad_context = Box::new(script.clone());
+    let _ = script.connect("Real pad");
+
+    let id = GamepadId(0);
+    let mut recording = Recording::new();
+    recording.push(1, InputEvent::GamepadConnected { id });
+    recording.push(
+        2,
+        InputEvent::GamepadAxis {
+            axis: Axis::LeftStickX,
+            value: 0.5,
+            id,
+        },
+    );
+    recording.push(
+        2,
+        InputEvent::GamepadButtonDown {
+            button: Button::South,
+            id,
+        },
+    );
+    recording.push(
+        3,
+        InputEvent::GamepadButtonUp {
+            button: Button::South,
+            id,
+        },
+    );
+    recording.push(4, InputEvent::GamepadDisconnected { id });
+
+    let mut logger = GamepadLogger::default();
+    replay::run(c, &recording, &mut logger).unwrap();
+    assert_eq!(logger.gamepads, vec![1, 1, 1, 0]);
+    assert_eq!(logger.axis, vec![0.0, 0.5, 0.5, 0.0]);
+    assert_eq!(logger.pressed_on_ticks, vec![2]);
+
+    // The real gamepads are back afterwards.
+    assert_eq!(gamepad::list_gamepads(c)[0].name, "Real pad");
+}
+
+#[test]
+fn recording_survives_the_clock_starting_over() {
+    let c = &mut tests::make_headless_context(32.0, 32.0);
+    let event = InputEvent::Focus { gained: true };
+    for _ in 0..5 {
+        c.timer_context.tick();
+    }
+    replay::start_recording(c);
+    c.timer_context.tick();
+    event::dispatch_event(c, &mut Logger::default(), event);
+    timer::set_clock(
+        c,
+        timer::FixedClock::new(std::time::Duration::from_millis(10)),
+    );
+    c.timer_context.tick();
+    event::dispatch_event(c, &mut Logger::default(), event);
+    c.timer_context.tick();
+    event::dispatch_event(c, &mut Logger::default(), event);
+    let recording = replay::stop_recording(c).unwrap();
+    let ticks: Vec<_> = recording.events.iter().map(|e| e.tick).collect();
+    assert_eq!(ticks, vec![1, 2, 3]);
+
+    // Including when recording starts on the very first frame.
+    let clock = || timer::FixedClock::new(std::time::Duration::from_millis(10));
+    timer::set_clock(c, clock());
+    replay::start_recording(c);
+    c.timer_context.tick();
+    event::dispatch_event(c, &mut Logger::default(), event);
+    timer::set_clock(c, clock());
+    c.timer_context.tick();
+    event::dispatch_event(c, &mut Logger::default(), event);
+    let recording = replay::stop_recording(c).unwrap();
+    let ticks: Vec<_> = recording.events.iter().map(|e| e.tick).collect();
+    assert_eq!(ticks, vec![1, 2]);
+}
+
+#[test]
+fn custom_loops_see_gamepad_transitions() {
+    use crate::input::gamepad::{self, ScriptedGamepadContext};
+
+    let c = &mut tests::make_headless_context(32.0, 32.0);
+    let script = ScriptedGamepadContext::new();
+    c.gamepad_context = Box::new(script.clone());
+    let id = script.connect("Test Pad");
+    script.press_button(id, Button::South);
+
+    input::reset_transitions(c);
+    while let Some(event) = c.gamepad_context.next_event() {
+        c.process_gamepad_event(&event);
+    }
+    assert!(gamepad::button_just_pressed(c, id, Button::South));
+}
+
+#[test]
+fn replay_moves_audio_along() {
+    use crate::audio::{self, SoundSource};
+    use std::time::Duration;
+
+    let c = &mut tests::make_headless_context(32.0, 32.0);
+    c.audio_context = Box::new(audio::OfflineAudioContext::new(2, 44100));
+    timer::set_clock(c, timer::FixedClock::new(Duration::from_millis(100)));
+    let mut sound = audio::Source::new(c, "/pew.wav").unwrap();
+    sound.play().unwrap();
+
+    let mut recording = Recording::new();
+    recording.push(5, InputEvent::Focus { gained: true });
+    replay::run(c, &recording, &mut Logger::default()).unwrap();
+    // Half a second of frames is longer than the sound.
+    assert!(sound.stopped());
+}
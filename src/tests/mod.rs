@@ -4,20 +4,104 @@ use crate::*;
 use std::env;
 use std::path;
 
+mod assets;
 mod audio;
 mod conf;
 mod filesystem;
 mod graphics;
+mod input;
 mod mesh;
 mod text;
 
 /// Make a basic `Context` with sane defaults.
 pub fn make_context() -> (Context, event::EventsLoop) {
+    make_context_builder().build().unwrap()
+}
+
+/// Make a headless `Context` that draws to an offscreen target
+/// of the given size.
+pub fn make_headless_context(width: f32, height: f32) -> Context {
+    make_context_builder()
+        .window_mode(conf::WindowMode::default().dimensions(width, height))
+        .build_headless()
+        .unwrap()
+}
+
+fn make_context_builder() -> ContextBuilder {
     let mut cb = ContextBuilder::new("ggez_unit_tests", "ggez");
     if let Ok(manifest_dir) = env::var("CARGO_MANIFEST_DIR") {
         let mut path = path::PathBuf::from(manifest_dir);
         path.push("resources");
         cb = cb.add_resource_path(path);
     }
-    cb.build().unwrap()
+    cb
+}
+
+// Helpers for the graphics tests, which draw into a 32x32 headless
+// context and check the pixels that come out.
+
+gfx_defines! {
+    constant Unused {
+        unused: f32 = "u_Unused",
+    }
+}
+
+/// The vertex shader ggez uses by default, for test shaders that only
+/// change the pixel shader.
+pub const BASIC_VERTEX_SHADER: &[u8] = include_bytes!("../graphics/shader/basic_150.glslv");
+
+/// Make a headless `Context` that draws to a 32x32 target, the size
+/// [`target_pixel()`](fn.target_pixel.html) expects.
+pub fn make_target_context() -> Context {
+    make_headless_context(32.0, 32.0)
+}
+
+/// Make a filled rectangle `Mesh`.
+pub fn rectangle(c: &mut Context, rect: graphics::Rect, color: graphics::Color) -> graphics::Mesh {
+    graphics::Mesh::new_rectangle(c, graphics::DrawMode::fill(), rect, color).unwrap()
+}
+
+/// Make a `Shader` with no constants to speak of from the given sources.
+pub fn unused_shader(c: &mut Context, vertex: &[u8], pixel: &[u8]) -> graphics::Shader<Unused> {
+    graphics::Shader::from_u8(c, vertex, pixel, Unused { unused: 0.0 }, "Unused", None).unwrap()
+}
+
+/// Reads back what has been drawn to the given canvas, or to the
+/// screen for `None`.
+pub fn read_target(c: &mut Context, target: Option<&graphics::Canvas>) -> Vec<u8> {
+    match target {
+        Some(canvas) => canvas.image().to_rgba8(c).unwrap(),
+        None => graphics::screenshot(c).unwrap().to_rgba8(c).unwrap(),
+    }
+}
+
+/// Runs `draw` once with the screen as the target and once with the
+/// given canvas, presenting after each, and returns what each target
+/// ended up with.
+pub fn draw_to_each_target<F>(
+    c: &mut Context,
+    canvas: &graphics::Canvas,
+    mut draw: F,
+) -> Vec<Vec<u8>>
+where
+    F: FnMut(&mut Context),
+{
+    let drawn = [None, Some(canvas)]
+        .iter()
+        .map(|&target| {
+            graphics::set_canvas(c, target);
+            draw(c);
+            graphics::present(c).unwrap();
+            read_target(c, target)
+        })
+        .collect();
+    graphics::set_canvas(c, None);
+    drawn
+}
+
+/// Reads a pixel out of RGBA data read back from a 32x32 render
+/// target.  Rows come back bottom-up, the way OpenGL stores them.
+pub fn target_pixel(rgba: &[u8], x: usize, y: usize) -> Vec<u8> {
+    let i = ((31 - y) * 32 + x) * 4;
+    rgba[i..i + 4].to_vec()
 }
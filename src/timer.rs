@@ -11,6 +11,11 @@
 //! in your [`Conf`](../conf/struct.Conf.html) object is generally the best
 //! way to cap your displayed framerate.
 //!
+//! To run your game logic at a fixed rate, set a
+//! [`LoopMode`](../conf/enum.LoopMode.html) and
+//! [`event::run()`](../event/fn.run.html) will call `update()` for you
+//! at that rate.
+//!
 //! For a more detailed tutorial in how to handle frame timings in games,
 //! see <http://gafferongames.com/game-physics/fix-your-timestep/>
 
@@ -18,6 +23,8 @@ use crate::context::Context;
 
 use std::cmp;
 use std::f64;
+use std::fmt;
+use std::sync::{Arc, Mutex};
 use std::thread;
 use std::time;
 
@@ -86,11 +93,134 @@ where
     }
 }
 
+/// A source of time for a [`TimeContext`](struct.TimeContext.html).
+///
+/// Normally the timer just reads the system clock, but for tests or
+/// replays it's handy to control exactly how much time each frame takes;
+/// see [`set_clock()`](fn.set_clock.html).
+pub trait Clock: fmt::Debug {
+    /// Advances the clock to the start of a new frame and returns the
+    /// time since the clock was created.  Called once per
+    /// [`TimeContext::tick()`](struct.TimeContext.html#method.tick).
+    fn tick(&mut self) -> time::Duration;
+
+    /// Returns the time since the clock was created,
+    /// without advancing it.
+    fn now(&self) -> time::Duration;
+}
+
+/// A `Clock` that reads the system clock.  This is the default.
+#[derive(Debug, Copy, Clone)]
+pub struct RealClock {
+    start: time::Instant,
+}
+
+impl RealClock {
+    /// Creates a new `RealClock` starting at this instant.
+    pub fn new() -> Self {
+        Self {
+            start: time::Instant::now(),
+        }
+    }
+}
+
+impl Default for RealClock {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Clock for RealClock {
+    fn tick(&mut self) -> time::Duration {
+        self.now()
+    }
+
+    fn now(&self) -> time::Duration {
+        self.start.elapsed()
+    }
+}
+
+/// A `Clock` that only moves when you tell it to.
+///
+/// Clones share the same time, so keep a clone around to
+/// [`advance()`](#method.advance) it after handing it to
+/// [`set_clock()`](fn.set_clock.html).
+#[derive(Debug, Clone, Default)]
+pub struct ManualClock {
+    now: Arc<Mutex<time::Duration>>,
+}
+
+impl ManualClock {
+    /// Creates a new `ManualClock` starting at zero.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Moves the clock forward by the given amount.
+    pub fn advance(&self, dt: time::Duration) {
+        let mut now = self.now.lock().expect("ManualClock lock poisoned");
+        *now += dt;
+    }
+
+    /// Sets the time since the clock was created.  Setting it
+    /// to before the last frame makes the next frame take no time.
+    pub fn set(&self, t: time::Duration) {
+        *self.now.lock().expect("ManualClock lock poisoned") = t;
+    }
+}
+
+impl Clock for ManualClock {
+    fn tick(&mut self) -> time::Duration {
+        self.now()
+    }
+
+    fn now(&self) -> time::Duration {
+        *self.now.lock().expect("ManualClock lock poisoned")
+    }
+}
+
+/// A `Clock` where every frame takes exactly the same amount of time,
+/// no matter how long it really took.
+#[derive(Debug, Copy, Clone)]
+pub struct FixedClock {
+    step: time::Duration,
+    now: time::Duration,
+}
+
+impl FixedClock {
+    /// Creates a new `FixedClock` that advances by `step` every frame.
+    pub fn new(step: time::Duration) -> Self {
+        Self {
+            step,
+            now: time::Duration::from_secs(0),
+        }
+    }
+
+    /// Creates a new `FixedClock` that advances by one
+    /// frame at the given framerate every frame.  A framerate
+    /// of 0 is treated as 1.
+    pub fn from_fps(fps: u32) -> Self {
+        Self::new(fps_as_duration(fps.max(1)))
+    }
+}
+
+impl Clock for FixedClock {
+    fn tick(&mut self) -> time::Duration {
+        self.now += self.step;
+        self.now
+    }
+
+    fn now(&self) -> time::Duration {
+        self.now
+    }
+}
+
 /// A structure that contains our time-tracking state.
 #[derive(Debug)]
 pub struct TimeContext {
-    init_instant: time::Instant,
-    last_instant: time::Instant,
+    clock: Box<dyn Clock>,
+    init_time: time::Duration,
+    last_time: time::Duration,
     frame_durations: LogBuffer<time::Duration>,
     residual_update_dt: time::Duration,
     frame_count: usize,
@@ -102,10 +232,21 @@ const TIME_LOG_FRAMES: usize = 200;
 impl TimeContext {
     /// Creates a new `TimeContext` and initializes the start to this instant.
     pub fn new() -> TimeContext {
+        Self::with_clock(RealClock::new())
+    }
+
+    /// Creates a new `TimeContext` that gets its time from the
+    /// given `Clock`, starting at the clock's current time.
+    pub fn with_clock<C>(clock: C) -> TimeContext
+    where
+        C: Clock + 'static,
+    {
         let initial_dt = time::Duration::from_millis(16);
+        let now = clock.now();
         TimeContext {
-            init_instant: time::Instant::now(),
-            last_instant: time::Instant::now(),
+            clock: Box::new(clock),
+            init_time: now,
+            last_time: now,
             frame_durations: LogBuffer::new(TIME_LOG_FRAMES, initial_dt),
             residual_update_dt: time::Duration::from_secs(0),
             frame_count: 0,
@@ -120,14 +261,23 @@ impl TimeContext {
     /// It's usually not necessary to call this function yourself,
     /// [`event::run()`](../event/fn.run.html) will do it for you.
     pub fn tick(&mut self) {
-        let now = time::Instant::now();
-        let time_since_last = now - self.last_instant;
+        let now = self.clock.tick();
+        // A clock that was set back counts as no time passing.
+        let time_since_last = now
+            .checked_sub(self.last_time)
+            .unwrap_or_else(|| time::Duration::from_secs(0));
         self.frame_durations.push(time_since_last);
-        self.last_instant = now;
+        self.last_time = now;
         self.frame_count += 1;
 
         self.residual_update_dt += time_since_last;
     }
+
+    /// Throws away any accumulated update time, for when the game has
+    /// fallen too far behind to ever catch up.
+    pub(crate) fn discard_residual_update_time(&mut self) {
+        self.residual_update_dt = time::Duration::from_secs(0);
+    }
 }
 
 impl Default for TimeContext {
@@ -197,10 +347,33 @@ pub fn fps(ctx: &Context) -> f64 {
 }
 
 /// Returns the time since the game was initialized,
-/// as reported by the system clock.
+/// as reported by the timer's [`Clock`](trait.Clock.html)
+/// (normally the system clock).
 pub fn time_since_start(ctx: &Context) -> time::Duration {
     let tc = &ctx.timer_context;
-    time::Instant::now() - tc.init_instant
+    // A clock that was set back before it was installed counts as
+    // no time passing.
+    tc.clock
+        .now()
+        .checked_sub(tc.init_time)
+        .unwrap_or_else(|| time::Duration::from_secs(0))
+}
+
+/// Replaces the timer's [`Clock`](trait.Clock.html), for instance with a
+/// [`ManualClock`](struct.ManualClock.html) or
+/// [`FixedClock`](struct.FixedClock.html) to make frame timings
+/// deterministic in tests.
+///
+/// This resets all the timer's state, so `time_since_start()`,
+/// `ticks()` and the FPS counter all start over from zero.
+pub fn set_clock<C>(ctx: &mut Context, clock: C)
+where
+    C: Clock + 'static,
+{
+    if let Some(recorder) = ctx.recorder.as_mut() {
+        recorder.clock_reset(ctx.timer_context.frame_count);
+    }
+    ctx.timer_context = TimeContext::with_clock(clock);
 }
 
 /// Check whether or not the desired amount of time has elapsed
@@ -284,3 +457,53 @@ pub fn yield_now() {
 pub fn ticks(ctx: &Context) -> usize {
     ctx.timer_context.frame_count
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fixed_clock_ticks_deterministically() {
+        let mut tc = TimeContext::with_clock(FixedClock::new(time::Duration::from_millis(20)));
+        for _ in 0..3 {
+            tc.tick();
+        }
+        assert_eq!(tc.frame_durations.latest(), time::Duration::from_millis(20));
+        assert_eq!(tc.residual_update_dt, time::Duration::from_millis(60));
+        assert_eq!(
+            tc.clock.now() - tc.init_time,
+            time::Duration::from_millis(60)
+        );
+    }
+
+    #[test]
+    fn manual_clock_only_moves_when_advanced() {
+        let clock = ManualClock::new();
+        let mut tc = TimeContext::with_clock(clock.clone());
+        tc.tick();
+        assert_eq!(tc.frame_durations.latest(), time::Duration::from_secs(0));
+        clock.advance(time::Duration::from_millis(5));
+        tc.tick();
+        assert_eq!(tc.frame_durations.latest(), time::Duration::from_millis(5));
+        assert_eq!(tc.frame_count, 2);
+    }
+
+    #[test]
+    fn manual_clock_set_backwards_takes_no_time() {
+        let clock = ManualClock::new();
+        let mut tc = TimeContext::with_clock(clock.clone());
+        clock.set(time::Duration::from_millis(50));
+        tc.tick();
+        clock.set(time::Duration::from_millis(10));
+        tc.tick();
+        assert_eq!(tc.frame_durations.latest(), time::Duration::from_secs(0));
+        assert_eq!(tc.residual_update_dt, time::Duration::from_millis(50));
+    }
+
+    #[test]
+    fn fixed_clock_from_zero_fps() {
+        let mut clock = FixedClock::from_fps(0);
+        let _ = clock.tick();
+        assert_eq!(clock.tick(), time::Duration::from_secs(2));
+    }
+}
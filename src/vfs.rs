@@ -15,6 +15,7 @@ use std::fmt::{self, Debug};
 use std::fs;
 use std::io::{self, Read, Seek, Write};
 use std::path::{self, Path, PathBuf};
+use std::time::SystemTime;
 
 use zip;
 
@@ -136,6 +137,15 @@ pub trait VFS: Debug {
 
     /// Retrieve the actual location of the VFS root, if available.
     fn to_path_buf(&self) -> Option<PathBuf>;
+
+    /// Retrieve the actual location of the given file, if it exists
+    /// and is a real file on disk that can be opened without the VFS.
+    fn physical_path(&self, path: &Path) -> Option<PathBuf>;
+
+    /// Retrieve where the given file's bytes are on disk, if it exists
+    /// and they can be read straight from there, so the file can be
+    /// reopened without the VFS.
+    fn locate(&self, path: &Path) -> Option<FileLocation>;
 }
 
 pub trait VMetadata {
@@ -148,6 +158,9 @@ pub trait VMetadata {
     /// Returns the length of the thing.  If it is a directory,
     /// the result of this is undefined/platform dependent.
     fn len(&self) -> u64;
+    /// Returns when the thing was last modified, if the
+    /// underlying filesystem knows.
+    fn modified(&self) -> Option<SystemTime>;
 }
 
 /// A VFS that points to a directory and uses it as the root of its
@@ -174,6 +187,9 @@ impl VMetadata for PhysicalMetadata {
     fn len(&self) -> u64 {
         self.0.len()
     }
+    fn modified(&self) -> Option<SystemTime> {
+        self.0.modified().ok()
+    }
 }
 
 /// This takes an absolute path and returns either a sanitized relative
@@ -386,6 +402,14 @@ impl VFS for PhysicalFS {
     fn to_path_buf(&self) -> Option<PathBuf> {
         Some(self.root.clone())
     }
+
+    fn physical_path(&self, path: &Path) -> Option<PathBuf> {
+        self.to_absolute(path).ok().filter(|p| p.is_file())
+    }
+
+    fn locate(&self, path: &Path) -> Option<FileLocation> {
+        self.physical_path(path).map(FileLocation::Physical)
+    }
 }
 
 /// A structure that joins several VFS's together in order.
@@ -524,6 +548,22 @@ impl VFS for OverlayFS {
     fn to_path_buf(&self) -> Option<PathBuf> {
         None
     }
+
+    /// Only looks in the first VFS that has the file, since
+    /// that's the one it would be opened from.
+    fn physical_path(&self, path: &Path) -> Option<PathBuf> {
+        self.roots
+            .iter()
+            .find(|vfs| vfs.exists(path))
+            .and_then(|vfs| vfs.physical_path(path))
+    }
+
+    fn locate(&self, path: &Path) -> Option<FileLocation> {
+        self.roots
+            .iter()
+            .find(|vfs| vfs.exists(path))
+            .and_then(|vfs| vfs.locate(path))
+    }
 }
 
 trait ZipArchiveAccess {
@@ -665,6 +705,97 @@ impl Debug for ZipFileWrapper {
     }
 }
 
+/// Where the bytes of a file in a VFS are on disk.
+#[derive(Debug, Clone, PartialEq)]
+pub enum FileLocation {
+    /// A whole file.
+    Physical(PathBuf),
+    /// Part of a file, such as an uncompressed file in a zip archive.
+    Span { path: PathBuf, start: u64, len: u64 },
+}
+
+impl FileLocation {
+    /// Opens the file for reading.  The result is `Send`, unlike a file
+    /// from the VFS, so it can be read on another thread.
+    pub fn open(&self) -> io::Result<SpanFile> {
+        match *self {
+            FileLocation::Physical(ref path) => {
+                let file = fs::File::open(path)?;
+                let len = file.metadata()?.len();
+                Ok(SpanFile::new(file, 0, len))
+            }
+            FileLocation::Span {
+                ref path,
+                start,
+                len,
+            } => {
+                let mut file = fs::File::open(path)?;
+                let _ = file.seek(io::SeekFrom::Start(start))?;
+                Ok(SpanFile::new(file, start, len))
+            }
+        }
+    }
+}
+
+/// A read-only view of part of a file, which seeks relative to the
+/// start of that part and ends where it does.
+#[derive(Debug)]
+pub struct SpanFile {
+    file: fs::File,
+    start: u64,
+    len: u64,
+    pos: u64,
+}
+
+impl SpanFile {
+    /// Expects `file` to already be at `start`.
+    fn new(file: fs::File, start: u64, len: u64) -> Self {
+        Self {
+            file,
+            start,
+            len,
+            pos: 0,
+        }
+    }
+}
+
+impl io::Read for SpanFile {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let remaining = self.len.saturating_sub(self.pos);
+        let max = buf.len().min(remaining as usize);
+        let read = self.file.read(&mut buf[..max])?;
+        self.pos += read as u64;
+        Ok(read)
+    }
+}
+
+impl io::Seek for SpanFile {
+    fn seek(&mut self, pos: io::SeekFrom) -> io::Result<u64> {
+        let target = match pos {
+            io::SeekFrom::Start(offset) => Some(offset),
+            io::SeekFrom::End(offset) => add_offset(self.len, offset),
+            io::SeekFrom::Current(offset) => add_offset(self.pos, offset),
+        };
+        let target = target.ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "invalid seek to a negative or overflowing position",
+            )
+        })?;
+        let _ = self.file.seek(io::SeekFrom::Start(self.start + target))?;
+        self.pos = target;
+        Ok(target)
+    }
+}
+
+fn add_offset(base: u64, offset: i64) -> Option<u64> {
+    if offset < 0 {
+        base.checked_sub(offset.wrapping_neg() as u64)
+    } else {
+        base.checked_add(offset as u64)
+    }
+}
+
 #[derive(Debug, Copy, Clone, PartialEq)]
 struct ZipMetadata {
     len: u64,
@@ -704,6 +835,10 @@ impl VMetadata for ZipMetadata {
     fn len(&self) -> u64 {
         self.len
     }
+    /// Zip files never change under us, so we don't bother.
+    fn modified(&self) -> Option<SystemTime> {
+        None
+    }
 }
 
 impl VFS for ZipFS {
@@ -791,11 +926,33 @@ impl VFS for ZipFS {
     fn to_path_buf(&self) -> Option<PathBuf> {
         self.source.clone()
     }
+
+    fn physical_path(&self, _path: &Path) -> Option<PathBuf> {
+        None
+    }
+
+    /// Only files that are stored in the zip without compression can be
+    /// read straight from it, and only if it is a zip file on disk.
+    fn locate(&self, path: &Path) -> Option<FileLocation> {
+        let source = self.source.as_ref()?;
+        let name = convenient_path_to_str(path).ok()?;
+        let mut archive = self.archive.try_borrow_mut().ok()?;
+        let file = archive.by_name(name).ok()?;
+        if file.compression() != zip::CompressionMethod::Stored {
+            return None;
+        }
+        Some(FileLocation::Span {
+            path: source.clone(),
+            start: file.data_start(),
+            len: file.size(),
+        })
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use std::env;
     use std::io::{self, BufRead};
 
     #[test]
@@ -961,5 +1118,36 @@ mod tests {
         assert_eq!(contents, "Zip contents!");
     }
 
+    #[test]
+    fn headless_test_locate_stored_zip_file() {
+        let zip_path = env::temp_dir().join("ggez_test_locate_stored_zip_file.zip");
+        {
+            let mut zip_archive = zip::ZipWriter::new(fs::File::create(&zip_path).unwrap());
+            let options = zip::write::FileOptions::default()
+                .compression_method(zip::CompressionMethod::Stored);
+            zip_archive.start_file("first.txt", options).unwrap();
+            let _bytes = zip_archive.write(b"Padding").unwrap();
+            zip_archive.start_file("second.txt", options).unwrap();
+            let _bytes = zip_archive.write(b"Zip contents!").unwrap();
+            let _file = zip_archive.finish().unwrap();
+        }
+        let zfs = ZipFS::new(&zip_path).unwrap();
+        let location = zfs.locate(Path::new("second.txt")).unwrap();
+        assert!(zfs.locate(Path::new("missing.txt")).is_none());
+
+        let mut file = location.open().unwrap();
+        let mut contents = String::new();
+        let _bytes = file.read_to_string(&mut contents).unwrap();
+        assert_eq!(contents, "Zip contents!");
+
+        assert_eq!(file.seek(io::SeekFrom::End(-9)).unwrap(), 4);
+        contents.clear();
+        let _bytes = file.read_to_string(&mut contents).unwrap();
+        assert_eq!(contents, "contents!");
+        assert!(file.seek(io::SeekFrom::Current(-14)).is_err());
+
+        fs::remove_file(&zip_path).unwrap();
+    }
+
     // BUGGO: TODO: Make sure all functions are tested for OverlayFS and ZipFS!!
 }